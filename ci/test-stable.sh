@@ -67,6 +67,9 @@ test-stable)
   else
     _ ci/intercept.sh cargo test --jobs "$JOBS" --all --tests --exclude solana-local-cluster ${V:+--verbose} -- --nocapture
   fi
+  # the instrumented proof program is built separately so that its feature does not unify into
+  # the workspace tests
+  _ ci/intercept.sh cargo test --jobs "$JOBS" -p solana-zk-token-proof-program-tests --features instrumented --test instrumented ${V:+--verbose} -- --nocapture
   ;;
 test-stable-sbf)
   # Clear the C dependency files, if dependency moves these files are not regenerated
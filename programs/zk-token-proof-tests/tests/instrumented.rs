@@ -0,0 +1,97 @@
+//! Tests of the per-phase timings that the proof program logs when built with its
+//! `instrumented` feature, which is only enabled by running
+//! `cargo test -p solana-zk-token-proof-program-tests --features instrumented`.
+
+use {
+    solana_program_test::*,
+    solana_sdk::{
+        signature::Signer, signer::keypair::Keypair, system_instruction, transaction::Transaction,
+    },
+    solana_zk_token_sdk::{
+        encryption::elgamal::ElGamalKeypair, instruction::*, zk_token_proof_instruction::*,
+        zk_token_proof_program, zk_token_proof_state::ProofContextState,
+    },
+    std::mem::size_of,
+};
+
+#[tokio::test]
+async fn test_verify_proof_timings() {
+    let source_keypair = ElGamalKeypair::new_rand();
+    let dest_pubkey = ElGamalKeypair::new_rand().public;
+    let auditor_pubkey = ElGamalKeypair::new_rand().public;
+
+    let spendable_balance: u64 = 0;
+    let spendable_ciphertext = source_keypair.public.encrypt(spendable_balance);
+
+    let proof_data = TransferData::new(
+        0,
+        (spendable_balance, &spendable_ciphertext),
+        &source_keypair,
+        (&dest_pubkey, &auditor_pubkey),
+    )
+    .unwrap();
+
+    let mut context = ProgramTest::default().start_with_context().await;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    let context_state_account = Keypair::new();
+    let context_state_authority = Keypair::new();
+    let space = size_of::<ProofContextState<TransferProofContext>>();
+
+    let instructions = vec![
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &context_state_account.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &zk_token_proof_program::id(),
+        ),
+        verify_transfer(
+            Some(ContextStateInfo {
+                context_state_account: &context_state_account.pubkey(),
+                context_state_authority: &context_state_authority.pubkey(),
+            }),
+            &proof_data,
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &context_state_account],
+        recent_blockhash,
+    );
+    let result = client
+        .process_transaction_with_metadata(transaction)
+        .await
+        .unwrap();
+    assert_eq!(result.result, Ok(()));
+
+    let log_messages = result.metadata.unwrap().log_messages;
+    let timings = log_messages
+        .iter()
+        .find_map(|log| log.strip_prefix("proof timings: "))
+        .unwrap();
+    let phases: Vec<(&str, u64)> = timings
+        .split(", ")
+        .map(|phase| {
+            let (name, time) = phase.split_once(' ').unwrap();
+            (name, time.strip_suffix("ns").unwrap().parse().unwrap())
+        })
+        .collect();
+
+    assert_eq!(
+        phases.iter().map(|(name, _)| *name).collect::<Vec<_>>(),
+        vec!["parse", "verify", "encode"]
+    );
+
+    // proof verification dominates the cost of parsing and encoding
+    let (_, parse_time) = phases[0];
+    let (_, verify_time) = phases[1];
+    let (_, encode_time) = phases[2];
+    assert!(verify_time > parse_time);
+    assert!(verify_time > encode_time);
+}
@@ -1,27 +1,68 @@
 use {
-    bytemuck::Pod,
+    bytemuck::{bytes_of, Pod},
+    rand::thread_rng,
     solana_program_test::*,
     solana_sdk::{
-        instruction::InstructionError,
+        account::Account,
+        account_info::AccountInfo,
+        clock::Clock,
+        compute_budget::ComputeBudgetInstruction,
+        ed25519_instruction::new_ed25519_instruction,
+        entrypoint::ProgramResult,
+        hash::hash,
+        instruction::{AccountMeta, Instruction, InstructionError},
+        program::invoke,
+        pubkey::Pubkey,
         signature::Signer,
         signer::keypair::Keypair,
-        system_instruction,
+        system_instruction, system_program, sysvar,
         transaction::{Transaction, TransactionError},
     },
     solana_zk_token_sdk::{
-        encryption::elgamal::ElGamalKeypair, instruction::*, zk_token_proof_instruction::*,
-        zk_token_proof_program, zk_token_proof_state::ProofContextState,
+        encryption::{
+            elgamal::{ElGamalCiphertext, ElGamalKeypair, ElGamalPubkey},
+            pedersen::{Pedersen, PedersenOpening},
+        },
+        instruction::*,
+        test_vectors::*,
+        zk_token_elgamal::pod,
+        zk_token_proof_event::{
+            ProofVerified, VerifyTelemetry, PROOF_VERIFIED_EVENT_VERSION, VERIFY_TELEMETRY_VERSION,
+        },
+        zk_token_proof_instruction::*,
+        zk_token_proof_program,
+        zk_token_proof_state::{
+            context_state_policy_hash, find_policy_account_address,
+            find_program_managed_context_address, verification_receipt, AuthorityAllowlist,
+            ContextStateHeader, ExtendedProofContextStateMeta, KeyBlacklist, NullifierSet,
+            PolicyAccountHeader, ProofCommitment, ProofContextState, ProofContextStateMeta,
+            ProofReference, RateLimitConfig, RateLimitState, RetryNonceTracker,
+            SecurityLevelConfig, SlotWindows, Token2022ProofContextState, UsageCounter,
+            AUTHORITY_ALLOWLIST_DISCRIMINATOR, KEY_BLACKLIST_DISCRIMINATOR,
+            NULLIFIER_SET_DISCRIMINATOR, PROOF_CONTEXT_STATE_FLAG_COMPRESSED,
+            PROOF_CONTEXT_STATE_POLICY_AUTHORITY_ALLOWLIST, PROOF_CONTEXT_STATE_VERSION_DEFAULT,
+            PROOF_CONTEXT_STATE_VERSION_EXTENDED, RATE_LIMIT_CONFIG_DISCRIMINATOR,
+            RATE_LIMIT_STATE_DISCRIMINATOR, RETRY_NONCE_TRACKER_DISCRIMINATOR,
+            SECURITY_LEVEL_CONFIG_DISCRIMINATOR, SECURITY_LEVEL_FULL_RANGE,
+            SLOT_WINDOWS_DISCRIMINATOR, USAGE_COUNTER_DISCRIMINATOR,
+        },
     },
-    std::mem::size_of,
+    std::{mem::size_of, str::FromStr},
 };
 
-const VERIFY_INSTRUCTION_TYPES: [ProofInstruction; 6] = [
+const VERIFY_INSTRUCTION_TYPES: [ProofInstruction; 12] = [
     ProofInstruction::VerifyCloseAccount,
     ProofInstruction::VerifyWithdraw,
     ProofInstruction::VerifyWithdrawWithheldTokens,
     ProofInstruction::VerifyTransfer,
     ProofInstruction::VerifyTransferWithFee,
     ProofInstruction::VerifyPubkeyValidity,
+    ProofInstruction::VerifyTransferSum,
+    ProofInstruction::VerifyMaxValue,
+    ProofInstruction::VerifyParity,
+    ProofInstruction::VerifyGreaterThan,
+    ProofInstruction::VerifyKeyPossession,
+    ProofInstruction::VerifyCommitmentEquality,
 ];
 
 #[tokio::test]
@@ -223,99 +264,4676 @@ async fn test_transfer_with_fee() {
     )
     .await;
 
-    test_close_context_state(
-        ProofInstruction::VerifyTransferWithFee,
-        size_of::<ProofContextState<TransferWithFeeProofContext>>(),
-        &success_proof_data,
-    )
-    .await;
+    test_close_context_state(
+        ProofInstruction::VerifyTransferWithFee,
+        size_of::<ProofContextState<TransferWithFeeProofContext>>(),
+        &success_proof_data,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_withdraw() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+
+    let current_balance: u64 = 77;
+    let current_ciphertext = elgamal_keypair.public.encrypt(current_balance);
+    let withdraw_amount: u64 = 55;
+
+    let success_proof_data = WithdrawData::new(
+        withdraw_amount,
+        &elgamal_keypair,
+        current_balance,
+        &current_ciphertext,
+    )
+    .unwrap();
+
+    let incorrect_keypair = ElGamalKeypair {
+        public: ElGamalKeypair::new_rand().public,
+        secret: ElGamalKeypair::new_rand().secret,
+    };
+    let fail_proof_data = WithdrawData::new(
+        withdraw_amount,
+        &incorrect_keypair,
+        current_balance,
+        &current_ciphertext,
+    )
+    .unwrap();
+
+    test_verify_proof_without_context(
+        ProofInstruction::VerifyWithdraw,
+        &success_proof_data,
+        &fail_proof_data,
+    )
+    .await;
+
+    test_verify_proof_with_context(
+        ProofInstruction::VerifyWithdraw,
+        size_of::<ProofContextState<WithdrawProofContext>>(),
+        &success_proof_data,
+        &fail_proof_data,
+    )
+    .await;
+
+    test_close_context_state(
+        ProofInstruction::VerifyWithdraw,
+        size_of::<ProofContextState<WithdrawProofContext>>(),
+        &success_proof_data,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_pubkey_validity() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+
+    let success_proof_data = PubkeyValidityData::new(&elgamal_keypair).unwrap();
+
+    let incorrect_keypair = ElGamalKeypair {
+        public: ElGamalKeypair::new_rand().public,
+        secret: ElGamalKeypair::new_rand().secret,
+    };
+
+    let fail_proof_data = PubkeyValidityData::new(&incorrect_keypair).unwrap();
+
+    test_verify_proof_without_context(
+        ProofInstruction::VerifyPubkeyValidity,
+        &success_proof_data,
+        &fail_proof_data,
+    )
+    .await;
+
+    test_verify_proof_with_context(
+        ProofInstruction::VerifyPubkeyValidity,
+        size_of::<ProofContextState<PubkeyValidityProofContext>>(),
+        &success_proof_data,
+        &fail_proof_data,
+    )
+    .await;
+
+    test_close_context_state(
+        ProofInstruction::VerifyPubkeyValidity,
+        size_of::<ProofContextState<PubkeyValidityProofContext>>(),
+        &success_proof_data,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_transfer_sum() {
+    let source_keypair = ElGamalKeypair::new_rand();
+    let first_destination_pubkey = ElGamalKeypair::new_rand().public;
+    let second_destination_pubkey = ElGamalKeypair::new_rand().public;
+    let auditor_pubkey = ElGamalKeypair::new_rand().public;
+
+    let spendable_balance: u64 = 120;
+    let spendable_ciphertext = source_keypair.public.encrypt(spendable_balance);
+
+    let success_proof_data = TransferSumData::new(
+        (30, 55),
+        85,
+        (spendable_balance, &spendable_ciphertext),
+        &source_keypair,
+        (&first_destination_pubkey, &second_destination_pubkey),
+        &auditor_pubkey,
+    )
+    .unwrap();
+
+    let fail_proof_data = TransferSumData::new(
+        (30, 55),
+        86,
+        (spendable_balance, &spendable_ciphertext),
+        &source_keypair,
+        (&first_destination_pubkey, &second_destination_pubkey),
+        &auditor_pubkey,
+    )
+    .unwrap();
+
+    test_verify_proof_without_context(
+        ProofInstruction::VerifyTransferSum,
+        &success_proof_data,
+        &fail_proof_data,
+    )
+    .await;
+
+    test_verify_proof_with_context(
+        ProofInstruction::VerifyTransferSum,
+        size_of::<ProofContextState<TransferSumProofContext>>(),
+        &success_proof_data,
+        &fail_proof_data,
+    )
+    .await;
+
+    test_close_context_state(
+        ProofInstruction::VerifyTransferSum,
+        size_of::<ProofContextState<TransferSumProofContext>>(),
+        &success_proof_data,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_max_value() {
+    let opening = PedersenOpening::new_rand();
+
+    let success_proof_data = MaxValueData::new(55, &opening, 100).unwrap();
+
+    // a proof for a value over the claimed maximum value
+    let mut fail_proof_data = MaxValueData::new(55, &opening, 100).unwrap();
+    fail_proof_data.context.max_value = 50_u64.into();
+
+    test_verify_proof_without_context(
+        ProofInstruction::VerifyMaxValue,
+        &success_proof_data,
+        &fail_proof_data,
+    )
+    .await;
+
+    test_verify_proof_with_context(
+        ProofInstruction::VerifyMaxValue,
+        size_of::<ProofContextState<MaxValueProofContext>>(),
+        &success_proof_data,
+        &fail_proof_data,
+    )
+    .await;
+
+    test_close_context_state(
+        ProofInstruction::VerifyMaxValue,
+        size_of::<ProofContextState<MaxValueProofContext>>(),
+        &success_proof_data,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_parity() {
+    let opening = PedersenOpening::new_rand();
+
+    for value in [54, 55] {
+        let success_proof_data = ParityData::new(value, &opening).unwrap();
+
+        // a proof claiming the opposite parity
+        let mut fail_proof_data = ParityData::new(value, &opening).unwrap();
+        fail_proof_data.context.parity ^= 1;
+
+        test_verify_proof_without_context(
+            ProofInstruction::VerifyParity,
+            &success_proof_data,
+            &fail_proof_data,
+        )
+        .await;
+
+        test_verify_proof_with_context(
+            ProofInstruction::VerifyParity,
+            size_of::<ProofContextState<ParityProofContext>>(),
+            &success_proof_data,
+            &fail_proof_data,
+        )
+        .await;
+
+        test_close_context_state(
+            ProofInstruction::VerifyParity,
+            size_of::<ProofContextState<ParityProofContext>>(),
+            &success_proof_data,
+        )
+        .await;
+    }
+}
+
+#[tokio::test]
+async fn test_greater_than() {
+    let opening = PedersenOpening::new_rand();
+    let threshold_opening = PedersenOpening::new_rand();
+
+    let success_proof_data = GreaterThanData::new(55, &opening, 54, &threshold_opening).unwrap();
+
+    // a proof against a threshold that the value does not exceed
+    let mut fail_proof_data = GreaterThanData::new(55, &opening, 54, &threshold_opening).unwrap();
+    fail_proof_data.context.threshold_commitment =
+        Pedersen::with(55_u64, &threshold_opening).into();
+
+    test_verify_proof_without_context(
+        ProofInstruction::VerifyGreaterThan,
+        &success_proof_data,
+        &fail_proof_data,
+    )
+    .await;
+
+    test_verify_proof_with_context(
+        ProofInstruction::VerifyGreaterThan,
+        size_of::<ProofContextState<GreaterThanProofContext>>(),
+        &success_proof_data,
+        &fail_proof_data,
+    )
+    .await;
+
+    test_close_context_state(
+        ProofInstruction::VerifyGreaterThan,
+        size_of::<ProofContextState<GreaterThanProofContext>>(),
+        &success_proof_data,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_key_possession() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let challenge = [7; 32];
+
+    let success_proof_data = KeyPossessionData::new(&elgamal_keypair, &challenge).unwrap();
+
+    // a forged proof for a public key whose secret key the prover does not know
+    let incorrect_keypair = ElGamalKeypair {
+        public: ElGamalKeypair::new_rand().public,
+        secret: ElGamalKeypair::new_rand().secret,
+    };
+    let fail_proof_data = KeyPossessionData::new(&incorrect_keypair, &challenge).unwrap();
+
+    test_verify_proof_without_context(
+        ProofInstruction::VerifyKeyPossession,
+        &success_proof_data,
+        &fail_proof_data,
+    )
+    .await;
+
+    // a valid proof replayed under a different challenge
+    let mut replayed_proof_data = success_proof_data;
+    replayed_proof_data.context.challenge = [8; 32];
+
+    test_verify_proof_without_context(
+        ProofInstruction::VerifyKeyPossession,
+        &success_proof_data,
+        &replayed_proof_data,
+    )
+    .await;
+
+    test_verify_proof_with_context(
+        ProofInstruction::VerifyKeyPossession,
+        size_of::<ProofContextState<KeyPossessionProofContext>>(),
+        &success_proof_data,
+        &fail_proof_data,
+    )
+    .await;
+
+    test_close_context_state(
+        ProofInstruction::VerifyKeyPossession,
+        size_of::<ProofContextState<KeyPossessionProofContext>>(),
+        &success_proof_data,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_commitment_equality() {
+    let first_opening = PedersenOpening::new_rand();
+    let second_opening = PedersenOpening::new_rand();
+
+    let success_proof_data =
+        CommitmentEqualityData::new(55, &first_opening, &second_opening).unwrap();
+
+    // a proof for commitments to different values
+    let mut fail_proof_data =
+        CommitmentEqualityData::new(55, &first_opening, &second_opening).unwrap();
+    fail_proof_data.context.second_commitment = Pedersen::with(77_u64, &second_opening).into();
+
+    test_verify_proof_without_context(
+        ProofInstruction::VerifyCommitmentEquality,
+        &success_proof_data,
+        &fail_proof_data,
+    )
+    .await;
+
+    test_verify_proof_with_context(
+        ProofInstruction::VerifyCommitmentEquality,
+        size_of::<ProofContextState<CommitmentEqualityProofContext>>(),
+        &success_proof_data,
+        &fail_proof_data,
+    )
+    .await;
+
+    test_close_context_state(
+        ProofInstruction::VerifyCommitmentEquality,
+        size_of::<ProofContextState<CommitmentEqualityProofContext>>(),
+        &success_proof_data,
+    )
+    .await;
+}
+
+#[tokio::test]
+async fn test_test_vectors() {
+    let mut context = ProgramTest::default().start_with_context().await;
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    let test_vectors = [
+        (
+            ProofInstruction::VerifyCloseAccount,
+            close_account_test_vector(),
+        ),
+        (ProofInstruction::VerifyWithdraw, withdraw_test_vector()),
+        (
+            ProofInstruction::VerifyWithdrawWithheldTokens,
+            withdraw_withheld_tokens_test_vector(),
+        ),
+        (ProofInstruction::VerifyTransfer, transfer_test_vector()),
+        (
+            ProofInstruction::VerifyTransferWithFee,
+            transfer_with_fee_test_vector(),
+        ),
+        (
+            ProofInstruction::VerifyPubkeyValidity,
+            pubkey_validity_test_vector(),
+        ),
+        (
+            ProofInstruction::VerifyTransferSum,
+            transfer_sum_test_vector(),
+        ),
+        (ProofInstruction::VerifyMaxValue, max_value_test_vector()),
+        (ProofInstruction::VerifyParity, parity_test_vector()),
+        (
+            ProofInstruction::VerifyGreaterThan,
+            greater_than_test_vector(),
+        ),
+        (
+            ProofInstruction::VerifyKeyPossession,
+            key_possession_test_vector(),
+        ),
+        (
+            ProofInstruction::VerifyCommitmentEquality,
+            commitment_equality_test_vector(),
+        ),
+    ];
+
+    for (proof_instruction, test_vector) in test_vectors {
+        let mut data = vec![proof_instruction as u8];
+        data.extend_from_slice(&test_vector);
+
+        let instructions = vec![Instruction {
+            program_id: zk_token_proof_program::id(),
+            accounts: vec![],
+            data,
+        }];
+        let transaction = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&payer.pubkey()),
+            &[payer],
+            recent_blockhash,
+        );
+        client.process_transaction(transaction).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_verify_proof_with_prior_context() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+
+    // withdraw the entire balance so that the resulting ciphertext can be closed
+    let current_balance: u64 = 77;
+    let current_ciphertext = elgamal_keypair.public.encrypt(current_balance);
+    let withdraw_proof_data = WithdrawData::new(
+        current_balance,
+        &elgamal_keypair,
+        current_balance,
+        &current_ciphertext,
+    )
+    .unwrap();
+
+    let final_ciphertext: ElGamalCiphertext = withdraw_proof_data
+        .context
+        .final_ciphertext
+        .try_into()
+        .unwrap();
+    let linked_proof_data = CloseAccountData::new(&elgamal_keypair, &final_ciphertext).unwrap();
+
+    let unlinked_ciphertext = elgamal_keypair.public.encrypt(0_u64);
+    let unlinked_proof_data =
+        CloseAccountData::new(&elgamal_keypair, &unlinked_ciphertext).unwrap();
+
+    let mut context = ProgramTest::default().start_with_context().await;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    let prior_context_state_account = Keypair::new();
+    let prior_context_state_authority = Keypair::new();
+    let space = size_of::<ProofContextState<WithdrawProofContext>>();
+
+    // create the prior withdraw proof context state
+    let instructions = vec![
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &prior_context_state_account.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &zk_token_proof_program::id(),
+        ),
+        verify_withdraw(
+            Some(ContextStateInfo {
+                context_state_account: &prior_context_state_account.pubkey(),
+                context_state_authority: &prior_context_state_authority.pubkey(),
+            }),
+            &withdraw_proof_data,
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &prior_context_state_account],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    let option_accounts = [AccountMeta::new_readonly(
+        prior_context_state_account.pubkey(),
+        false,
+    )];
+
+    // verify a close account proof that consumes the ciphertext of the prior context
+    let instructions = vec![
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            None,
+            &linked_proof_data,
+            &[VerifyOption::PriorContext],
+            &option_accounts,
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    // try to verify a close account proof that is not linked to the prior context
+    let instructions = vec![
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            None,
+            &unlinked_proof_data,
+            &[VerifyOption::PriorContext],
+            &option_accounts,
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidInstructionData)
+    );
+}
+
+#[tokio::test]
+async fn test_verify_proof_with_partial_context() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+
+    // withdraw the entire balance so that the resulting ciphertext can be closed
+    let current_balance: u64 = 77;
+    let current_ciphertext = elgamal_keypair.public.encrypt(current_balance);
+    let withdraw_proof_data = WithdrawData::new(
+        current_balance,
+        &elgamal_keypair,
+        current_balance,
+        &current_ciphertext,
+    )
+    .unwrap();
+
+    let final_ciphertext: ElGamalCiphertext = withdraw_proof_data
+        .context
+        .final_ciphertext
+        .try_into()
+        .unwrap();
+    let linked_proof_data = CloseAccountData::new(&elgamal_keypair, &final_ciphertext).unwrap();
+
+    let mut context = ProgramTest::default().start_with_context().await;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    let context_state_account = Keypair::new();
+    let context_state_authority = Keypair::new();
+    let completing_authority = Keypair::new();
+    let space = size_of::<ExtendedProofContextStateMeta>() + size_of::<WithdrawProofContext>();
+
+    // create the partial withdraw proof context state
+    let instructions = vec![
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &context_state_account.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &zk_token_proof_program::id(),
+        ),
+        ProofInstruction::VerifyWithdraw.encode_verify_proof_with_options(
+            Some(ContextStateInfo {
+                context_state_account: &context_state_account.pubkey(),
+                context_state_authority: &context_state_authority.pubkey(),
+            }),
+            &withdraw_proof_data,
+            &[VerifyOption::Delegate, VerifyOption::Partial],
+            &[AccountMeta::new_readonly(
+                completing_authority.pubkey(),
+                false,
+            )],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &context_state_account],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    let account = client
+        .get_account(context_state_account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!ContextStateHeader::decode(&account.data)
+        .unwrap()
+        .is_complete());
+    assert!(ProofContextState::<WithdrawProofContext>::decode(&account.data).is_err());
+
+    // a consumer rejects the partial context state
+    let consumer_instructions = vec![ProofInstruction::VerifyCloseAccount
+        .encode_verify_proof_with_options(
+            None,
+            &linked_proof_data,
+            &[VerifyOption::PriorContext],
+            &[AccountMeta::new_readonly(
+                context_state_account.pubkey(),
+                false,
+            )],
+        )];
+    let transaction = Transaction::new_signed_with_payer(
+        &consumer_instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+
+    // only the delegate can complete the partial context state
+    let instructions = vec![complete_context_state(
+        &context_state_account.pubkey(),
+        &context_state_authority.pubkey(),
+    )];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &context_state_authority],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountOwner)
+    );
+
+    let instructions = vec![complete_context_state(
+        &context_state_account.pubkey(),
+        &completing_authority.pubkey(),
+    )];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &completing_authority],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    let account = client
+        .get_account(context_state_account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let context_state = ProofContextState::<WithdrawProofContext>::decode(&account.data).unwrap();
+    assert_eq!(
+        ContextStateHeader::decode(&account.data)
+            .unwrap()
+            .context_state_delegate,
+        completing_authority.pubkey()
+    );
+    assert_eq!(
+        bytes_of(&context_state.proof_context),
+        bytes_of(withdraw_proof_data.context_data())
+    );
+
+    // once completed, the context state is usable by the consumer, with a fresh proof so that the
+    // transaction differs from the rejected one
+    let linked_proof_data = CloseAccountData::new(&elgamal_keypair, &final_ciphertext).unwrap();
+    let consumer_instructions = vec![ProofInstruction::VerifyCloseAccount
+        .encode_verify_proof_with_options(
+            None,
+            &linked_proof_data,
+            &[VerifyOption::PriorContext],
+            &[AccountMeta::new_readonly(
+                context_state_account.pubkey(),
+                false,
+            )],
+        )];
+    let transaction = Transaction::new_signed_with_payer(
+        &consumer_instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_verify_proof_with_compressed_context() {
+    // a ciphertext of zero with zero randomness consists of identity points, which compress well
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = ElGamalCiphertext::from_bytes(&[0; 64]).unwrap();
+    let proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+
+    let mut context = ProgramTest::default().start_with_context().await;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    let context_state_account = Keypair::new();
+    let context_state_authority = Keypair::new();
+
+    let space = ProofContextState::encode_compressed(
+        &context_state_authority.pubkey(),
+        &Pubkey::default(),
+        ProofType::CloseAccount,
+        proof_data.context_data(),
+    )
+    .len();
+    assert!(space < size_of::<ProofContextState<CloseAccountProofContext>>());
+
+    let instructions = vec![
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &context_state_account.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &zk_token_proof_program::id(),
+        ),
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            Some(ContextStateInfo {
+                context_state_account: &context_state_account.pubkey(),
+                context_state_authority: &context_state_authority.pubkey(),
+            }),
+            &proof_data,
+            &[VerifyOption::Compressed],
+            &[],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &context_state_account],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    // the stored context state decodes back to the uncompressed proof context
+    let account = client
+        .get_account(context_state_account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let context_state =
+        ProofContextState::<CloseAccountProofContext>::decode(&account.data).unwrap();
+    assert_eq!(
+        context_state.context_state_authority,
+        context_state_authority.pubkey()
+    );
+    assert_eq!(
+        bytes_of(&context_state.proof_context),
+        bytes_of(proof_data.context_data())
+    );
+}
+
+#[tokio::test]
+async fn test_migrate_context_state() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = ElGamalCiphertext::from_bytes(&[0; 64]).unwrap();
+    let proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+
+    let mut context = ProgramTest::default().start_with_context().await;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    let context_state_account = Keypair::new();
+    let context_state_authority = Keypair::new();
+    let context_state_info = ContextStateInfo {
+        context_state_account: &context_state_account.pubkey(),
+        context_state_authority: &context_state_authority.pubkey(),
+    };
+    let space = size_of::<ProofContextState<CloseAccountProofContext>>();
+
+    let instructions = vec![
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &context_state_account.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &zk_token_proof_program::id(),
+        ),
+        verify_close_account(Some(context_state_info), &proof_data),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &context_state_account],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    // the context state is in the default layout that predates the extended layout
+    let account = client
+        .get_account(context_state_account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        account.data,
+        ProofContextState::encode(
+            &context_state_authority.pubkey(),
+            ProofType::CloseAccount,
+            proof_data.context_data(),
+        )
+    );
+
+    // migrate the context state to the extended layout with a compressed proof context
+    let transaction = Transaction::new_signed_with_payer(
+        &[migrate_context_state(
+            context_state_info,
+            PROOF_CONTEXT_STATE_VERSION_EXTENDED,
+            true,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, &context_state_authority],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    let account = client
+        .get_account(context_state_account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(account.data.len() < space);
+    let context_state =
+        ProofContextState::<CloseAccountProofContext>::decode(&account.data).unwrap();
+    let header = ContextStateHeader::decode(&account.data).unwrap();
+    assert_eq!(header.version, PROOF_CONTEXT_STATE_VERSION_EXTENDED);
+    assert_eq!(header.flags, PROOF_CONTEXT_STATE_FLAG_COMPRESSED);
+    assert_eq!(
+        bytes_of(&context_state.proof_context),
+        bytes_of(proof_data.context_data())
+    );
+
+    // try to migrate the context state back to the default layout, to an unknown version, or to
+    // the layout and encoding it already has
+    for (version, compressed) in [
+        (PROOF_CONTEXT_STATE_VERSION_DEFAULT, false),
+        (PROOF_CONTEXT_STATE_VERSION_EXTENDED + 1, true),
+        (PROOF_CONTEXT_STATE_VERSION_EXTENDED, true),
+    ] {
+        let transaction = Transaction::new_signed_with_payer(
+            &[migrate_context_state(
+                context_state_info,
+                version,
+                compressed,
+            )],
+            Some(&payer.pubkey()),
+            &[payer, &context_state_authority],
+            recent_blockhash,
+        );
+        let err = client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_verify_proof_borsh() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = elgamal_keypair.public.encrypt(0_u64);
+    let proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+
+    let mut context = ProgramTest::default().start_with_context().await;
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    // verify a borsh-encoded proof
+    let instructions = vec![
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_borsh(None, &proof_data, &[], &[]),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    // truncated borsh data should fail to decode
+    let mut instruction =
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_borsh(None, &proof_data, &[], &[]);
+    instruction.data.pop();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidInstructionData)
+    );
+}
+
+#[tokio::test]
+async fn test_verify_batch() {
+    let first_keypair = ElGamalKeypair::new_rand();
+    let second_keypair = ElGamalKeypair::new_rand();
+    let first_proof_data = PubkeyValidityData::new(&first_keypair).unwrap();
+    let second_proof_data = PubkeyValidityData::new(&second_keypair).unwrap();
+    let zero_ciphertext = first_keypair.public.encrypt(0_u64);
+    let close_account_proof_data = CloseAccountData::new(&first_keypair, &zero_ciphertext).unwrap();
+
+    let mut context = ProgramTest::default().start_with_context().await;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    let space = size_of::<ProofContextState<PubkeyValidityProofContext>>();
+    let context_state_authority = Pubkey::new_unique();
+    let first_context_state_account = Keypair::new();
+    let second_context_state_account = Keypair::new();
+    let instructions = vec![
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &first_context_state_account.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &zk_token_proof_program::id(),
+        ),
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &second_context_state_account.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &zk_token_proof_program::id(),
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[
+            payer,
+            &first_context_state_account,
+            &second_context_state_account,
+        ],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    let first_context_state_info = ContextStateInfo {
+        context_state_account: &first_context_state_account.pubkey(),
+        context_state_authority: &context_state_authority,
+    };
+    let second_context_state_info = ContextStateInfo {
+        context_state_account: &second_context_state_account.pubkey(),
+        context_state_authority: &context_state_authority,
+    };
+
+    // a batch with a failing entry in the middle fails as a whole and reports the entry
+    let incorrect_ciphertext = first_keypair.public.encrypt(1_u64);
+    let mut fail_proof_data = close_account_proof_data;
+    fail_proof_data.context.ciphertext = incorrect_ciphertext.into();
+    let instructions = vec![verify_batch(&[
+        (
+            ProofInstruction::VerifyPubkeyValidity,
+            bytes_of(&first_proof_data),
+            Some(first_context_state_info),
+        ),
+        (
+            ProofInstruction::VerifyCloseAccount,
+            bytes_of(&fail_proof_data),
+            None,
+        ),
+        (
+            ProofInstruction::VerifyPubkeyValidity,
+            bytes_of(&second_proof_data),
+            Some(second_context_state_info),
+        ),
+    ])];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let result = client
+        .process_transaction_with_metadata(transaction)
+        .await
+        .unwrap();
+    assert_eq!(
+        result.result,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::InvalidInstructionData
+        ))
+    );
+    assert!(result
+        .metadata
+        .unwrap()
+        .log_messages
+        .iter()
+        .any(|log| log.starts_with("Program log: batch entry 1 (VerifyCloseAccount) failed")));
+    let context_state = client
+        .get_account(first_context_state_account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(context_state.data, vec![0; space]);
+
+    // a valid batch of mixed proofs creates two of three possible context states
+    let instructions = vec![
+        ComputeBudgetInstruction::set_compute_unit_limit(300_000),
+        verify_batch(&[
+            (
+                ProofInstruction::VerifyPubkeyValidity,
+                bytes_of(&first_proof_data),
+                Some(first_context_state_info),
+            ),
+            (
+                ProofInstruction::VerifyCloseAccount,
+                bytes_of(&close_account_proof_data),
+                None,
+            ),
+            (
+                ProofInstruction::VerifyPubkeyValidity,
+                bytes_of(&second_proof_data),
+                Some(second_context_state_info),
+            ),
+        ]),
+    ];
+    assert_eq!(check_budget_sufficiency(&instructions), Ok(()));
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let result = client
+        .process_transaction_with_metadata(transaction)
+        .await
+        .unwrap();
+    assert_eq!(result.result, Ok(()));
+
+    let return_data = result.metadata.unwrap().return_data.unwrap();
+    assert_eq!(return_data.program_id, zk_token_proof_program::id());
+    assert_eq!(return_data.data, 2_u64.to_le_bytes().to_vec());
+
+    for (context_state_account, proof_data) in [
+        (&first_context_state_account, &first_proof_data),
+        (&second_context_state_account, &second_proof_data),
+    ] {
+        let context_state = client
+            .get_account(context_state_account.pubkey())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            context_state.data,
+            ProofContextState::encode(
+                &context_state_authority,
+                ProofType::PubkeyValidity,
+                proof_data.context_data(),
+            )
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_verify_withheld_fee_sum() {
+    let withdraw_withheld_authority_keypair = ElGamalKeypair::new_rand();
+    let destination_pubkey = ElGamalKeypair::new_rand().public;
+
+    let proof_data: Vec<_> = [55_u64, 77]
+        .iter()
+        .map(|amount| {
+            let ciphertext = withdraw_withheld_authority_keypair.public.encrypt(*amount);
+            WithdrawWithheldTokensData::new(
+                &withdraw_withheld_authority_keypair,
+                &destination_pubkey,
+                &ciphertext,
+                *amount,
+            )
+            .unwrap()
+        })
+        .collect();
+
+    let mut context = ProgramTest::default().start_with_context().await;
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    // the correct total, whose proofs and sum proof exceed the default compute unit limit
+    let fee_sum_data =
+        WithheldFeeSumData::new(&withdraw_withheld_authority_keypair, 132, &proof_data).unwrap();
+    let instructions = vec![verify_withheld_fee_sum(&fee_sum_data, &proof_data)];
+    assert_eq!(
+        check_budget_sufficiency(&instructions),
+        Err(compute_units(ProofInstruction::VerifyCloseAccount))
+    );
+    let compute_unit_limit = ComputeBudgetInstruction::set_compute_unit_limit(
+        withheld_fee_sum_compute_units(proof_data.len() as u64) as u32,
+    );
+    let instructions = vec![
+        compute_unit_limit.clone(),
+        verify_withheld_fee_sum(&fee_sum_data, &proof_data),
+    ];
+    assert_eq!(check_budget_sufficiency(&instructions), Ok(()));
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    // an incorrect total
+    let fee_sum_data =
+        WithheldFeeSumData::new(&withdraw_withheld_authority_keypair, 133, &proof_data).unwrap();
+    let instructions = vec![
+        compute_unit_limit.clone(),
+        verify_withheld_fee_sum(&fee_sum_data, &proof_data),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(1, InstructionError::InvalidInstructionData)
+    );
+
+    // a correct total over a subset of the fees
+    let fee_sum_data =
+        WithheldFeeSumData::new(&withdraw_withheld_authority_keypair, 132, &proof_data).unwrap();
+    let instructions = vec![
+        compute_unit_limit,
+        verify_withheld_fee_sum(&fee_sum_data, &proof_data[..1]),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(1, InstructionError::InvalidInstructionData)
+    );
+}
+
+#[tokio::test]
+async fn test_verify_proof_with_memo_log() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = elgamal_keypair.public.encrypt(0_u64);
+    let proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+
+    let mut context = ProgramTest::default().start_with_context().await;
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    let instructions = vec![
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            None,
+            &proof_data,
+            &[VerifyOption::MemoLog],
+            &[],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let result = client
+        .process_transaction_with_metadata(transaction)
+        .await
+        .unwrap();
+    assert_eq!(result.result, Ok(()));
+
+    let commitment: String = hash(bytes_of(proof_data.context_data()))
+        .to_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    let expected_log = format!("Program log: Memo (len 64): \"{}\"", commitment);
+    assert!(result
+        .metadata
+        .unwrap()
+        .log_messages
+        .contains(&expected_log));
+}
+
+#[tokio::test]
+async fn test_verify_proof_with_slot_log() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = elgamal_keypair.public.encrypt(0_u64);
+    let proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+
+    let mut context = ProgramTest::default().start_with_context().await;
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    let instructions = vec![
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            None,
+            &proof_data,
+            &[VerifyOption::SlotLog],
+            &[],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let result = client
+        .process_transaction_with_metadata(transaction)
+        .await
+        .unwrap();
+    assert_eq!(result.result, Ok(()));
+
+    let expected_log = format!(
+        "Program log: proof verified in slot {} of epoch {}",
+        clock.slot, clock.epoch
+    );
+    assert!(result
+        .metadata
+        .unwrap()
+        .log_messages
+        .contains(&expected_log));
+
+    // the slot is not logged without the option
+    let instructions =
+        vec![ProofInstruction::VerifyCloseAccount.encode_verify_proof(None, &proof_data)];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let result = client
+        .process_transaction_with_metadata(transaction)
+        .await
+        .unwrap();
+    assert_eq!(result.result, Ok(()));
+    assert!(!result
+        .metadata
+        .unwrap()
+        .log_messages
+        .iter()
+        .any(|log| log.starts_with("Program log: proof verified in slot")));
+}
+
+#[tokio::test]
+async fn test_verify_proof_with_receipt() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = elgamal_keypair.public.encrypt(0_u64);
+    let proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+
+    let mut context = ProgramTest::default().start_with_context().await;
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    let instructions = vec![
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            None,
+            &proof_data,
+            &[VerifyOption::Receipt],
+            &[],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let result = client
+        .process_transaction_with_metadata(transaction)
+        .await
+        .unwrap();
+    assert_eq!(result.result, Ok(()));
+
+    let return_data = result.metadata.unwrap().return_data.unwrap();
+    assert_eq!(return_data.program_id, zk_token_proof_program::id());
+    assert_eq!(
+        return_data.data,
+        verification_receipt(
+            ProofType::CloseAccount,
+            bytes_of(proof_data.context_data()),
+            clock.slot
+        )
+    );
+}
+
+/// Initializes the policy account of the kind `discriminator` that `admin` administers under `id`
+/// with the body `body`, and returns its address.
+async fn create_policy_account(
+    context: &mut ProgramTestContext,
+    admin: &Keypair,
+    discriminator: &[u8; 8],
+    id: u64,
+    body: &[u8],
+) -> Pubkey {
+    let instruction = initialize_policy_account(
+        &context.payer.pubkey(),
+        &admin.pubkey(),
+        discriminator,
+        id,
+        body,
+    );
+    let policy_account = instruction.accounts[0].pubkey;
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, admin],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    policy_account
+}
+
+/// A program-owned account holding a copy of the policy account of the kind `discriminator` that
+/// `admin` would initialize under `id` with the body `body`, for placing at another address.
+fn forged_policy_account(admin: &Pubkey, discriminator: &[u8; 8], id: u64, body: &[u8]) -> Account {
+    let (_, bump) = find_policy_account_address(discriminator, admin, id);
+    let mut data = bytes_of(&PolicyAccountHeader {
+        discriminator: *discriminator,
+        admin: *admin,
+        id: id.into(),
+        bump,
+    })
+    .to_vec();
+    data.extend_from_slice(body);
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: zk_token_proof_program::id(),
+        ..Account::default()
+    }
+}
+
+#[tokio::test]
+async fn test_verify_proof_with_nullifier() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = elgamal_keypair.public.encrypt(0_u64);
+    let proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+
+    let mut context = ProgramTest::default().start_with_context().await;
+    let nullifier_set_account = create_policy_account(
+        &mut context,
+        &Keypair::new(),
+        &NULLIFIER_SET_DISCRIMINATOR,
+        0,
+        &NullifierSet::encode(4),
+    )
+    .await;
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    let nullifier_accounts = [AccountMeta::new(nullifier_set_account, false)];
+
+    // the first use of a proof records its nullifier
+    let instructions = vec![
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            None,
+            &proof_data,
+            &[VerifyOption::Nullifier],
+            &nullifier_accounts,
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    let account = client
+        .get_account(nullifier_set_account)
+        .await
+        .unwrap()
+        .unwrap();
+    let nullifier =
+        NullifierSet::nullifier(ProofType::CloseAccount, bytes_of(proof_data.context_data()));
+    let (_, nullifier_set) =
+        PolicyAccountHeader::split(&account.data, &NULLIFIER_SET_DISCRIMINATOR).unwrap();
+    assert!(NullifierSet::contains(nullifier_set, &nullifier).unwrap());
+
+    // a replay of the proof is rejected, even in a differently signed transaction
+    let instructions = vec![
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            None,
+            &proof_data,
+            &[VerifyOption::MemoLog, VerifyOption::Nullifier],
+            &nullifier_accounts,
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+}
+
+#[tokio::test]
+async fn test_verify_proof_with_mirror_account() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = elgamal_keypair.public.encrypt(0_u64);
+    let proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+
+    let mut context = ProgramTest::default().start_with_context().await;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    let space = size_of::<ProofContextState<CloseAccountProofContext>>();
+    let context_state_authority = Pubkey::new_unique();
+
+    // write the context state to both the proof context account and the mirror account
+    let context_state_account = Keypair::new();
+    let mirror_account = Keypair::new();
+    let instructions = vec![
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &context_state_account.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &zk_token_proof_program::id(),
+        ),
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &mirror_account.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &zk_token_proof_program::id(),
+        ),
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            Some(ContextStateInfo {
+                context_state_account: &context_state_account.pubkey(),
+                context_state_authority: &context_state_authority,
+            }),
+            &proof_data,
+            &[VerifyOption::MirrorAccount],
+            &[AccountMeta::new(mirror_account.pubkey(), false)],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &context_state_account, &mirror_account],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    let context_state = client
+        .get_account(context_state_account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let mirror_state = client
+        .get_account(mirror_account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        context_state.data,
+        ProofContextState::encode(
+            &context_state_authority,
+            ProofType::CloseAccount,
+            proof_data.context_data(),
+        )
+    );
+    assert_eq!(mirror_state.data, context_state.data);
+
+    // a mirror account of a different length fails the whole instruction
+    let context_state_account = Keypair::new();
+    let mirror_account = Keypair::new();
+    let mirror_space = space.checked_add(1).unwrap();
+    let instructions = vec![
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &context_state_account.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &zk_token_proof_program::id(),
+        ),
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &mirror_account.pubkey(),
+            rent.minimum_balance(mirror_space),
+            mirror_space as u64,
+            &zk_token_proof_program::id(),
+        ),
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            Some(ContextStateInfo {
+                context_state_account: &context_state_account.pubkey(),
+                context_state_authority: &context_state_authority,
+            }),
+            &proof_data,
+            &[VerifyOption::MirrorAccount],
+            &[AccountMeta::new(mirror_account.pubkey(), false)],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &context_state_account, &mirror_account],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(2, InstructionError::InvalidAccountData)
+    );
+    assert!(client
+        .get_account(context_state_account.pubkey())
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_verify_proof_with_context_address() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = elgamal_keypair.public.encrypt(0_u64);
+    let proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+
+    let mut context = ProgramTest::default().start_with_context().await;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    let space = size_of::<ProofContextState<CloseAccountProofContext>>();
+    let context_state_authority = Pubkey::new_unique();
+
+    for (committed_address, expected_result) in [
+        (None, Ok(())),
+        (
+            Some(Pubkey::new_unique()),
+            Err(TransactionError::InstructionError(
+                1,
+                InstructionError::InvalidArgument,
+            )),
+        ),
+    ] {
+        let context_state_account = Keypair::new();
+        let committed_address = committed_address.unwrap_or_else(|| context_state_account.pubkey());
+        let instructions = vec![
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &context_state_account.pubkey(),
+                rent.minimum_balance(space),
+                space as u64,
+                &zk_token_proof_program::id(),
+            ),
+            ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+                Some(ContextStateInfo {
+                    context_state_account: &context_state_account.pubkey(),
+                    context_state_authority: &context_state_authority,
+                }),
+                &proof_data,
+                &[VerifyOption::ContextAddress(committed_address)],
+                &[],
+            ),
+        ];
+        let transaction = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&payer.pubkey()),
+            &[payer, &context_state_account],
+            recent_blockhash,
+        );
+        let result = client
+            .process_transaction(transaction)
+            .await
+            .map_err(|err| err.unwrap());
+        assert_eq!(result, expected_result);
+
+        let context_state = client
+            .get_account(context_state_account.pubkey())
+            .await
+            .unwrap();
+        if expected_result.is_ok() {
+            assert_eq!(
+                context_state.unwrap().data,
+                ProofContextState::encode(
+                    &context_state_authority,
+                    ProofType::CloseAccount,
+                    proof_data.context_data(),
+                )
+            );
+        } else {
+            assert!(context_state.is_none());
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_verify_proof_with_reference_only() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let proof_data = PubkeyValidityData::new(&elgamal_keypair).unwrap();
+
+    let mut context = ProgramTest::default().start_with_context().await;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    let space = size_of::<ProofReference>();
+    let context_state_authority = Keypair::new();
+    let context_state_account = Keypair::new();
+    let context_state_info = ContextStateInfo {
+        context_state_account: &context_state_account.pubkey(),
+        context_state_authority: &context_state_authority.pubkey(),
+    };
+    let create_account_instruction = system_instruction::create_account(
+        &payer.pubkey(),
+        &context_state_account.pubkey(),
+        rent.minimum_balance(space),
+        space as u64,
+        &zk_token_proof_program::id(),
+    );
+
+    // a reference has no proof context to compress
+    let instructions = vec![
+        create_account_instruction.clone(),
+        ProofInstruction::VerifyPubkeyValidity.encode_verify_proof_with_options(
+            Some(context_state_info),
+            &proof_data,
+            &[VerifyOption::ReferenceOnly, VerifyOption::Compressed],
+            &[],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &context_state_account],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(1, InstructionError::InvalidInstructionData)
+    );
+
+    let instructions = vec![
+        create_account_instruction,
+        ProofInstruction::VerifyPubkeyValidity.encode_verify_proof_with_options(
+            Some(context_state_info),
+            &proof_data,
+            &[VerifyOption::ReferenceOnly],
+            &[],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &context_state_account],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    let account = client
+        .get_account(context_state_account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.data.len(), space);
+    let reference = ProofReference::try_from_bytes(&account.data).unwrap();
+    assert_eq!(
+        reference.meta.context_state_authority,
+        context_state_authority.pubkey()
+    );
+    assert_eq!(reference.meta.proof_type, ProofType::PubkeyValidity.into());
+    assert_eq!(reference.proof_hash, ProofCommitment::hash(&proof_data));
+
+    // the reference can be closed by its authority
+    let destination_account = Pubkey::new_unique();
+    let instructions = vec![close_context_state(
+        context_state_info,
+        &destination_account,
+    )];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &context_state_authority],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+    assert!(client
+        .get_account(context_state_account.pubkey())
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_verify_proof_with_token_2022_layout() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let proof_data = PubkeyValidityData::new(&elgamal_keypair).unwrap();
+
+    let mut context = ProgramTest::default().start_with_context().await;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    let space = size_of::<Token2022ProofContextState<PubkeyValidityProofContext>>();
+    let context_state_authority = Keypair::new();
+    let context_state_account = Keypair::new();
+    let context_state_info = ContextStateInfo {
+        context_state_account: &context_state_account.pubkey(),
+        context_state_authority: &context_state_authority.pubkey(),
+    };
+    let create_account_instruction = system_instruction::create_account(
+        &payer.pubkey(),
+        &context_state_account.pubkey(),
+        rent.minimum_balance(space),
+        space as u64,
+        &zk_token_proof_program::id(),
+    );
+
+    // the layout has no room for compression
+    let instructions = vec![
+        create_account_instruction.clone(),
+        ProofInstruction::VerifyPubkeyValidity.encode_verify_proof_with_options(
+            Some(context_state_info),
+            &proof_data,
+            &[VerifyOption::Token2022Layout, VerifyOption::Compressed],
+            &[],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &context_state_account],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(1, InstructionError::InvalidInstructionData)
+    );
+
+    let instructions = vec![
+        create_account_instruction,
+        ProofInstruction::VerifyPubkeyValidity.encode_verify_proof_with_options(
+            Some(context_state_info),
+            &proof_data,
+            &[VerifyOption::Token2022Layout],
+            &[],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &context_state_account],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    // read the context state the way that token-2022 does
+    let account = client
+        .get_account(context_state_account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.owner, zk_token_proof_program::id());
+    let context_state = bytemuck::try_from_bytes::<
+        Token2022ProofContextState<PubkeyValidityProofContext>,
+    >(&account.data)
+    .unwrap();
+    assert_eq!(
+        context_state.context_state_authority,
+        context_state_authority.pubkey()
+    );
+    assert_eq!(context_state.proof_type, ProofType::PubkeyValidity.into());
+    assert_eq!(
+        bytes_of(&context_state.proof_context),
+        bytes_of(proof_data.context_data())
+    );
+
+    // the context state can be closed by its authority
+    let destination_account = Pubkey::new_unique();
+    let instructions = vec![close_context_state(
+        context_state_info,
+        &destination_account,
+    )];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &context_state_authority],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+    assert!(client
+        .get_account(context_state_account.pubkey())
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_close_context_state_in_default_layout_resembling_extended_layout() {
+    let context_state_account = Pubkey::new_unique();
+    let context_state_authority = Keypair::new();
+    let attacker = Keypair::new();
+
+    // a context state in the default layout, as written before the extended layout existed, whose
+    // proof context bytes are where the version, flags, and delegate of the extended layout would
+    // be
+    let mut data = ProofContextState::encode(
+        &context_state_authority.pubkey(),
+        ProofType::CloseAccount,
+        &CloseAccountProofContext {
+            pubkey: pod::ElGamalPubkey([1; 32]),
+            ciphertext: pod::ElGamalCiphertext([2; 64]),
+        },
+    );
+    data[34] = PROOF_CONTEXT_STATE_VERSION_EXTENDED;
+    data[35] = 0;
+    data[36..68].copy_from_slice(attacker.pubkey().as_ref());
+
+    let mut program_test = ProgramTest::default();
+    program_test.add_account(
+        context_state_account,
+        Account {
+            lamports: 1_000_000_000,
+            data,
+            owner: zk_token_proof_program::id(),
+            ..Account::default()
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+    let destination_account = Pubkey::new_unique();
+
+    // the attacker cannot close the context state as its delegate
+    let instructions = vec![close_context_state(
+        ContextStateInfo {
+            context_state_account: &context_state_account,
+            context_state_authority: &attacker.pubkey(),
+        },
+        &destination_account,
+    )];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &attacker],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountOwner)
+    );
+
+    // nor sweep it
+    let instructions = vec![sweep_context_states(
+        &destination_account,
+        &attacker.pubkey(),
+        &[context_state_account],
+    )];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &attacker],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+    assert!(client
+        .get_account(context_state_account)
+        .await
+        .unwrap()
+        .is_some());
+
+    // the authority of the default layout can close it
+    let instructions = vec![close_context_state(
+        ContextStateInfo {
+            context_state_account: &context_state_account,
+            context_state_authority: &context_state_authority.pubkey(),
+        },
+        &destination_account,
+    )];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &context_state_authority],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+    assert!(client
+        .get_account(context_state_account)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_verify_proof_with_prior_instruction() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = elgamal_keypair.public.encrypt(0_u64);
+    let proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+
+    let mut context = ProgramTest::default().start_with_context().await;
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    // require a system program transfer, whose bincode-encoded discriminant starts with 2
+    let approval_instruction = system_instruction::transfer(&payer.pubkey(), &payer.pubkey(), 1);
+    let verify_instruction = ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+        None,
+        &proof_data,
+        &[VerifyOption::PriorInstruction {
+            program_id: system_program::id(),
+            discriminant: 2,
+        }],
+        &[AccountMeta::new_readonly(sysvar::instructions::id(), false)],
+    );
+
+    // the required instruction precedes the verify instruction
+    let transaction = Transaction::new_signed_with_payer(
+        &[approval_instruction.clone(), verify_instruction.clone()],
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    // the required instruction is missing
+    let transaction = Transaction::new_signed_with_payer(
+        &[verify_instruction.clone()],
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+
+    // the required instruction follows the verify instruction
+    let transaction = Transaction::new_signed_with_payer(
+        &[verify_instruction, approval_instruction],
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+}
+
+#[tokio::test]
+async fn test_verify_proof_with_telemetry() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = elgamal_keypair.public.encrypt(0_u64);
+    let proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+
+    let mut context = ProgramTest::default().start_with_context().await;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    let space = size_of::<ProofContextState<CloseAccountProofContext>>();
+    let context_state_account = Keypair::new();
+    let context_state_authority = Pubkey::new_unique();
+
+    for create_context_state in [false, true] {
+        let context_state_info = ContextStateInfo {
+            context_state_account: &context_state_account.pubkey(),
+            context_state_authority: &context_state_authority,
+        };
+        let (instructions, signers) = if create_context_state {
+            (
+                vec![
+                    system_instruction::create_account(
+                        &payer.pubkey(),
+                        &context_state_account.pubkey(),
+                        rent.minimum_balance(space),
+                        space as u64,
+                        &zk_token_proof_program::id(),
+                    ),
+                    ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+                        Some(context_state_info),
+                        &proof_data,
+                        &[VerifyOption::Telemetry],
+                        &[],
+                    ),
+                ],
+                vec![payer, &context_state_account],
+            )
+        } else {
+            (
+                vec![
+                    ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+                        None,
+                        &proof_data,
+                        &[VerifyOption::Telemetry],
+                        &[],
+                    ),
+                ],
+                vec![payer],
+            )
+        };
+        let transaction = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&payer.pubkey()),
+            &signers,
+            recent_blockhash,
+        );
+        let result = client
+            .process_transaction_with_metadata(transaction)
+            .await
+            .unwrap();
+        assert_eq!(result.result, Ok(()));
+
+        let return_data = result.metadata.unwrap().return_data.unwrap();
+        assert_eq!(return_data.program_id, zk_token_proof_program::id());
+        let telemetry = VerifyTelemetry::decode(&return_data.data).unwrap();
+        assert_eq!(telemetry.version, VERIFY_TELEMETRY_VERSION);
+        assert_eq!(telemetry.proof_type, ProofType::CloseAccount as u8);
+        assert_eq!(
+            telemetry.context_state_created,
+            u8::from(create_context_state)
+        );
+        assert_eq!(
+            u64::from(telemetry.compute_units_charged),
+            compute_units(ProofInstruction::VerifyCloseAccount)
+        );
+        assert!(u64::from(telemetry.compute_units_remaining) > 0);
+    }
+
+    // telemetry and a receipt both claim the return data
+    let instructions = vec![
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            None,
+            &proof_data,
+            &[VerifyOption::Receipt, VerifyOption::Telemetry],
+            &[],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidInstructionData)
+    );
+}
+
+#[tokio::test]
+async fn test_verify_proof_with_usage_counter() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = elgamal_keypair.public.encrypt(0_u64);
+    let success_proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+
+    let incorrect_keypair = ElGamalKeypair {
+        public: ElGamalKeypair::new_rand().public,
+        secret: ElGamalKeypair::new_rand().secret,
+    };
+    let fail_proof_data = CloseAccountData::new(&incorrect_keypair, &zero_ciphertext).unwrap();
+
+    let foreign_counter_account = Pubkey::new_unique();
+
+    let mut program_test = ProgramTest::default();
+    program_test.add_account(
+        foreign_counter_account,
+        Account {
+            lamports: 1_000_000_000,
+            data: bytes_of(&UsageCounter::new(0)).to_vec(),
+            owner: Pubkey::new_unique(),
+            ..Account::default()
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+
+    let admin = Keypair::new();
+    let counter_account = create_policy_account(
+        &mut context,
+        &admin,
+        &USAGE_COUNTER_DISCRIMINATOR,
+        0,
+        bytes_of(&UsageCounter::new(0)),
+    )
+    .await;
+    let full_counter_account = create_policy_account(
+        &mut context,
+        &admin,
+        &USAGE_COUNTER_DISCRIMINATOR,
+        1,
+        bytes_of(&UsageCounter::new(u64::MAX)),
+    )
+    .await;
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    // a verified proof increments the counter
+    let instructions = vec![
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            None,
+            &success_proof_data,
+            &[VerifyOption::UsageCounter],
+            &[AccountMeta::new(counter_account, false)],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    let account = client.get_account(counter_account).await.unwrap().unwrap();
+    let (_, counter) =
+        PolicyAccountHeader::split(&account.data, &USAGE_COUNTER_DISCRIMINATOR).unwrap();
+    assert_eq!(
+        bytemuck::from_bytes::<UsageCounter>(counter).count,
+        1_u64.into()
+    );
+
+    // an invalid proof leaves the counter unchanged
+    let instructions = vec![
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            None,
+            &fail_proof_data,
+            &[VerifyOption::UsageCounter],
+            &[AccountMeta::new(counter_account, false)],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidInstructionData)
+    );
+
+    let account = client.get_account(counter_account).await.unwrap().unwrap();
+    let (_, counter) =
+        PolicyAccountHeader::split(&account.data, &USAGE_COUNTER_DISCRIMINATOR).unwrap();
+    assert_eq!(
+        bytemuck::from_bytes::<UsageCounter>(counter).count,
+        1_u64.into()
+    );
+
+    // a counter that would overflow rejects the proof
+    let instructions = vec![
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            None,
+            &success_proof_data,
+            &[VerifyOption::UsageCounter],
+            &[AccountMeta::new(full_counter_account, false)],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::ArithmeticOverflow)
+    );
+
+    // a counter that is not owned by the proof program is rejected
+    let instructions = vec![
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            None,
+            &success_proof_data,
+            &[VerifyOption::UsageCounter],
+            &[AccountMeta::new(foreign_counter_account, false)],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountOwner)
+    );
+}
+
+#[tokio::test]
+async fn test_verify_proof_with_slot_windows() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = elgamal_keypair.public.encrypt(0_u64);
+    let proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+
+    let admin = Keypair::new();
+    let open_windows = SlotWindows::encode(&[(0, 1_000_000), (2_000_000, 3_000_000)]);
+    let forged_windows_account = Pubkey::new_unique();
+
+    let mut program_test = ProgramTest::default();
+    program_test.add_account(
+        forged_windows_account,
+        forged_policy_account(
+            &admin.pubkey(),
+            &SLOT_WINDOWS_DISCRIMINATOR,
+            0,
+            &open_windows,
+        ),
+    );
+    let mut context = program_test.start_with_context().await;
+
+    let open_windows_account = create_policy_account(
+        &mut context,
+        &admin,
+        &SLOT_WINDOWS_DISCRIMINATOR,
+        0,
+        &open_windows,
+    )
+    .await;
+    let closed_windows_account = create_policy_account(
+        &mut context,
+        &admin,
+        &SLOT_WINDOWS_DISCRIMINATOR,
+        1,
+        &SlotWindows::encode(&[(1_000_000, 2_000_000), (3_000_000, 4_000_000)]),
+    )
+    .await;
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    // verify a proof within an allowed window
+    let instructions = vec![
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            None,
+            &proof_data,
+            &[VerifyOption::SlotWindows],
+            &[AccountMeta::new_readonly(open_windows_account, false)],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    // try to verify a proof outside the allowed windows
+    let instructions = vec![
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            None,
+            &proof_data,
+            &[VerifyOption::SlotWindows],
+            &[AccountMeta::new_readonly(closed_windows_account, false)],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+
+    // a copy of the open windows at an address not derived from its header is rejected
+    let instructions = vec![
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            None,
+            &proof_data,
+            &[VerifyOption::SlotWindows],
+            &[AccountMeta::new_readonly(forged_windows_account, false)],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+}
+
+#[tokio::test]
+async fn test_verify_proof_with_anchor_event() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = elgamal_keypair.public.encrypt(0_u64);
+    let proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+
+    let mut context = ProgramTest::default().start_with_context().await;
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    let context_state_account = Keypair::new();
+    let context_state_authority = Keypair::new();
+    let space = size_of::<ProofContextState<CloseAccountProofContext>>();
+
+    let instructions = vec![
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &context_state_account.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &zk_token_proof_program::id(),
+        ),
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            Some(ContextStateInfo {
+                context_state_account: &context_state_account.pubkey(),
+                context_state_authority: &context_state_authority.pubkey(),
+            }),
+            &proof_data,
+            &[VerifyOption::AnchorEvent],
+            &[],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &context_state_account],
+        recent_blockhash,
+    );
+    let result = client
+        .process_transaction_with_metadata(transaction)
+        .await
+        .unwrap();
+    assert_eq!(result.result, Ok(()));
+
+    let log_messages = result.metadata.unwrap().log_messages;
+    let event_data = log_messages
+        .iter()
+        .find_map(|log| log.strip_prefix("Program data: "))
+        .unwrap();
+    let event = ProofVerified::decode(&base64::decode(event_data).unwrap()).unwrap();
+    assert_eq!(
+        event,
+        ProofVerified {
+            version: PROOF_VERIFIED_EVENT_VERSION,
+            proof_type: ProofType::CloseAccount as u8,
+            context_state_account: Some(context_state_account.pubkey()),
+            slot: clock.slot,
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_verify_proof_with_structured_log() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = elgamal_keypair.public.encrypt(0_u64);
+    let proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+
+    let mut context = ProgramTest::default().start_with_context().await;
+    let clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    let instructions = vec![
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            None,
+            &proof_data,
+            &[VerifyOption::StructuredLog],
+            &[],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let result = client
+        .process_transaction_with_metadata(transaction)
+        .await
+        .unwrap();
+    assert_eq!(result.result, Ok(()));
+
+    let log_messages = result.metadata.unwrap().log_messages;
+    assert!(log_messages.contains(&"Program log: CloseAccount proof verified".to_string()));
+
+    let payload = log_messages
+        .iter()
+        .find_map(|log| log.strip_prefix("Program log: {"))
+        .map(|payload| format!("{{{}", payload))
+        .unwrap();
+    let payload: serde_json::Value = serde_json::from_str(&payload).unwrap();
+    assert_eq!(payload["version"], PROOF_VERIFIED_EVENT_VERSION);
+    assert_eq!(payload["proof_type"], ProofType::CloseAccount as u8);
+    assert_eq!(payload["context_state_account"], serde_json::Value::Null);
+    assert_eq!(payload["slot"], clock.slot);
+}
+
+#[tokio::test]
+async fn test_verify_proof_with_context_account_creation() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = elgamal_keypair.public.encrypt(0_u64);
+    let proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+
+    let underfunded_payer = Keypair::new();
+    let mut program_test = ProgramTest::default();
+    program_test.add_account(
+        underfunded_payer.pubkey(),
+        Account {
+            lamports: 1_000,
+            ..Account::default()
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    let context_state_authority = Keypair::new();
+    let space = size_of::<ProofContextState<CloseAccountProofContext>>();
+
+    // create and write a proof context account in a single instruction
+    let context_state_account = Keypair::new();
+    let instructions = vec![
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            Some(ContextStateInfo {
+                context_state_account: &context_state_account.pubkey(),
+                context_state_authority: &context_state_authority.pubkey(),
+            }),
+            &proof_data,
+            &[VerifyOption::CreateContextAccount],
+            &[
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &context_state_account],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    let account = client
+        .get_account(context_state_account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.owner, zk_token_proof_program::id());
+    assert_eq!(account.lamports, rent.minimum_balance(space));
+    let context_state =
+        ProofContextState::<CloseAccountProofContext>::try_from_bytes(&account.data).unwrap();
+    assert_eq!(
+        context_state.context_state_authority,
+        context_state_authority.pubkey()
+    );
+
+    // try to create a proof context account with an underfunded payer
+    let context_state_account = Keypair::new();
+    let instructions = vec![
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            Some(ContextStateInfo {
+                context_state_account: &context_state_account.pubkey(),
+                context_state_authority: &context_state_authority.pubkey(),
+            }),
+            &proof_data,
+            &[VerifyOption::CreateContextAccount],
+            &[
+                AccountMeta::new(underfunded_payer.pubkey(), true),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &context_state_account, &underfunded_payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InsufficientFunds)
+    );
+}
+
+/// Returns an SPL Token 2022 account with a confidential transfer extension for `elgamal_pubkey`.
+fn confidential_transfer_token_account(elgamal_pubkey: &ElGamalPubkey) -> Account {
+    let pending_balance = elgamal_pubkey.encrypt(0_u64).to_bytes();
+
+    // base account, account type, and the extension type and length
+    let mut data = vec![0; 165];
+    data.push(2);
+    data.extend_from_slice(&5_u16.to_le_bytes());
+    data.extend_from_slice(&295_u16.to_le_bytes());
+
+    // approved, ElGamal pubkey, pending balance, and the remaining fields
+    data.push(1);
+    data.extend_from_slice(&elgamal_pubkey.to_bytes());
+    data.extend_from_slice(&pending_balance);
+    data.extend_from_slice(&pending_balance);
+    data.resize(465, 0);
+
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: Pubkey::from_str("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb").unwrap(),
+        ..Account::default()
+    }
+}
+
+#[tokio::test]
+async fn test_verify_proof_with_destination_account() {
+    let source_keypair = ElGamalKeypair::new_rand();
+    let dest_pubkey = ElGamalKeypair::new_rand().public;
+    let auditor_pubkey = ElGamalKeypair::new_rand().public;
+
+    let spendable_balance: u64 = 0;
+    let spendable_ciphertext = source_keypair.public.encrypt(spendable_balance);
+    let proof_data = TransferData::new(
+        0,
+        (spendable_balance, &spendable_ciphertext),
+        &source_keypair,
+        (&dest_pubkey, &auditor_pubkey),
+    )
+    .unwrap();
+
+    let destination_account = Pubkey::new_unique();
+    let other_account = Pubkey::new_unique();
+
+    let mut program_test = ProgramTest::default();
+    program_test.add_account(
+        destination_account,
+        confidential_transfer_token_account(&dest_pubkey),
+    );
+    program_test.add_account(
+        other_account,
+        confidential_transfer_token_account(&ElGamalKeypair::new_rand().public),
+    );
+    let mut context = program_test.start_with_context().await;
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    // verify a proof against its destination account
+    let instructions = vec![
+        ProofInstruction::VerifyTransfer.encode_verify_proof_with_options(
+            None,
+            &proof_data,
+            &[VerifyOption::DestinationAccount],
+            &[AccountMeta::new_readonly(destination_account, false)],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    // try to verify a proof against an account of a different ElGamal pubkey
+    let instructions = vec![
+        ProofInstruction::VerifyTransfer.encode_verify_proof_with_options(
+            None,
+            &proof_data,
+            &[VerifyOption::DestinationAccount],
+            &[AccountMeta::new_readonly(other_account, false)],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidInstructionData)
+    );
+}
+
+#[tokio::test]
+async fn test_verify_proof_with_distinct_endpoints() {
+    let source_keypair = ElGamalKeypair::new_rand();
+    let dest_pubkey = ElGamalKeypair::new_rand().public;
+    let auditor_pubkey = ElGamalKeypair::new_rand().public;
+
+    let spendable_balance: u64 = 0;
+    let spendable_ciphertext = source_keypair.public.encrypt(spendable_balance);
+    let distinct_proof_data = TransferData::new(
+        0,
+        (spendable_balance, &spendable_ciphertext),
+        &source_keypair,
+        (&dest_pubkey, &auditor_pubkey),
+    )
+    .unwrap();
+    let self_transfer_proof_data = TransferData::new(
+        0,
+        (spendable_balance, &spendable_ciphertext),
+        &source_keypair,
+        (&source_keypair.public, &auditor_pubkey),
+    )
+    .unwrap();
+
+    let mut context = ProgramTest::default().start_with_context().await;
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    // verify a transfer proof between distinct pubkeys
+    let instructions = vec![
+        ProofInstruction::VerifyTransfer.encode_verify_proof_with_options(
+            None,
+            &distinct_proof_data,
+            &[VerifyOption::DistinctEndpoints],
+            &[],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    // try to verify a transfer proof from a pubkey to itself
+    let instructions = vec![
+        ProofInstruction::VerifyTransfer.encode_verify_proof_with_options(
+            None,
+            &self_transfer_proof_data,
+            &[VerifyOption::DistinctEndpoints],
+            &[],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+}
+
+#[tokio::test]
+async fn test_verify_proof_with_commitment() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = elgamal_keypair.public.encrypt(0_u64);
+    let committed_proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+    let other_proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+
+    let mut context = ProgramTest::default().start_with_context().await;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    let commitment_account = Keypair::new();
+    let space = size_of::<ProofCommitment>();
+    let reveal_accounts = [
+        AccountMeta::new(commitment_account.pubkey(), false),
+        AccountMeta::new_readonly(payer.pubkey(), true),
+    ];
+
+    // commit to a proof
+    let instructions = vec![
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &commitment_account.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &zk_token_proof_program::id(),
+        ),
+        commit_proof(
+            &commitment_account.pubkey(),
+            &payer.pubkey(),
+            &committed_proof_data,
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &commitment_account],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    let account = client
+        .get_account(commitment_account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let commitment = ProofCommitment::try_from_bytes(&account.data).unwrap();
+    assert_eq!(commitment.committer, payer.pubkey());
+    assert_eq!(
+        commitment.proof_hash,
+        ProofCommitment::hash(&committed_proof_data)
+    );
+
+    // try to reveal a different proof
+    let instructions = vec![
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            None,
+            &other_proof_data,
+            &[VerifyOption::RevealCommitment],
+            &reveal_accounts,
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidInstructionData)
+    );
+
+    // reveal the committed proof
+    let instructions = vec![
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            None,
+            &committed_proof_data,
+            &[VerifyOption::RevealCommitment],
+            &reveal_accounts,
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    // the commitment is consumed
+    let account = client
+        .get_account(commitment_account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(ProofCommitment::try_from_bytes(&account.data).is_err());
+}
+
+#[tokio::test]
+async fn test_verify_proof_with_authority_allowlist() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = elgamal_keypair.public.encrypt(0_u64);
+    let proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+
+    let member_authority = Pubkey::new_unique();
+    let non_member_authority = Pubkey::new_unique();
+    let admin = Keypair::new();
+    let forged_allowlist_account = Pubkey::new_unique();
+
+    // a copy of the allowlist admitting the non-member, at an address not derived from its header
+    let mut program_test = ProgramTest::default();
+    program_test.add_account(
+        forged_allowlist_account,
+        forged_policy_account(
+            &admin.pubkey(),
+            &AUTHORITY_ALLOWLIST_DISCRIMINATOR,
+            0,
+            &AuthorityAllowlist::encode(&[member_authority, non_member_authority]),
+        ),
+    );
+    let mut context = program_test.start_with_context().await;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let allowlist_account = create_policy_account(
+        &mut context,
+        &admin,
+        &AUTHORITY_ALLOWLIST_DISCRIMINATOR,
+        0,
+        &AuthorityAllowlist::encode(&[
+            Pubkey::new_unique(),
+            member_authority,
+            Pubkey::new_unique(),
+        ]),
+    )
+    .await;
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    // the context state records the allowlist in the extended layout
+    let space = size_of::<ExtendedProofContextStateMeta>() + size_of::<CloseAccountProofContext>();
+    let allowlist_accounts = [AccountMeta::new_readonly(allowlist_account, false)];
+
+    // create a proof context state for a member of the allowlist
+    let context_state_account = Keypair::new();
+    let instructions = vec![
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &context_state_account.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &zk_token_proof_program::id(),
+        ),
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            Some(ContextStateInfo {
+                context_state_account: &context_state_account.pubkey(),
+                context_state_authority: &member_authority,
+            }),
+            &proof_data,
+            &[VerifyOption::AuthorityAllowlist],
+            &allowlist_accounts,
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &context_state_account],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    let account = client
+        .get_account(context_state_account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let header = ContextStateHeader::decode(&account.data).unwrap();
+    assert_eq!(
+        header.policies,
+        PROOF_CONTEXT_STATE_POLICY_AUTHORITY_ALLOWLIST
+    );
+    assert_eq!(
+        header.policy_hash,
+        context_state_policy_hash(&[allowlist_account])
+    );
+
+    // a proof context state created without the allowlist records no policy
+    let unscreened_space = size_of::<ProofContextState<CloseAccountProofContext>>();
+    let unscreened_context_state_account = Keypair::new();
+    let instructions = vec![
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &unscreened_context_state_account.pubkey(),
+            rent.minimum_balance(unscreened_space),
+            unscreened_space as u64,
+            &zk_token_proof_program::id(),
+        ),
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof(
+            Some(ContextStateInfo {
+                context_state_account: &unscreened_context_state_account.pubkey(),
+                context_state_authority: &member_authority,
+            }),
+            &proof_data,
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &unscreened_context_state_account],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    let account = client
+        .get_account(unscreened_context_state_account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let header = ContextStateHeader::decode(&account.data).unwrap();
+    assert_eq!(header.policies, 0);
+    assert_eq!(header.policy_hash, [0; 32]);
+
+    // try to create a proof context state for an authority outside the allowlist
+    let context_state_account = Keypair::new();
+    let instructions = vec![
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &context_state_account.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &zk_token_proof_program::id(),
+        ),
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            Some(ContextStateInfo {
+                context_state_account: &context_state_account.pubkey(),
+                context_state_authority: &non_member_authority,
+            }),
+            &proof_data,
+            &[VerifyOption::AuthorityAllowlist],
+            &allowlist_accounts,
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &context_state_account],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(1, InstructionError::InvalidArgument)
+    );
+
+    // the forged allowlist does not admit the non-member
+    let context_state_account = Keypair::new();
+    let instructions = vec![
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &context_state_account.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &zk_token_proof_program::id(),
+        ),
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            Some(ContextStateInfo {
+                context_state_account: &context_state_account.pubkey(),
+                context_state_authority: &non_member_authority,
+            }),
+            &proof_data,
+            &[VerifyOption::AuthorityAllowlist],
+            &[AccountMeta::new_readonly(forged_allowlist_account, false)],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &context_state_account],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(1, InstructionError::InvalidAccountData)
+    );
+
+    // only the admin of the allowlist can update it
+    let updated_allowlist = AuthorityAllowlist::encode(&[member_authority, non_member_authority]);
+    let impostor = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[update_policy_account(
+            &allowlist_account,
+            &payer.pubkey(),
+            &impostor.pubkey(),
+            &updated_allowlist,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, &impostor],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[update_policy_account(
+            &allowlist_account,
+            &payer.pubkey(),
+            &admin.pubkey(),
+            &updated_allowlist,
+        )],
+        Some(&payer.pubkey()),
+        &[payer, &admin],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    // the authority added by the update is admitted
+    let context_state_account = Keypair::new();
+    let instructions = vec![
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &context_state_account.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &zk_token_proof_program::id(),
+        ),
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            Some(ContextStateInfo {
+                context_state_account: &context_state_account.pubkey(),
+                context_state_authority: &non_member_authority,
+            }),
+            &proof_data,
+            &[VerifyOption::AuthorityAllowlist],
+            &allowlist_accounts,
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &context_state_account],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_verify_proof_with_predicate() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = elgamal_keypair.public.encrypt(0_u64);
+    let proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+
+    let predicate_account = Pubkey::new_unique();
+    let mut program_test = ProgramTest::default();
+    program_test.add_account(
+        predicate_account,
+        Account {
+            lamports: 1_000_000_000,
+            data: vec![0, 0, 0, 0, 1, 2, 3, 4],
+            owner: Pubkey::new_unique(),
+            ..Account::default()
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    let space = size_of::<ProofContextState<CloseAccountProofContext>>();
+    let context_state_authority = Pubkey::new_unique();
+    let predicate_accounts = [AccountMeta::new_readonly(predicate_account, false)];
+
+    let mut expected = [0; MAX_PREDICATE_LENGTH];
+    expected[..4].copy_from_slice(&[1, 2, 3, 4]);
+    let matching_predicate = VerifyOption::Predicate {
+        offset: 4,
+        length: 4,
+        expected,
+    };
+    let non_matching_predicate = VerifyOption::Predicate {
+        offset: 3,
+        length: 4,
+        expected,
+    };
+
+    for (predicate, context_created) in
+        [(matching_predicate, true), (non_matching_predicate, false)]
+    {
+        let context_state_account = Keypair::new();
+        let instructions = vec![
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &context_state_account.pubkey(),
+                rent.minimum_balance(space),
+                space as u64,
+                &zk_token_proof_program::id(),
+            ),
+            ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+                Some(ContextStateInfo {
+                    context_state_account: &context_state_account.pubkey(),
+                    context_state_authority: &context_state_authority,
+                }),
+                &proof_data,
+                &[predicate],
+                &predicate_accounts,
+            ),
+        ];
+        let transaction = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&payer.pubkey()),
+            &[payer, &context_state_account],
+            recent_blockhash,
+        );
+        client.process_transaction(transaction).await.unwrap();
+
+        let account = client
+            .get_account(context_state_account.pubkey())
+            .await
+            .unwrap()
+            .unwrap();
+        let proof_context_state = ProofContextStateMeta::try_from_bytes(&account.data).unwrap();
+        let expected_proof_type = if context_created {
+            ProofType::CloseAccount
+        } else {
+            ProofType::Uninitialized
+        };
+        assert_eq!(proof_context_state.proof_type, expected_proof_type.into());
+    }
+}
+
+#[tokio::test]
+async fn test_verify_proof_with_verifier_signature() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = elgamal_keypair.public.encrypt(0_u64);
+    let proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+
+    let incorrect_keypair = ElGamalKeypair {
+        public: ElGamalKeypair::new_rand().public,
+        secret: ElGamalKeypair::new_rand().secret,
+    };
+    let fail_proof_data = CloseAccountData::new(&incorrect_keypair, &zero_ciphertext).unwrap();
+
+    let verifier = ed25519_dalek::Keypair::generate(&mut thread_rng());
+
+    let mut context = ProgramTest::default().start_with_context().await;
+    let verifier_allowlist = create_policy_account(
+        &mut context,
+        &Keypair::new(),
+        &AUTHORITY_ALLOWLIST_DISCRIMINATOR,
+        0,
+        &AuthorityAllowlist::encode(&[Pubkey::new_from_array(verifier.public.to_bytes())]),
+    )
+    .await;
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    let verifier_accounts = [
+        AccountMeta::new_readonly(verifier_allowlist, false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+    ];
+
+    // verify a valid proof signed by the verifier
+    let instructions = vec![
+        new_ed25519_instruction(&verifier, bytes_of(&proof_data)),
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            None,
+            &proof_data,
+            &[VerifyOption::VerifierSignature],
+            &verifier_accounts,
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    // try to verify an invalid proof signed by the verifier
+    let instructions = vec![
+        new_ed25519_instruction(&verifier, bytes_of(&fail_proof_data)),
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            None,
+            &fail_proof_data,
+            &[VerifyOption::VerifierSignature],
+            &verifier_accounts,
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(1, InstructionError::InvalidInstructionData)
+    );
+
+    // try to verify a valid proof whose signature is not over the proof data
+    let instructions = vec![
+        new_ed25519_instruction(&verifier, bytes_of(&fail_proof_data)),
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            None,
+            &proof_data,
+            &[VerifyOption::VerifierSignature],
+            &verifier_accounts,
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(1, InstructionError::InvalidArgument)
+    );
+}
+
+/// Creates a proof context state for `context_state_authority` under the rate limit.
+async fn create_rate_limited_context_state(
+    context: &mut ProgramTestContext,
+    context_state_authority: &Pubkey,
+    rate_limit_accounts: &[AccountMeta],
+) -> Result<(), TransactionError> {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = elgamal_keypair.public.encrypt(0_u64);
+    let proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let space = size_of::<ExtendedProofContextStateMeta>() + size_of::<CloseAccountProofContext>();
+    let context_state_account = Keypair::new();
+
+    let instructions = vec![
+        system_instruction::create_account(
+            &context.payer.pubkey(),
+            &context_state_account.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &zk_token_proof_program::id(),
+        ),
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            Some(ContextStateInfo {
+                context_state_account: &context_state_account.pubkey(),
+                context_state_authority,
+            }),
+            &proof_data,
+            &[VerifyOption::RateLimit],
+            rate_limit_accounts,
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &context_state_account],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+#[tokio::test]
+async fn test_verify_proof_with_rate_limit() {
+    let context_state_authority = Pubkey::new_unique();
+
+    let mut context = ProgramTest::default().start_with_context().await;
+    let admin = Keypair::new();
+    let config_account = create_policy_account(
+        &mut context,
+        &admin,
+        &RATE_LIMIT_CONFIG_DISCRIMINATOR,
+        0,
+        bytes_of(&RateLimitConfig::new(100, 2)),
+    )
+    .await;
+    let state_account = create_policy_account(
+        &mut context,
+        &admin,
+        &RATE_LIMIT_STATE_DISCRIMINATOR,
+        0,
+        bytes_of(&RateLimitState::new(
+            &config_account,
+            &context_state_authority,
+        )),
+    )
+    .await;
+
+    let rate_limit_accounts = [
+        AccountMeta::new_readonly(config_account, false),
+        AccountMeta::new(state_account, false),
+    ];
+
+    // create proof context states up to the limit of the window
+    for _ in 0..2 {
+        create_rate_limited_context_state(
+            &mut context,
+            &context_state_authority,
+            &rate_limit_accounts,
+        )
+        .await
+        .unwrap();
+    }
+
+    // try to create a proof context state over the limit
+    let err = create_rate_limited_context_state(
+        &mut context,
+        &context_state_authority,
+        &rate_limit_accounts,
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(1, InstructionError::InvalidArgument)
+    );
+
+    // the limit resets once the window elapses
+    context.warp_to_slot(200).unwrap();
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    create_rate_limited_context_state(&mut context, &context_state_authority, &rate_limit_accounts)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_verify_proof_with_min_security_level() {
+    let mut context = ProgramTest::default().start_with_context().await;
+    let config_account = create_policy_account(
+        &mut context,
+        &Keypair::new(),
+        &SECURITY_LEVEL_CONFIG_DISCRIMINATOR,
+        0,
+        bytes_of(&SecurityLevelConfig::new(SECURITY_LEVEL_FULL_RANGE)),
+    )
+    .await;
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    let config_accounts = [AccountMeta::new_readonly(config_account, false)];
+
+    // a proof that meets the minimum security level
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let proof_data = PubkeyValidityData::new(&elgamal_keypair).unwrap();
+    let instructions = vec![ProofInstruction::VerifyPubkeyValidity
+        .encode_verify_proof_with_options(
+            None,
+            &proof_data,
+            &[VerifyOption::MinSecurityLevel],
+            &config_accounts,
+        )];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    // a proof with chunked range proofs below the minimum security level
+    let source_keypair = ElGamalKeypair::new_rand();
+    let destination_pubkey = ElGamalKeypair::new_rand().public;
+    let auditor_pubkey = ElGamalKeypair::new_rand().public;
+    let spendable_ciphertext = source_keypair.public.encrypt(120_u64);
+    let proof_data = TransferData::new(
+        55,
+        (120, &spendable_ciphertext),
+        &source_keypair,
+        (&destination_pubkey, &auditor_pubkey),
+    )
+    .unwrap();
+    let instructions = vec![
+        ProofInstruction::VerifyTransfer.encode_verify_proof_with_options(
+            None,
+            &proof_data,
+            &[VerifyOption::MinSecurityLevel],
+            &config_accounts,
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+}
+
+#[tokio::test]
+async fn test_verify_proof_with_key_blacklist() {
+    let blacklisted_pubkey = ElGamalKeypair::new_rand().public;
+    let admin = Keypair::new();
+    let forged_blacklist_account = Pubkey::new_unique();
+
+    // an empty copy of the blacklist at an address not derived from its header
+    let mut program_test = ProgramTest::default();
+    program_test.add_account(
+        forged_blacklist_account,
+        forged_policy_account(&admin.pubkey(), &KEY_BLACKLIST_DISCRIMINATOR, 0, &[]),
+    );
+    let mut context = program_test.start_with_context().await;
+    let blacklist_account = create_policy_account(
+        &mut context,
+        &admin,
+        &KEY_BLACKLIST_DISCRIMINATOR,
+        0,
+        &KeyBlacklist::encode(&[
+            pod::ElGamalPubkey(blacklisted_pubkey.to_bytes()),
+            pod::ElGamalPubkey(ElGamalKeypair::new_rand().public.to_bytes()),
+        ]),
+    )
+    .await;
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    let blacklist_accounts = [AccountMeta::new_readonly(blacklist_account, false)];
+
+    let source_keypair = ElGamalKeypair::new_rand();
+    let auditor_pubkey = ElGamalKeypair::new_rand().public;
+    let spendable_ciphertext = source_keypair.public.encrypt(120_u64);
+
+    // a transfer between keys that are not blacklisted
+    let destination_pubkey = ElGamalKeypair::new_rand().public;
+    let proof_data = TransferData::new(
+        55,
+        (120, &spendable_ciphertext),
+        &source_keypair,
+        (&destination_pubkey, &auditor_pubkey),
+    )
+    .unwrap();
+    let instructions = vec![
+        ProofInstruction::VerifyTransfer.encode_verify_proof_with_options(
+            None,
+            &proof_data,
+            &[VerifyOption::KeyBlacklist],
+            &blacklist_accounts,
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    // a transfer to a blacklisted key
+    let proof_data = TransferData::new(
+        55,
+        (120, &spendable_ciphertext),
+        &source_keypair,
+        (&blacklisted_pubkey, &auditor_pubkey),
+    )
+    .unwrap();
+    let instructions = vec![
+        ProofInstruction::VerifyTransfer.encode_verify_proof_with_options(
+            None,
+            &proof_data,
+            &[VerifyOption::KeyBlacklist],
+            &blacklist_accounts,
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+
+    // the transfer to the blacklisted key is not let through by the forged blacklist
+    let instructions = vec![
+        ProofInstruction::VerifyTransfer.encode_verify_proof_with_options(
+            None,
+            &proof_data,
+            &[VerifyOption::KeyBlacklist],
+            &[AccountMeta::new_readonly(forged_blacklist_account, false)],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+}
+
+#[tokio::test]
+async fn test_verify_proof_with_route() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = elgamal_keypair.public.encrypt(0_u64);
+    let close_account_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+    let pubkey_validity_data = PubkeyValidityData::new(&elgamal_keypair).unwrap();
+
+    let mut context = ProgramTest::default().start_with_context().await;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    let context_state_authority = Keypair::new();
+    let close_account_context_state = Keypair::new();
+    let pubkey_validity_context_state = Keypair::new();
+    let close_account_space = size_of::<ProofContextState<CloseAccountProofContext>>();
+    let pubkey_validity_space = size_of::<ProofContextState<PubkeyValidityProofContext>>();
+
+    let candidates = [
+        (
+            ProofType::PubkeyValidity,
+            &pubkey_validity_context_state.pubkey(),
+        ),
+        (
+            ProofType::CloseAccount,
+            &close_account_context_state.pubkey(),
+        ),
+    ];
+
+    // a close account proof is routed to the close account candidate
+    let instructions = vec![
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &close_account_context_state.pubkey(),
+            rent.minimum_balance(close_account_space),
+            close_account_space as u64,
+            &zk_token_proof_program::id(),
+        ),
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &pubkey_validity_context_state.pubkey(),
+            rent.minimum_balance(pubkey_validity_space),
+            pubkey_validity_space as u64,
+            &zk_token_proof_program::id(),
+        ),
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_route(
+            &context_state_authority.pubkey(),
+            &candidates,
+            &close_account_data,
+            &[],
+            &[],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[
+            payer,
+            &close_account_context_state,
+            &pubkey_validity_context_state,
+        ],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    let account = client
+        .get_account(close_account_context_state.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let context_state =
+        ProofContextState::<CloseAccountProofContext>::decode(&account.data).unwrap();
+    assert_eq!(
+        context_state.context_state_authority,
+        context_state_authority.pubkey()
+    );
+    assert_eq!(
+        bytes_of(&context_state.proof_context),
+        bytes_of(close_account_data.context_data())
+    );
+
+    // the other candidate is left uninitialized
+    let account = client
+        .get_account(pubkey_validity_context_state.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(account.data.iter().all(|byte| *byte == 0));
+
+    // a proof without a candidate of its type is rejected
+    let instructions = vec![
+        ProofInstruction::VerifyPubkeyValidity.encode_verify_proof_with_route(
+            &context_state_authority.pubkey(),
+            &candidates[1..],
+            &pubkey_validity_data,
+            &[],
+            &[],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+}
+
+#[tokio::test]
+async fn test_close_context_state_with_delegate() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = elgamal_keypair.public.encrypt(0_u64);
+    let proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+
+    let mut context = ProgramTest::default().start_with_context().await;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    let context_state_authority = Keypair::new();
+    let context_state_delegate = Keypair::new();
+    let destination_account = Keypair::new();
+    let space = size_of::<ExtendedProofContextStateMeta>() + size_of::<CloseAccountProofContext>();
+
+    let delegate_accounts = [AccountMeta::new_readonly(
+        context_state_delegate.pubkey(),
+        false,
+    )];
+
+    // create two proof context states with a delegate
+    let first_context_state_account = Keypair::new();
+    let second_context_state_account = Keypair::new();
+    for context_state_account in [&first_context_state_account, &second_context_state_account] {
+        let instructions = vec![
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &context_state_account.pubkey(),
+                rent.minimum_balance(space),
+                space as u64,
+                &zk_token_proof_program::id(),
+            ),
+            ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+                Some(ContextStateInfo {
+                    context_state_account: &context_state_account.pubkey(),
+                    context_state_authority: &context_state_authority.pubkey(),
+                }),
+                &proof_data,
+                &[VerifyOption::Delegate],
+                &delegate_accounts,
+            ),
+        ];
+        let transaction = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&payer.pubkey()),
+            &[payer, context_state_account],
+            recent_blockhash,
+        );
+        client.process_transaction(transaction).await.unwrap();
+    }
+
+    // try to close a proof context state with an unrelated signer
+    let unrelated_signer = Keypair::new();
+    let instruction = close_context_state(
+        ContextStateInfo {
+            context_state_account: &first_context_state_account.pubkey(),
+            context_state_authority: &unrelated_signer.pubkey(),
+        },
+        &destination_account.pubkey(),
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer.pubkey()),
+        &[payer, &unrelated_signer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountOwner)
+    );
+
+    // close a proof context state with the delegate
+    let instruction = close_context_state(
+        ContextStateInfo {
+            context_state_account: &first_context_state_account.pubkey(),
+            context_state_authority: &context_state_delegate.pubkey(),
+        },
+        &destination_account.pubkey(),
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer.pubkey()),
+        &[payer, &context_state_delegate],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    // close a proof context state with the owner
+    let instruction = close_context_state(
+        ContextStateInfo {
+            context_state_account: &second_context_state_account.pubkey(),
+            context_state_authority: &context_state_authority.pubkey(),
+        },
+        &destination_account.pubkey(),
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer.pubkey()),
+        &[payer, &context_state_authority],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_verify_proof_into_initialized_context_state() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = elgamal_keypair.public.encrypt(0_u64);
+    let proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+
+    // an account in the default layout whose proof type is unset is uninitialized, whatever its
+    // other bytes hold
+    let stale_context_state_account = Pubkey::new_unique();
+    let space = size_of::<ProofContextState<CloseAccountProofContext>>();
+    let mut stale_data = vec![1; space];
+    stale_data[size_of::<Pubkey>()] = ProofType::Uninitialized as u8;
+
+    let mut program_test = ProgramTest::default();
+    program_test.add_account(
+        stale_context_state_account,
+        Account {
+            lamports: 1_000_000_000,
+            data: stale_data,
+            owner: zk_token_proof_program::id(),
+            ..Account::default()
+        },
+    );
+    let mut context = program_test.start_with_context().await;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    let context_state_authority = Keypair::new();
+    let instruction = verify_close_account(
+        Some(ContextStateInfo {
+            context_state_account: &stale_context_state_account,
+            context_state_authority: &context_state_authority.pubkey(),
+        }),
+        &proof_data,
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    // a context state in the extended layout cannot be overwritten
+    let context_state_account = Keypair::new();
+    let context_state_info = ContextStateInfo {
+        context_state_account: &context_state_account.pubkey(),
+        context_state_authority: &context_state_authority.pubkey(),
+    };
+    let space = size_of::<ExtendedProofContextStateMeta>() + size_of::<CloseAccountProofContext>();
+    let delegate_accounts = [AccountMeta::new_readonly(Pubkey::new_unique(), false)];
+    let verify_instruction = ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+        Some(context_state_info),
+        &proof_data,
+        &[VerifyOption::Delegate],
+        &delegate_accounts,
+    );
+    let instructions = vec![
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &context_state_account.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &zk_token_proof_program::id(),
+        ),
+        verify_instruction.clone(),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &context_state_account],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[verify_instruction],
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)
+    );
+}
+
+/// Returns an initialized SPL Token multisig account requiring `m` of `signers`.
+fn spl_multisig_account(m: u8, signers: &[Pubkey]) -> Account {
+    let mut data = vec![m, signers.len() as u8, 1];
+    for signer in signers {
+        data.extend_from_slice(signer.as_ref());
+    }
+    data.resize(355, 0);
+
+    Account {
+        lamports: 1_000_000_000,
+        data,
+        owner: Pubkey::from_str("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA").unwrap(),
+        ..Account::default()
+    }
+}
+
+#[tokio::test]
+async fn test_close_context_state_with_multisig() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = elgamal_keypair.public.encrypt(0_u64);
+    let proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+
+    let multisig_signers = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let multisig_signer_pubkeys: Vec<_> = multisig_signers.iter().map(|s| s.pubkey()).collect();
+    let multisig_authority = Pubkey::new_unique();
+
+    let mut program_test = ProgramTest::default();
+    program_test.add_account(
+        multisig_authority,
+        spl_multisig_account(2, &multisig_signer_pubkeys),
+    );
+    let mut context = program_test.start_with_context().await;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    let context_state_account = Keypair::new();
+    let context_state_info = ContextStateInfo {
+        context_state_account: &context_state_account.pubkey(),
+        context_state_authority: &multisig_authority,
+    };
+    let destination_account = Keypair::new();
+    let space = size_of::<ProofContextState<CloseAccountProofContext>>();
+
+    // create a proof context state owned by the multisig
+    let instructions = vec![
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &context_state_account.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &zk_token_proof_program::id(),
+        ),
+        verify_close_account(Some(context_state_info), &proof_data),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &context_state_account],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    // try to close the proof context state with fewer signers than the threshold
+    let instruction = close_context_state_with_multisig(
+        context_state_info,
+        &destination_account.pubkey(),
+        &[&multisig_signer_pubkeys[0]],
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer.pubkey()),
+        &[payer, &multisig_signers[0]],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+
+    // try to close the proof context state with signers that are not of the multisig
+    let unrelated_signer = Keypair::new();
+    let instruction = close_context_state_with_multisig(
+        context_state_info,
+        &destination_account.pubkey(),
+        &[&multisig_signer_pubkeys[0], &unrelated_signer.pubkey()],
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer.pubkey()),
+        &[payer, &multisig_signers[0], &unrelated_signer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+
+    // close the proof context state with the threshold of signers
+    let instruction = close_context_state_with_multisig(
+        context_state_info,
+        &destination_account.pubkey(),
+        &[&multisig_signer_pubkeys[0], &multisig_signer_pubkeys[2]],
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer.pubkey()),
+        &[payer, &multisig_signers[0], &multisig_signers[2]],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    let account = client
+        .get_account(context_state_account.pubkey())
+        .await
+        .unwrap();
+    assert!(account.is_none());
+}
+
+// Closes the proof context account as the context state authority in the third account
+fn process_close_as_authority(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _input: &[u8],
+) -> ProgramResult {
+    let instruction = close_context_state_by_program(
+        ContextStateInfo {
+            context_state_account: accounts[0].key,
+            context_state_authority: accounts[2].key,
+        },
+        accounts[1].key,
+    );
+    invoke(&instruction, accounts)
+}
+
+#[tokio::test]
+async fn test_close_context_state_by_program() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = elgamal_keypair.public.encrypt(0_u64);
+    let proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+
+    let authority_program_id = Pubkey::new_unique();
+    let other_program_id = Pubkey::new_unique();
+
+    let mut program_test = ProgramTest::new(
+        "close_as_authority",
+        authority_program_id,
+        processor!(process_close_as_authority),
+    );
+    program_test.add_program(
+        "other_close_as_authority",
+        other_program_id,
+        processor!(process_close_as_authority),
+    );
+    let mut context = program_test.start_with_context().await;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    // create a proof context state whose authority is the program
+    let context_state_account = Keypair::new();
+    let space = size_of::<ProofContextState<CloseAccountProofContext>>();
+    let instructions = vec![
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &context_state_account.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &zk_token_proof_program::id(),
+        ),
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof(
+            Some(ContextStateInfo {
+                context_state_account: &context_state_account.pubkey(),
+                context_state_authority: &authority_program_id,
+            }),
+            &proof_data,
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &context_state_account],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    let destination_account = Pubkey::new_unique();
+    let accounts = vec![
+        AccountMeta::new(context_state_account.pubkey(), false),
+        AccountMeta::new(destination_account, false),
+        AccountMeta::new_readonly(authority_program_id, false),
+        AccountMeta::new_readonly(zk_token_proof_program::id(), false),
+    ];
+
+    // a program other than the authority cannot close the proof context state
+    let instructions = vec![Instruction {
+        program_id: other_program_id,
+        accounts: accounts.clone(),
+        data: vec![],
+    }];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+
+    // the authority program closes the proof context state without a signature
+    let instructions = vec![Instruction {
+        program_id: authority_program_id,
+        accounts,
+        data: vec![],
+    }];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    assert!(client
+        .get_account(context_state_account.pubkey())
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        client
+            .get_account(destination_account)
+            .await
+            .unwrap()
+            .unwrap()
+            .lamports,
+        rent.minimum_balance(space)
+    );
+}
+
+// Relays the instruction data to the proof program with all accounts but the proof program
+fn process_relay_to_proof_program(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction {
+        program_id: zk_token_proof_program::id(),
+        accounts: accounts
+            .iter()
+            .filter(|account| *account.key != zk_token_proof_program::id())
+            .map(|account| AccountMeta {
+                pubkey: *account.key,
+                is_signer: account.is_signer,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data: input.to_vec(),
+    };
+    invoke(&instruction, accounts)
+}
+
+#[tokio::test]
+async fn test_verify_proof_with_program_managed_context() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = elgamal_keypair.public.encrypt(0_u64);
+    let proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+
+    let controlling_program_id = Pubkey::new_unique();
+    let other_program_id = Pubkey::new_unique();
+
+    let mut program_test = ProgramTest::new(
+        "relay_to_proof_program",
+        controlling_program_id,
+        processor!(process_relay_to_proof_program),
+    );
+    program_test.add_program(
+        "other_relay_to_proof_program",
+        other_program_id,
+        processor!(process_relay_to_proof_program),
+    );
+    let mut context = program_test.start_with_context().await;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
+
+    let id = 7;
+    let (context_state_address, _) =
+        find_program_managed_context_address(&controlling_program_id, id);
+    let verify_instruction = ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+        None,
+        &proof_data,
+        &[VerifyOption::ProgramManaged(id)],
+        &[
+            AccountMeta::new(context_state_address, false),
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    );
+
+    // a program-managed context cannot be created without an invoking program
+    let transaction = Transaction::new_signed_with_payer(
+        &[verify_instruction.clone()],
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+
+    // nor by a program whose program-managed address it is not
+    let mut relayed_accounts = verify_instruction.accounts.clone();
+    relayed_accounts.push(AccountMeta::new_readonly(
+        zk_token_proof_program::id(),
+        false,
+    ));
+    let transaction = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: other_program_id,
+            accounts: relayed_accounts.clone(),
+            data: verify_instruction.data.clone(),
+        }],
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+
+    // a program cannot invoke a verify instruction without a program-managed context state
+    let relayed_instruction =
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof(None, &proof_data);
+    let transaction = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: controlling_program_id,
+            accounts: vec![AccountMeta::new_readonly(
+                zk_token_proof_program::id(),
+                false,
+            )],
+            data: relayed_instruction.data,
+        }],
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::UnsupportedProgramId)
+    );
+
+    // the controlling program creates the program-managed context state
+    let transaction = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: controlling_program_id,
+            accounts: relayed_accounts,
+            data: verify_instruction.data.clone(),
+        }],
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    let account = client
+        .get_account(context_state_address)
+        .await
+        .unwrap()
+        .unwrap();
+    let space = size_of::<ProofContextState<CloseAccountProofContext>>();
+    assert_eq!(account.owner, zk_token_proof_program::id());
+    assert_eq!(account.lamports, rent.minimum_balance(space));
+    let context_state =
+        ProofContextState::<CloseAccountProofContext>::decode(&account.data).unwrap();
+    assert_eq!(
+        context_state.context_state_authority,
+        controlling_program_id
+    );
+    assert_eq!(
+        bytes_of(&context_state.proof_context),
+        bytes_of(proof_data.context_data())
+    );
+
+    let destination_account = Pubkey::new_unique();
+    let close_instruction = close_context_state_by_program(
+        ContextStateInfo {
+            context_state_account: &context_state_address,
+            context_state_authority: &controlling_program_id,
+        },
+        &destination_account,
+    );
+
+    // the proof context state cannot be closed without the controlling program
+    let transaction = Transaction::new_signed_with_payer(
+        &[close_instruction.clone()],
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+
+    let mut relayed_accounts = close_instruction.accounts.clone();
+    relayed_accounts.push(AccountMeta::new_readonly(
+        zk_token_proof_program::id(),
+        false,
+    ));
+
+    // nor by another program
+    let transaction = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: other_program_id,
+            accounts: relayed_accounts.clone(),
+            data: close_instruction.data.clone(),
+        }],
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+
+    // the controlling program closes the proof context state
+    let transaction = Transaction::new_signed_with_payer(
+        &[Instruction {
+            program_id: controlling_program_id,
+            accounts: relayed_accounts,
+            data: close_instruction.data,
+        }],
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    assert!(client
+        .get_account(context_state_address)
+        .await
+        .unwrap()
+        .is_none());
+
+    // a program-managed address that already holds lamports is topped up to its rent-exempt
+    // balance instead of failing to be created
+    let prefunded_lamports = rent.minimum_balance(space) / 2;
+    let mut relayed_accounts = verify_instruction.accounts.clone();
+    relayed_accounts.push(AccountMeta::new_readonly(
+        zk_token_proof_program::id(),
+        false,
+    ));
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::transfer(
+                &payer.pubkey(),
+                &context_state_address,
+                prefunded_lamports,
+            ),
+            Instruction {
+                program_id: controlling_program_id,
+                accounts: relayed_accounts,
+                data: verify_instruction.data,
+            },
+        ],
+        Some(&payer.pubkey()),
+        &[payer],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
+
+    let account = client
+        .get_account(context_state_address)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.owner, zk_token_proof_program::id());
+    assert_eq!(account.lamports, rent.minimum_balance(space));
+    let context_state =
+        ProofContextState::<CloseAccountProofContext>::decode(&account.data).unwrap();
+    assert_eq!(
+        context_state.context_state_authority,
+        controlling_program_id
+    );
 }
 
 #[tokio::test]
-async fn test_withdraw() {
+async fn test_sweep_context_states() {
     let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = elgamal_keypair.public.encrypt(0_u64);
+    let proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
 
-    let current_balance: u64 = 77;
-    let current_ciphertext = elgamal_keypair.public.encrypt(current_balance);
-    let withdraw_amount: u64 = 55;
+    let mut context = ProgramTest::default().start_with_context().await;
+    let rent = context.banks_client.get_rent().await.unwrap();
 
-    let success_proof_data = WithdrawData::new(
-        withdraw_amount,
-        &elgamal_keypair,
-        current_balance,
-        &current_ciphertext,
-    )
-    .unwrap();
+    let client = &mut context.banks_client;
+    let payer = &context.payer;
+    let recent_blockhash = context.last_blockhash;
 
-    let incorrect_keypair = ElGamalKeypair {
-        public: ElGamalKeypair::new_rand().public,
-        secret: ElGamalKeypair::new_rand().secret,
-    };
-    let fail_proof_data = WithdrawData::new(
-        withdraw_amount,
-        &incorrect_keypair,
-        current_balance,
-        &current_ciphertext,
-    )
-    .unwrap();
+    let space = size_of::<ProofContextState<CloseAccountProofContext>>();
+    let context_state_authority = Keypair::new();
+    let other_authority = Pubkey::new_unique();
+    let destination_account = Pubkey::new_unique();
 
-    test_verify_proof_without_context(
-        ProofInstruction::VerifyWithdraw,
-        &success_proof_data,
-        &fail_proof_data,
-    )
-    .await;
+    // create two proof context states of the authority and one of another authority
+    let context_state_accounts = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let authorities = [
+        context_state_authority.pubkey(),
+        other_authority,
+        context_state_authority.pubkey(),
+    ];
+    for (context_state_account, authority) in context_state_accounts.iter().zip(&authorities) {
+        let instructions = vec![
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &context_state_account.pubkey(),
+                rent.minimum_balance(space),
+                space as u64,
+                &zk_token_proof_program::id(),
+            ),
+            ProofInstruction::VerifyCloseAccount.encode_verify_proof(
+                Some(ContextStateInfo {
+                    context_state_account: &context_state_account.pubkey(),
+                    context_state_authority: authority,
+                }),
+                &proof_data,
+            ),
+        ];
+        let transaction = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&payer.pubkey()),
+            &[payer, context_state_account],
+            recent_blockhash,
+        );
+        client.process_transaction(transaction).await.unwrap();
+    }
 
-    test_verify_proof_with_context(
-        ProofInstruction::VerifyWithdraw,
-        size_of::<ProofContextState<WithdrawProofContext>>(),
-        &success_proof_data,
-        &fail_proof_data,
-    )
-    .await;
+    // sweep the accounts along with an account that is not a proof context account
+    let mut swept_accounts: Vec<Pubkey> = context_state_accounts
+        .iter()
+        .map(|context_state_account| context_state_account.pubkey())
+        .collect();
+    swept_accounts.push(payer.pubkey());
+    let instruction = sweep_context_states(
+        &destination_account,
+        &context_state_authority.pubkey(),
+        &swept_accounts,
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&payer.pubkey()),
+        &[payer, &context_state_authority],
+        recent_blockhash,
+    );
+    client.process_transaction(transaction).await.unwrap();
 
-    test_close_context_state(
-        ProofInstruction::VerifyWithdraw,
-        size_of::<ProofContextState<WithdrawProofContext>>(),
-        &success_proof_data,
-    )
-    .await;
+    for (context_state_account, authority) in context_state_accounts.iter().zip(&authorities) {
+        let account = client
+            .get_account(context_state_account.pubkey())
+            .await
+            .unwrap();
+        if *authority == context_state_authority.pubkey() {
+            assert!(account.is_none());
+        } else {
+            assert!(account.is_some());
+        }
+    }
+
+    let destination = client
+        .get_account(destination_account)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        destination.lamports,
+        rent.minimum_balance(space).checked_mul(2).unwrap()
+    );
 }
 
 #[tokio::test]
-async fn test_pubkey_validity() {
+async fn test_verify_proof_with_min_epoch() {
     let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = elgamal_keypair.public.encrypt(0_u64);
 
-    let success_proof_data = PubkeyValidityData::new(&elgamal_keypair).unwrap();
-
-    let incorrect_keypair = ElGamalKeypair {
-        public: ElGamalKeypair::new_rand().public,
-        secret: ElGamalKeypair::new_rand().secret,
-    };
+    let mut context = ProgramTest::default().start_with_context().await;
+    let mut clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    let min_epoch = clock.epoch.checked_add(5).unwrap();
 
-    let fail_proof_data = PubkeyValidityData::new(&incorrect_keypair).unwrap();
+    // try to verify a proof before the minimum epoch
+    clock.epoch = min_epoch.checked_sub(1).unwrap();
+    context.set_sysvar(&clock);
 
-    test_verify_proof_without_context(
-        ProofInstruction::VerifyPubkeyValidity,
-        &success_proof_data,
-        &fail_proof_data,
-    )
-    .await;
+    let proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+    let instructions = vec![
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            None,
+            &proof_data,
+            &[VerifyOption::MinEpoch(min_epoch)],
+            &[],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
 
-    test_verify_proof_with_context(
-        ProofInstruction::VerifyPubkeyValidity,
-        size_of::<ProofContextState<PubkeyValidityProofContext>>(),
-        &success_proof_data,
-        &fail_proof_data,
-    )
-    .await;
+    // verify a proof in the minimum epoch
+    clock.epoch = min_epoch;
+    context.set_sysvar(&clock);
 
-    test_close_context_state(
-        ProofInstruction::VerifyPubkeyValidity,
-        size_of::<ProofContextState<PubkeyValidityProofContext>>(),
-        &success_proof_data,
-    )
-    .await;
+    let proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+    let instructions = vec![
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            None,
+            &proof_data,
+            &[VerifyOption::MinEpoch(min_epoch)],
+            &[],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
 }
 
 async fn test_verify_proof_without_context<T, U>(
@@ -491,6 +5109,36 @@ async fn test_verify_proof_with_context<T, U>(
         TransactionError::InsufficientFundsForRent { account_index: 1 },
     );
 
+    // try to create proof context state without the context state authority account
+    let mut verify_instruction =
+        instruction_type.encode_verify_proof(Some(context_state_info), success_proof_data);
+    verify_instruction.accounts.truncate(1);
+    let instructions = vec![
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &context_state_account.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &zk_token_proof_program::id(),
+        ),
+        verify_instruction,
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer, &context_state_account],
+        recent_blockhash,
+    );
+    let err = client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(1, InstructionError::NotEnoughAccountKeys)
+    );
+
     // try to create proof context state with an invalid `ProofType`
     for wrong_instruction_type in VERIFY_INSTRUCTION_TYPES {
         if instruction_type == wrong_instruction_type {
@@ -786,3 +5434,194 @@ async fn test_close_context_state<T, U>(
     );
     client.process_transaction(transaction).await.unwrap();
 }
+
+#[tokio::test]
+async fn test_verify_proof_with_retry_nonce() {
+    let elgamal_keypair = ElGamalKeypair::new_rand();
+    let zero_ciphertext = elgamal_keypair.public.encrypt(0_u64);
+    let proof_data = CloseAccountData::new(&elgamal_keypair, &zero_ciphertext).unwrap();
+
+    let tracker_authority = Keypair::new();
+
+    let mut context = ProgramTest::default().start_with_context().await;
+    let tracker_account = create_policy_account(
+        &mut context,
+        &tracker_authority,
+        &RETRY_NONCE_TRACKER_DISCRIMINATOR,
+        0,
+        bytes_of(&RetryNonceTracker::new()),
+    )
+    .await;
+    let rent = context.banks_client.get_rent().await.unwrap();
+
+    let space = size_of::<ProofContextState<CloseAccountProofContext>>();
+    let context_state_account = Keypair::new();
+    let context_state_authority = Pubkey::new_unique();
+    let context_state_info = ContextStateInfo {
+        context_state_account: &context_state_account.pubkey(),
+        context_state_authority: &context_state_authority,
+    };
+    let verify_instruction = ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+        Some(context_state_info),
+        &proof_data,
+        &[VerifyOption::RetryNonce(7)],
+        &[
+            AccountMeta::new(tracker_account, false),
+            AccountMeta::new_readonly(tracker_authority.pubkey(), true),
+        ],
+    );
+
+    // a fresh nonce is recorded and the proof is verified and charged as usual
+    let instructions = vec![
+        system_instruction::create_account(
+            &context.payer.pubkey(),
+            &context_state_account.pubkey(),
+            rent.minimum_balance(space),
+            space as u64,
+            &zk_token_proof_program::id(),
+        ),
+        verify_instruction.clone(),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &context_state_account, &tracker_authority],
+        context.last_blockhash,
+    );
+    let result = context
+        .banks_client
+        .process_transaction_with_metadata(transaction)
+        .await
+        .unwrap();
+    assert_eq!(result.result, Ok(()));
+    assert!(
+        result.metadata.unwrap().compute_units_consumed
+            >= compute_units(ProofInstruction::VerifyCloseAccount)
+    );
+
+    let context_state = context
+        .banks_client
+        .get_account(context_state_account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let tracker = context
+        .banks_client
+        .get_account(tracker_account)
+        .await
+        .unwrap()
+        .unwrap();
+    let (_, nonces) =
+        PolicyAccountHeader::split(&tracker.data, &RETRY_NONCE_TRACKER_DISCRIMINATOR).unwrap();
+    assert!(RetryNonceTracker::try_from_bytes(nonces)
+        .unwrap()
+        .contains(7));
+
+    // a retry of the instruction, in a differently signed transaction, succeeds as a cheap no-op
+    // even though the proof context account is already initialized
+    let recent_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[verify_instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &tracker_authority],
+        recent_blockhash,
+    );
+    let result = context
+        .banks_client
+        .process_transaction_with_metadata(transaction)
+        .await
+        .unwrap();
+    assert_eq!(result.result, Ok(()));
+    assert!(
+        result.metadata.unwrap().compute_units_consumed
+            < compute_units(ProofInstruction::VerifyCloseAccount)
+    );
+
+    assert_eq!(
+        context
+            .banks_client
+            .get_account(context_state_account.pubkey())
+            .await
+            .unwrap()
+            .unwrap(),
+        context_state
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .get_account(tracker_account)
+            .await
+            .unwrap()
+            .unwrap(),
+        tracker
+    );
+
+    // a replay of the nonce with different proof data, or with the same proof data but different
+    // accounts, fails instead of being skipped
+    let mut garbage_proof_data = proof_data;
+    bytemuck::bytes_of_mut(&mut garbage_proof_data)[64] ^= 1;
+    let other_context_state_account = Pubkey::new_unique();
+    for (context_state_account, proof_data) in [
+        (context_state_account.pubkey(), &garbage_proof_data),
+        (other_context_state_account, &proof_data),
+    ] {
+        let instructions = vec![ProofInstruction::VerifyCloseAccount
+            .encode_verify_proof_with_options(
+                Some(ContextStateInfo {
+                    context_state_account: &context_state_account,
+                    context_state_authority: &context_state_authority,
+                }),
+                proof_data,
+                &[VerifyOption::RetryNonce(7)],
+                &[
+                    AccountMeta::new(tracker_account, false),
+                    AccountMeta::new_readonly(tracker_authority.pubkey(), true),
+                ],
+            )];
+        let transaction = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &tracker_authority],
+            recent_blockhash,
+        );
+        let err = context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+        );
+    }
+
+    // the nonce can only be recorded by the admin of the tracker
+    let instructions = vec![
+        ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            None,
+            &proof_data,
+            &[VerifyOption::RetryNonce(8)],
+            &[
+                AccountMeta::new(tracker_account, false),
+                AccountMeta::new_readonly(context.payer.pubkey(), true),
+            ],
+        ),
+    ];
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        recent_blockhash,
+    );
+    let err = context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap_err()
+        .unwrap();
+    assert_eq!(
+        err,
+        TransactionError::InstructionError(0, InstructionError::InvalidAccountData)
+    );
+}
@@ -0,0 +1,50 @@
+/// Partial SPL Token declarations inlined to avoid an external dependency on the spl-token crate
+use solana_sdk::pubkey::{Pubkey, PUBKEY_BYTES};
+
+solana_sdk::declare_id!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+
+/*
+    spl_token::state::Multisig {
+        m: u8,
+        n: u8,
+        is_initialized: bool,
+        signers: [Pubkey; MAX_SIGNERS],
+    }
+*/
+const SPL_TOKEN_MULTISIG_MAX_SIGNERS: usize = 11;
+const SPL_TOKEN_MULTISIG_SIGNERS_OFFSET: usize = 3;
+const SPL_TOKEN_MULTISIG_LENGTH: usize = 355;
+
+/// The fields of a multisig account that a multisig authority check reads
+pub struct Multisig {
+    /// The number of signers required
+    pub m: u8,
+    /// The valid signers
+    pub signers: Vec<Pubkey>,
+}
+
+/// Unpack the data of an initialized multisig account, returning `None` if the data is not a
+/// valid multisig. Multisig accounts have the same layout in SPL Token and SPL Token 2022.
+pub fn unpack_multisig(account_data: &[u8]) -> Option<Multisig> {
+    if account_data.len() != SPL_TOKEN_MULTISIG_LENGTH {
+        return None;
+    }
+
+    let (m, n, is_initialized) = match account_data {
+        [m, n, is_initialized, ..] => (*m, *n, *is_initialized),
+        _ => return None,
+    };
+    if is_initialized != 1 || m == 0 || m > n || usize::from(n) > SPL_TOKEN_MULTISIG_MAX_SIGNERS {
+        return None;
+    }
+
+    let signers = account_data
+        .get(SPL_TOKEN_MULTISIG_SIGNERS_OFFSET..)?
+        .chunks_exact(PUBKEY_BYTES)
+        .take(usize::from(n))
+        .map(Pubkey::try_from)
+        .collect::<Result<_, _>>()
+        .ok()?;
+
+    Some(Multisig { m, signers })
+}
@@ -0,0 +1,86 @@
+/// Partial SPL Token 2022 declarations inlined to avoid an external dependency on the
+/// spl-token-2022 crate
+use solana_zk_token_sdk::zk_token_elgamal::pod;
+
+solana_sdk::declare_id!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+const SPL_TOKEN_ACCOUNT_LENGTH: usize = 165;
+
+// `spl_token_2022::extension::AccountType::Account` ordinal value
+const ACCOUNTTYPE_ACCOUNT: u8 = 2;
+
+// `spl_token_2022::extension::ExtensionType::ConfidentialTransferAccount` ordinal value
+const EXTENSIONTYPE_CONFIDENTIAL_TRANSFER_ACCOUNT: u16 = 5;
+
+/*
+    spl_token_2022::extension::confidential_transfer::ConfidentialTransferAccount {
+        approved: PodBool,
+        elgamal_pubkey: ElGamalPubkey,
+        pending_balance_lo: ElGamalCiphertext,
+        pending_balance_hi: ElGamalCiphertext,
+        ...
+    }
+*/
+const CONFIDENTIAL_TRANSFER_ACCOUNT_ELGAMAL_PUBKEY_OFFSET: usize = 1;
+const CONFIDENTIAL_TRANSFER_ACCOUNT_PENDING_BALANCE_LO_OFFSET: usize = 33;
+const CONFIDENTIAL_TRANSFER_ACCOUNT_PENDING_BALANCE_HI_OFFSET: usize = 97;
+const CONFIDENTIAL_TRANSFER_ACCOUNT_AVAILABLE_BALANCE_OFFSET: usize = 161;
+
+/// The fields of a token account's confidential transfer extension that a transfer credits
+pub struct ConfidentialTransferAccount {
+    pub elgamal_pubkey: pod::ElGamalPubkey,
+    pub pending_balance_lo: pod::ElGamalCiphertext,
+    pub pending_balance_hi: pod::ElGamalCiphertext,
+}
+
+/// Find the confidential transfer extension in the data of a token account, returning `None` if
+/// the data is not a token account or the account does not have the extension.
+pub fn unpack_confidential_transfer_account(
+    account_data: &[u8],
+) -> Option<ConfidentialTransferAccount> {
+    if *account_data.get(SPL_TOKEN_ACCOUNT_LENGTH)? != ACCOUNTTYPE_ACCOUNT {
+        return None;
+    }
+
+    // the extensions follow the account type as (type: u16, length: u16, value) entries
+    let mut tlv_data = account_data.get(SPL_TOKEN_ACCOUNT_LENGTH.saturating_add(1)..)?;
+    while tlv_data.len() >= 4 {
+        let extension_type = u16::from_le_bytes([tlv_data[0], tlv_data[1]]);
+        let length = usize::from(u16::from_le_bytes([tlv_data[2], tlv_data[3]]));
+        let value = tlv_data.get(4..length.saturating_add(4))?;
+
+        if extension_type == EXTENSIONTYPE_CONFIDENTIAL_TRANSFER_ACCOUNT {
+            return Some(ConfidentialTransferAccount {
+                elgamal_pubkey: pod::ElGamalPubkey(
+                    value
+                        .get(
+                            CONFIDENTIAL_TRANSFER_ACCOUNT_ELGAMAL_PUBKEY_OFFSET
+                                ..CONFIDENTIAL_TRANSFER_ACCOUNT_PENDING_BALANCE_LO_OFFSET,
+                        )?
+                        .try_into()
+                        .ok()?,
+                ),
+                pending_balance_lo: pod::ElGamalCiphertext(
+                    value
+                        .get(
+                            CONFIDENTIAL_TRANSFER_ACCOUNT_PENDING_BALANCE_LO_OFFSET
+                                ..CONFIDENTIAL_TRANSFER_ACCOUNT_PENDING_BALANCE_HI_OFFSET,
+                        )?
+                        .try_into()
+                        .ok()?,
+                ),
+                pending_balance_hi: pod::ElGamalCiphertext(
+                    value
+                        .get(
+                            CONFIDENTIAL_TRANSFER_ACCOUNT_PENDING_BALANCE_HI_OFFSET
+                                ..CONFIDENTIAL_TRANSFER_ACCOUNT_AVAILABLE_BALANCE_OFFSET,
+                        )?
+                        .try_into()
+                        .ok()?,
+                ),
+            });
+        }
+        tlv_data = &tlv_data[length.saturating_add(4)..];
+    }
+    None
+}
@@ -1,29 +1,1766 @@
 #![forbid(unsafe_code)]
 
+mod inline_spl_token;
+mod inline_spl_token_2022;
+
+#[cfg(feature = "instrumented")]
+use solana_measure::measure::Measure;
 use {
-    bytemuck::Pod,
-    solana_program_runtime::{ic_msg, invoke_context::InvokeContext},
+    borsh::BorshDeserialize,
+    bytemuck::{bytes_of, Pod},
+    solana_program_runtime::{ic_msg, invoke_context::InvokeContext, stable_log},
+    solana_rbpf::vm::ContextObject,
     solana_sdk::{
+        ed25519_instruction::{
+            PUBKEY_SERIALIZED_SIZE, SIGNATURE_OFFSETS_SERIALIZED_SIZE, SIGNATURE_OFFSETS_START,
+        },
+        ed25519_program,
+        hash::hash,
         instruction::{InstructionError, TRANSACTION_LEVEL_STACK_HEIGHT},
-        system_program,
+        pubkey::Pubkey,
+        system_instruction, system_program, sysvar,
+        transaction_context::{BorrowedAccount, IndexOfAccount},
     },
     solana_zk_token_sdk::{
+        encryption::elgamal::ElGamalCiphertext,
+        zk_token_elgamal::pod,
+        zk_token_proof_event::{
+            ProofVerified, VerifyTelemetry, PROOF_VERIFIED_EVENT_VERSION, VERIFY_TELEMETRY_VERSION,
+        },
         zk_token_proof_instruction::*,
         zk_token_proof_program::id,
-        zk_token_proof_state::{ProofContextState, ProofContextStateMeta},
+        zk_token_proof_state::{
+            context_state_policy_hash, decode_proof_context_data, extract_elgamal_pubkeys,
+            find_policy_account_address, find_program_managed_context_address, is_closeable_by,
+            migrate_context_state_data, retry_nonce_instruction_hash, security_level,
+            validate_policy_account_body, verification_receipt, AuthorityAllowlist,
+            ContextStateHeader, ExtendedProofContextStateMeta, KeyBlacklist, NullifierSet,
+            PolicyAccountHeader, ProofCommitment, ProofContextState, ProofContextStateMeta,
+            ProofReference, RateLimitConfig, RateLimitState, RetryNonceTracker,
+            SecurityLevelConfig, SlotWindows, Token2022ProofContextState, UsageCounter,
+            AUTHORITY_ALLOWLIST_DISCRIMINATOR, KEY_BLACKLIST_DISCRIMINATOR,
+            NULLIFIER_SET_DISCRIMINATOR, PROOF_CONTEXT_STATE_FLAG_COMPRESSED,
+            PROOF_CONTEXT_STATE_FLAG_INCOMPLETE, RATE_LIMIT_CONFIG_DISCRIMINATOR,
+            RATE_LIMIT_STATE_DISCRIMINATOR, RETRY_NONCE_TRACKER_DISCRIMINATOR,
+            SECURITY_LEVEL_CONFIG_DISCRIMINATOR, SLOT_WINDOWS_DISCRIMINATOR,
+            USAGE_COUNTER_DISCRIMINATOR,
+        },
     },
-    std::result::Result,
+    std::{mem::size_of, result::Result},
 };
 
-fn process_verify_proof<T, U>(invoke_context: &mut InvokeContext) -> Result<(), InstructionError>
+/// Returns the ciphertext that a proof context leaves behind for a subsequent proof to consume.
+fn produced_ciphertext(
+    proof_type: ProofType,
+    proof_context: &[u8],
+) -> Option<pod::ElGamalCiphertext> {
+    match proof_type {
+        ProofType::Withdraw => bytemuck::try_from_bytes::<WithdrawProofContext>(proof_context)
+            .ok()
+            .map(|context| context.final_ciphertext),
+        ProofType::Transfer => bytemuck::try_from_bytes::<TransferProofContext>(proof_context)
+            .ok()
+            .map(|context| context.new_source_ciphertext),
+        ProofType::TransferWithFee => {
+            bytemuck::try_from_bytes::<TransferWithFeeProofContext>(proof_context)
+                .ok()
+                .map(|context| context.new_source_ciphertext)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the ciphertext that a proof context takes as an input.
+fn consumed_ciphertext(
+    proof_type: ProofType,
+    proof_context: &[u8],
+) -> Option<pod::ElGamalCiphertext> {
+    match proof_type {
+        ProofType::CloseAccount => {
+            bytemuck::try_from_bytes::<CloseAccountProofContext>(proof_context)
+                .ok()
+                .map(|context| context.ciphertext)
+        }
+        _ => None,
+    }
+}
+
+fn process_prior_context_option(
+    invoke_context: &InvokeContext,
+    account_index: IndexOfAccount,
+    proof_type: ProofType,
+    proof_context: &[u8],
+) -> Result<(), InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let prior_context_account =
+        instruction_context.try_borrow_instruction_account(transaction_context, account_index)?;
+
+    if *prior_context_account.get_owner() != id() {
+        return Err(InstructionError::InvalidAccountOwner);
+    }
+
+    let prior_context_data = prior_context_account.get_data();
+    let prior_context_state_header = ContextStateHeader::decode(prior_context_data)?;
+    if !prior_context_state_header.is_complete() {
+        ic_msg!(invoke_context, "prior proof context state is incomplete");
+        return Err(InstructionError::InvalidAccountData);
+    }
+    let prior_proof_type: ProofType = prior_context_state_header.proof_type.try_into()?;
+
+    let prior_proof_context = decode_proof_context_data(prior_context_data)?;
+    let prior_ciphertext =
+        produced_ciphertext(prior_proof_type, &prior_proof_context).ok_or_else(|| {
+            ic_msg!(
+                invoke_context,
+                "prior proof context does not produce a ciphertext"
+            );
+            InstructionError::InvalidAccountData
+        })?;
+
+    let ciphertext = consumed_ciphertext(proof_type, proof_context).ok_or_else(|| {
+        ic_msg!(invoke_context, "proof does not consume a ciphertext");
+        InstructionError::InvalidInstructionData
+    })?;
+
+    if prior_ciphertext != ciphertext {
+        ic_msg!(
+            invoke_context,
+            "proof is not linked to the prior proof context"
+        );
+        return Err(InstructionError::InvalidInstructionData);
+    }
+
+    Ok(())
+}
+
+/// Returns the pubkey that the destination ciphertexts of a transfer proof context are encrypted
+/// under.
+fn destination_pubkey(proof_type: ProofType, proof_context: &[u8]) -> Option<pod::ElGamalPubkey> {
+    match proof_type {
+        ProofType::Transfer => bytemuck::try_from_bytes::<TransferProofContext>(proof_context)
+            .ok()
+            .map(|context| context.transfer_pubkeys.destination_pubkey),
+        ProofType::TransferWithFee => {
+            bytemuck::try_from_bytes::<TransferWithFeeProofContext>(proof_context)
+                .ok()
+                .map(|context| context.transfer_with_fee_pubkeys.destination_pubkey)
+        }
+        _ => None,
+    }
+}
+
+/// Returns the source pubkey of a transfer proof context.
+fn source_pubkey(proof_type: ProofType, proof_context: &[u8]) -> Option<pod::ElGamalPubkey> {
+    match proof_type {
+        ProofType::Transfer => bytemuck::try_from_bytes::<TransferProofContext>(proof_context)
+            .ok()
+            .map(|context| context.transfer_pubkeys.source_pubkey),
+        ProofType::TransferWithFee => {
+            bytemuck::try_from_bytes::<TransferWithFeeProofContext>(proof_context)
+                .ok()
+                .map(|context| context.transfer_with_fee_pubkeys.source_pubkey)
+        }
+        _ => None,
+    }
+}
+
+fn process_destination_account_option(
+    invoke_context: &InvokeContext,
+    account_index: IndexOfAccount,
+    proof_type: ProofType,
+    proof_context: &[u8],
+) -> Result<(), InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let destination_account =
+        instruction_context.try_borrow_instruction_account(transaction_context, account_index)?;
+
+    if *destination_account.get_owner() != inline_spl_token_2022::id() {
+        return Err(InstructionError::InvalidAccountOwner);
+    }
+
+    let destination =
+        inline_spl_token_2022::unpack_confidential_transfer_account(destination_account.get_data())
+            .ok_or_else(|| {
+                ic_msg!(
+                    invoke_context,
+                    "destination account is not configured for confidential transfers"
+                );
+                InstructionError::InvalidAccountData
+            })?;
+
+    // a malformed pending balance could not be credited
+    ElGamalCiphertext::try_from(destination.pending_balance_lo)
+        .and(ElGamalCiphertext::try_from(destination.pending_balance_hi))
+        .map_err(|_| InstructionError::InvalidAccountData)?;
+
+    let pubkey = destination_pubkey(proof_type, proof_context).ok_or_else(|| {
+        ic_msg!(invoke_context, "proof does not have a destination");
+        InstructionError::InvalidInstructionData
+    })?;
+
+    if destination.elgamal_pubkey != pubkey {
+        ic_msg!(
+            invoke_context,
+            "proof destination does not match the destination account"
+        );
+        return Err(InstructionError::InvalidInstructionData);
+    }
+
+    Ok(())
+}
+
+/// Returns the header and body of the policy account `account` of the kind `discriminator`.
+///
+/// Any program-owned account could carry a policy account header, but only the address derived
+/// from the header is written by its admin, so an account at any other address is rejected.
+fn policy_account<'a>(
+    account: &'a BorrowedAccount,
+    discriminator: &[u8; 8],
+) -> Result<(&'a PolicyAccountHeader, &'a [u8]), InstructionError> {
+    if *account.get_owner() != id() {
+        return Err(InstructionError::InvalidAccountOwner);
+    }
+    let (header, body) = PolicyAccountHeader::split(account.get_data(), discriminator)?;
+    if header.address()? != *account.get_key() {
+        return Err(InstructionError::InvalidAccountData);
+    }
+    Ok((header, body))
+}
+
+/// Returns the body of the policy account `account` of the kind `discriminator`, for writing.
+fn policy_account_body_mut<'a>(
+    account: &'a mut BorrowedAccount,
+    discriminator: &[u8; 8],
+) -> Result<&'a mut [u8], InstructionError> {
+    policy_account(account, discriminator)?;
+    PolicyAccountHeader::body_mut(account.get_data_mut()?, discriminator)
+}
+
+fn process_authority_allowlist_option(
+    invoke_context: &InvokeContext,
+    account_index: IndexOfAccount,
+) -> Result<(), InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let context_state_authority = *instruction_context
+        .try_borrow_instruction_account(transaction_context, 1)?
+        .get_key();
+    let allowlist_account =
+        instruction_context.try_borrow_instruction_account(transaction_context, account_index)?;
+    let (_, allowlist) = policy_account(&allowlist_account, &AUTHORITY_ALLOWLIST_DISCRIMINATOR)?;
+
+    if !AuthorityAllowlist::contains(allowlist, &context_state_authority)? {
+        ic_msg!(
+            invoke_context,
+            "context state authority {} is not in the allowlist",
+            context_state_authority
+        );
+        return Err(InstructionError::InvalidArgument);
+    }
+
+    Ok(())
+}
+
+fn process_slot_windows_option(
+    invoke_context: &InvokeContext,
+    account_index: IndexOfAccount,
+) -> Result<(), InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let windows_account =
+        instruction_context.try_borrow_instruction_account(transaction_context, account_index)?;
+    let (_, windows) = policy_account(&windows_account, &SLOT_WINDOWS_DISCRIMINATOR)?;
+
+    let slot = invoke_context.get_sysvar_cache().get_clock()?.slot;
+    if !SlotWindows::contains(windows, slot)? {
+        ic_msg!(
+            invoke_context,
+            "proof submitted in slot {} outside the allowed slot windows",
+            slot
+        );
+        return Err(InstructionError::InvalidArgument);
+    }
+
+    Ok(())
+}
+
+fn process_nullifier_option(
+    invoke_context: &InvokeContext,
+    account_index: IndexOfAccount,
+    proof_type: ProofType,
+    proof_context: &[u8],
+) -> Result<(), InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let mut nullifier_set_account =
+        instruction_context.try_borrow_instruction_account(transaction_context, account_index)?;
+    let nullifier_set =
+        policy_account_body_mut(&mut nullifier_set_account, &NULLIFIER_SET_DISCRIMINATOR)?;
+
+    let nullifier = NullifierSet::nullifier(proof_type, proof_context);
+    if !NullifierSet::try_insert(nullifier_set, &nullifier)? {
+        ic_msg!(invoke_context, "proof nullifier has already been used");
+        return Err(InstructionError::InvalidArgument);
+    }
+
+    Ok(())
+}
+
+fn predicate_matches(
+    invoke_context: &InvokeContext,
+    account_index: IndexOfAccount,
+    offset: u32,
+    length: u8,
+    expected: &[u8],
+) -> Result<bool, InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let predicate_account =
+        instruction_context.try_borrow_instruction_account(transaction_context, account_index)?;
+
+    let expected = expected
+        .get(..usize::from(length))
+        .ok_or(InstructionError::InvalidInstructionData)?;
+    let start = usize::try_from(offset).map_err(|_| InstructionError::InvalidInstructionData)?;
+    let actual = start
+        .checked_add(expected.len())
+        .and_then(|end| predicate_account.get_data().get(start..end));
+
+    Ok(actual == Some(expected))
+}
+
+fn process_rate_limit_option(
+    invoke_context: &InvokeContext,
+    account_index: IndexOfAccount,
+) -> Result<(), InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let context_state_authority = *instruction_context
+        .try_borrow_instruction_account(transaction_context, 1)?
+        .get_key();
+
+    let config_account =
+        instruction_context.try_borrow_instruction_account(transaction_context, account_index)?;
+    let config_pubkey = *config_account.get_key();
+    let (config_header, config) =
+        policy_account(&config_account, &RATE_LIMIT_CONFIG_DISCRIMINATOR)?;
+    let config_admin = config_header.admin;
+    let config = *RateLimitConfig::try_from_bytes(config)?;
+    drop(config_account);
+
+    let mut state_account = instruction_context
+        .try_borrow_instruction_account(transaction_context, account_index.saturating_add(1))?;
+    // a state initialized by anyone but the admin of the config could be swapped for a fresh one
+    if policy_account(&state_account, &RATE_LIMIT_STATE_DISCRIMINATOR)?
+        .0
+        .admin
+        != config_admin
+    {
+        return Err(InstructionError::InvalidAccountData);
+    }
+    let state = RateLimitState::try_from_bytes_mut(policy_account_body_mut(
+        &mut state_account,
+        &RATE_LIMIT_STATE_DISCRIMINATOR,
+    )?)?;
+    if state.config != config_pubkey || state.context_state_authority != context_state_authority {
+        return Err(InstructionError::InvalidAccountData);
+    }
+
+    let slot = invoke_context.get_sysvar_cache().get_clock()?.slot;
+    if !state.try_count(&config, slot) {
+        ic_msg!(
+            invoke_context,
+            "context state authority {} exceeded its rate limit",
+            context_state_authority
+        );
+        return Err(InstructionError::InvalidArgument);
+    }
+
+    Ok(())
+}
+
+/// Record the nonce of a `RetryNonce` option with the hash of the instruction, returning false
+/// without recording it if the nonce has already been processed with the same instruction.
+fn process_retry_nonce_option(
+    invoke_context: &InvokeContext,
+    account_index: IndexOfAccount,
+    nonce: u64,
+) -> Result<bool, InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+
+    let account_keys = (0..instruction_context.get_number_of_instruction_accounts())
+        .map(|index| {
+            transaction_context
+                .get_key_of_account_at_index(
+                    instruction_context.get_index_of_instruction_account_in_transaction(index)?,
+                )
+                .copied()
+        })
+        .collect::<Result<Vec<_>, InstructionError>>()?;
+    let instruction_hash =
+        retry_nonce_instruction_hash(instruction_context.get_instruction_data(), &account_keys);
+
+    let authority_account = instruction_context
+        .try_borrow_instruction_account(transaction_context, account_index.saturating_add(1))?;
+    if !authority_account.is_signer() {
+        return Err(InstructionError::MissingRequiredSignature);
+    }
+    let authority = *authority_account.get_key();
+    drop(authority_account);
+
+    let mut tracker_account =
+        instruction_context.try_borrow_instruction_account(transaction_context, account_index)?;
+    if policy_account(&tracker_account, &RETRY_NONCE_TRACKER_DISCRIMINATOR)?
+        .0
+        .admin
+        != authority
+    {
+        return Err(InstructionError::InvalidAccountData);
+    }
+    let tracker = RetryNonceTracker::try_from_bytes_mut(policy_account_body_mut(
+        &mut tracker_account,
+        &RETRY_NONCE_TRACKER_DISCRIMINATOR,
+    )?)?;
+
+    match tracker.instruction_hash(nonce) {
+        // a replay of the instruction that recorded the nonce
+        Some(recorded_hash) if *recorded_hash == instruction_hash => Ok(false),
+        Some(_) => {
+            ic_msg!(
+                invoke_context,
+                "retry nonce {} was processed with a different instruction",
+                nonce
+            );
+            Err(InstructionError::InvalidArgument)
+        }
+        None => Ok(tracker.try_record(nonce, instruction_hash)),
+    }
+}
+
+fn process_min_security_level_option(
+    invoke_context: &InvokeContext,
+    account_index: IndexOfAccount,
+    proof_type: ProofType,
+) -> Result<(), InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+
+    let config_account =
+        instruction_context.try_borrow_instruction_account(transaction_context, account_index)?;
+    let (_, config) = policy_account(&config_account, &SECURITY_LEVEL_CONFIG_DISCRIMINATOR)?;
+    let config = SecurityLevelConfig::try_from_bytes(config)?;
+
+    let proof_security_level = security_level(proof_type);
+    if proof_security_level < config.minimum_security_level {
+        ic_msg!(
+            invoke_context,
+            "{:?} proof has security level {} below the minimum security level {}",
+            proof_type,
+            proof_security_level,
+            config.minimum_security_level
+        );
+        return Err(InstructionError::InvalidArgument);
+    }
+
+    Ok(())
+}
+
+fn process_key_blacklist_option(
+    invoke_context: &InvokeContext,
+    account_index: IndexOfAccount,
+    proof_type: ProofType,
+    proof_context: &[u8],
+) -> Result<(), InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+
+    let blacklist_account =
+        instruction_context.try_borrow_instruction_account(transaction_context, account_index)?;
+    let (_, blacklist) = policy_account(&blacklist_account, &KEY_BLACKLIST_DISCRIMINATOR)?;
+
+    for pubkey in extract_elgamal_pubkeys(proof_type, proof_context)? {
+        if KeyBlacklist::contains(blacklist, &pubkey)? {
+            ic_msg!(
+                invoke_context,
+                "{:?} proof uses blacklisted ElGamal pubkey {}",
+                proof_type,
+                pubkey
+            );
+            return Err(InstructionError::InvalidArgument);
+        }
+    }
+
+    Ok(())
+}
+
+/// The accounts of a `ProgramManaged` option
+struct ProgramManagedContext {
+    account_index: IndexOfAccount,
+    address: Pubkey,
+    controlling_program_id: Pubkey,
+    payer: Pubkey,
+    payer_lamports: u64,
+}
+
+fn process_program_managed_option(
+    invoke_context: &InvokeContext,
+    account_index: IndexOfAccount,
+    id: u64,
+) -> Result<ProgramManagedContext, InstructionError> {
+    let controlling_program_id = match invoking_program_id(invoke_context)? {
+        Some(controlling_program_id) => controlling_program_id,
+        None => {
+            ic_msg!(
+                invoke_context,
+                "program-managed context requires an invoking program"
+            );
+            return Err(InstructionError::InvalidArgument);
+        }
+    };
+
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+
+    let address = *instruction_context
+        .try_borrow_instruction_account(transaction_context, account_index)?
+        .get_key();
+    let (expected_address, _) = find_program_managed_context_address(&controlling_program_id, id);
+    if address != expected_address {
+        ic_msg!(
+            invoke_context,
+            "proof context account {} is not the program-managed address {}",
+            address,
+            expected_address
+        );
+        return Err(InstructionError::InvalidArgument);
+    }
+
+    let payer_account = instruction_context
+        .try_borrow_instruction_account(transaction_context, account_index.saturating_add(1))?;
+    if !payer_account.is_signer() {
+        return Err(InstructionError::MissingRequiredSignature);
+    }
+
+    Ok(ProgramManagedContext {
+        account_index,
+        address,
+        controlling_program_id,
+        payer: *payer_account.get_key(),
+        payer_lamports: payer_account.get_lamports(),
+    })
+}
+
+fn process_usage_counter_option(
+    invoke_context: &InvokeContext,
+    account_index: IndexOfAccount,
+) -> Result<(), InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+
+    let mut counter_account =
+        instruction_context.try_borrow_instruction_account(transaction_context, account_index)?;
+    let counter = UsageCounter::try_from_bytes_mut(policy_account_body_mut(
+        &mut counter_account,
+        &USAGE_COUNTER_DISCRIMINATOR,
+    )?)?;
+    if !counter.try_increment() {
+        ic_msg!(invoke_context, "usage counter overflow");
+        return Err(InstructionError::ArithmeticOverflow);
+    }
+
+    Ok(())
+}
+
+fn process_reveal_commitment_option(
+    invoke_context: &InvokeContext,
+    account_index: IndexOfAccount,
+    proof_data: &[u8],
+) -> Result<(), InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+
+    let committer_account = instruction_context
+        .try_borrow_instruction_account(transaction_context, account_index.saturating_add(1))?;
+    if !committer_account.is_signer() {
+        return Err(InstructionError::MissingRequiredSignature);
+    }
+    let committer = *committer_account.get_key();
+    drop(committer_account);
+
+    let mut commitment_account =
+        instruction_context.try_borrow_instruction_account(transaction_context, account_index)?;
+    if *commitment_account.get_owner() != id() {
+        return Err(InstructionError::InvalidAccountOwner);
+    }
+    let commitment = ProofCommitment::try_from_bytes(commitment_account.get_data())?;
+    if commitment.committer != committer {
+        return Err(InstructionError::InvalidAccountOwner);
+    }
+    if commitment.proof_hash != hash(proof_data).to_bytes() {
+        ic_msg!(
+            invoke_context,
+            "revealed proof does not match the commitment"
+        );
+        return Err(InstructionError::InvalidInstructionData);
+    }
+
+    // consume the commitment so that it cannot be revealed twice
+    commitment_account.get_data_mut()?.fill(0);
+
+    Ok(())
+}
+
+/// Returns the pubkeys and messages of the signatures in the data of an Ed25519 program
+/// instruction whose pubkey and message are stored in the instruction itself.
+fn ed25519_signed_messages(data: &[u8]) -> Vec<(Pubkey, &[u8])> {
+    let read_u16 = |offsets: &[u8], index: usize| -> Option<usize> {
+        let start = index.checked_mul(2)?;
+        let bytes = offsets.get(start..start.checked_add(2)?)?;
+        Some(usize::from(u16::from_le_bytes([bytes[0], bytes[1]])))
+    };
+    let signed_message = |signature_index: usize| -> Option<(Pubkey, &[u8])> {
+        let start = signature_index
+            .checked_mul(SIGNATURE_OFFSETS_SERIALIZED_SIZE)?
+            .checked_add(SIGNATURE_OFFSETS_START)?;
+        let offsets = data.get(start..start.checked_add(SIGNATURE_OFFSETS_SERIALIZED_SIZE)?)?;
+
+        // a `u16::MAX` instruction index refers to the Ed25519 instruction itself
+        let current_instruction = usize::from(u16::MAX);
+        if read_u16(offsets, 3)? != current_instruction
+            || read_u16(offsets, 6)? != current_instruction
+        {
+            return None;
+        }
+
+        let pubkey_offset = read_u16(offsets, 2)?;
+        let pubkey = data.get(pubkey_offset..pubkey_offset.checked_add(PUBKEY_SERIALIZED_SIZE)?)?;
+        let message_offset = read_u16(offsets, 4)?;
+        let message =
+            data.get(message_offset..message_offset.checked_add(read_u16(offsets, 5)?)?)?;
+        Some((Pubkey::try_from(pubkey).ok()?, message))
+    };
+
+    let number_of_signatures = data.first().copied().unwrap_or(0);
+    (0..usize::from(number_of_signatures))
+        .filter_map(signed_message)
+        .collect()
+}
+
+fn process_verifier_signature_option(
+    invoke_context: &InvokeContext,
+    account_index: IndexOfAccount,
+    proof_data: &[u8],
+) -> Result<(), InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let allowlist_account =
+        instruction_context.try_borrow_instruction_account(transaction_context, account_index)?;
+    let (_, allowlist) = policy_account(&allowlist_account, &AUTHORITY_ALLOWLIST_DISCRIMINATOR)?;
+
+    let instructions_account = instruction_context
+        .try_borrow_instruction_account(transaction_context, account_index.saturating_add(1))?;
+    if !sysvar::instructions::check_id(instructions_account.get_key()) {
+        return Err(InstructionError::UnsupportedSysvar);
+    }
+    let instructions_data = instructions_account.get_data();
+    let number_of_instructions = instructions_data
+        .get(..2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .ok_or(InstructionError::InvalidAccountData)?;
+
+    for index in 0..usize::from(number_of_instructions) {
+        // the address of the instructions sysvar is checked above
+        #[allow(deprecated)]
+        let instruction = sysvar::instructions::load_instruction_at(index, instructions_data)
+            .map_err(|_| InstructionError::InvalidAccountData)?;
+        if instruction.program_id != ed25519_program::id() {
+            continue;
+        }
+        for (verifier, message) in ed25519_signed_messages(&instruction.data) {
+            if message == proof_data && AuthorityAllowlist::contains(allowlist, &verifier)? {
+                return Ok(());
+            }
+        }
+    }
+
+    ic_msg!(
+        invoke_context,
+        "proof is not signed by an allowlisted verifier"
+    );
+    Err(InstructionError::InvalidArgument)
+}
+
+fn process_prior_instruction_option(
+    invoke_context: &InvokeContext,
+    account_index: IndexOfAccount,
+    program_id: &Pubkey,
+    discriminant: u8,
+) -> Result<(), InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let instructions_account =
+        instruction_context.try_borrow_instruction_account(transaction_context, account_index)?;
+    if !sysvar::instructions::check_id(instructions_account.get_key()) {
+        return Err(InstructionError::UnsupportedSysvar);
+    }
+    let instructions_data = instructions_account.get_data();
+    if instructions_data.len() < 2 {
+        return Err(InstructionError::InvalidAccountData);
+    }
+
+    // the address of the instructions sysvar is checked above
+    #[allow(deprecated)]
+    let current_index = sysvar::instructions::load_current_index(instructions_data);
+    for index in 0..usize::from(current_index) {
+        #[allow(deprecated)]
+        let instruction = sysvar::instructions::load_instruction_at(index, instructions_data)
+            .map_err(|_| InstructionError::InvalidAccountData)?;
+        if instruction.program_id == *program_id && instruction.data.first() == Some(&discriminant)
+        {
+            return Ok(());
+        }
+    }
+
+    ic_msg!(
+        invoke_context,
+        "transaction does not include a prior instruction {} of program {}",
+        discriminant,
+        program_id
+    );
+    Err(InstructionError::InvalidArgument)
+}
+
+/// Allocate the proof context account at `account_index` with `space` bytes through the system
+/// program, funding its rent-exempt balance from `payer`.
+///
+/// An account that already holds lamports, such as a program-managed address that lamports have
+/// been transferred to ahead of time, cannot be created by the system program, so it is only
+/// topped up to its rent-exempt balance and then allocated and assigned to the proof program.
+///
+/// The proof context account must either sign the instruction or be one of the `signers`, the
+/// program-managed addresses that the proof program signs for. Policy accounts, which the proof
+/// program signs for as well, are created the same way.
+fn create_proof_context_account(
+    invoke_context: &mut InvokeContext,
+    account_index: IndexOfAccount,
+    payer: &Pubkey,
+    payer_lamports: u64,
+    space: usize,
+    signers: &[Pubkey],
+) -> Result<(), InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let proof_context_account =
+        instruction_context.try_borrow_instruction_account(transaction_context, account_index)?;
+    if !proof_context_account.is_signer() && !signers.contains(proof_context_account.get_key()) {
+        return Err(InstructionError::MissingRequiredSignature);
+    }
+    let proof_context_account_pubkey = *proof_context_account.get_key();
+    let current_lamports = proof_context_account.get_lamports();
+    drop(proof_context_account);
+
+    let required_lamports = invoke_context
+        .get_sysvar_cache()
+        .get_rent()?
+        .minimum_balance(space);
+    let shortfall = required_lamports.saturating_sub(current_lamports);
+    if payer_lamports < shortfall {
+        ic_msg!(
+            invoke_context,
+            "payer has insufficient funds for the proof context account"
+        );
+        return Err(InstructionError::InsufficientFunds);
+    }
+
+    if current_lamports == 0 {
+        return invoke_context.native_invoke(
+            system_instruction::create_account(
+                payer,
+                &proof_context_account_pubkey,
+                required_lamports,
+                space as u64,
+                &id(),
+            )
+            .into(),
+            signers,
+        );
+    }
+
+    if shortfall > 0 {
+        invoke_context.native_invoke(
+            system_instruction::transfer(payer, &proof_context_account_pubkey, shortfall).into(),
+            signers,
+        )?;
+    }
+    invoke_context.native_invoke(
+        system_instruction::allocate(&proof_context_account_pubkey, space as u64).into(),
+        signers,
+    )?;
+    invoke_context.native_invoke(
+        system_instruction::assign(&proof_context_account_pubkey, &id()).into(),
+        signers,
+    )
+}
+
+/// The options of a verify instruction, parsed and validated before the proof is verified.
+///
+/// Options that only check or update their own accounts are kept in `account_options` and
+/// processed in instruction order once the proof is verified.
+#[derive(Default)]
+struct VerifyOptions {
+    number_of_context_accounts: usize,
+    account_options: Vec<(IndexOfAccount, VerifyOption)>,
+    delegate: Option<IndexOfAccount>,
+    compressed: bool,
+    token_2022_layout: bool,
+    reference_only: bool,
+    partial: bool,
+    memo_log: bool,
+    anchor_event: bool,
+    structured_log: bool,
+    slot_log: bool,
+    receipt: bool,
+    telemetry: bool,
+    mirror_account: Option<IndexOfAccount>,
+    create_context_account: Option<IndexOfAccount>,
+    program_managed: Option<(IndexOfAccount, u64)>,
+    routed: bool,
+    routed_context_account: Option<IndexOfAccount>,
+    retry_nonce: Option<(IndexOfAccount, u64)>,
+    policies: u8,
+    policy_accounts: Vec<IndexOfAccount>,
+}
+
+impl VerifyOptions {
+    fn parse(
+        invoke_context: &InvokeContext,
+        options: &[VerifyOption],
+        proof_type: ProofType,
+    ) -> Result<Self, InstructionError> {
+        // an inner verify instruction must create a program-managed context state for its invoking
+        // program
+        if invoke_context.get_stack_height() != TRANSACTION_LEVEL_STACK_HEIGHT
+            && !options
+                .iter()
+                .any(|option| matches!(option, VerifyOption::ProgramManaged(_)))
+        {
+            return Err(InstructionError::UnsupportedProgramId);
+        }
+
+        // accounts consumed by the verification options follow the proof context accounts
+        let number_of_option_accounts: usize =
+            options.iter().map(VerifyOption::number_of_accounts).sum();
+        let number_of_context_accounts = usize::from(
+            invoke_context
+                .transaction_context
+                .get_current_instruction_context()?
+                .get_number_of_instruction_accounts(),
+        )
+        .checked_sub(number_of_option_accounts)
+        .ok_or(InstructionError::NotEnoughAccountKeys)?;
+
+        let mut verify_options = Self {
+            number_of_context_accounts,
+            ..Self::default()
+        };
+        let mut option_account_index = number_of_context_accounts;
+        for option in options {
+            verify_options.parse_option(
+                invoke_context,
+                option_account_index as IndexOfAccount,
+                *option,
+                proof_type,
+            )?;
+            // the context state records the policies enforced on it
+            if option.context_state_policy() != 0 {
+                verify_options.policies |= option.context_state_policy();
+                verify_options
+                    .policy_accounts
+                    .push(option_account_index as IndexOfAccount);
+            }
+            option_account_index = option_account_index.saturating_add(option.number_of_accounts());
+        }
+        verify_options.validate(invoke_context, proof_type)?;
+
+        Ok(verify_options)
+    }
+
+    fn parse_option(
+        &mut self,
+        invoke_context: &InvokeContext,
+        account_index: IndexOfAccount,
+        option: VerifyOption,
+        proof_type: ProofType,
+    ) -> Result<(), InstructionError> {
+        match option {
+            VerifyOption::Delegate => {
+                self.require_context_accounts(
+                    invoke_context,
+                    1,
+                    "delegate requires a proof context account",
+                )?;
+                self.delegate = Some(account_index);
+            }
+            VerifyOption::AuthorityAllowlist => {
+                self.require_context_accounts(
+                    invoke_context,
+                    2,
+                    "allowlist requires a proof context account",
+                )?;
+                self.account_options.push((account_index, option));
+            }
+            VerifyOption::RateLimit => {
+                self.require_context_accounts(
+                    invoke_context,
+                    2,
+                    "rate limit requires a proof context account",
+                )?;
+                self.account_options.push((account_index, option));
+            }
+            VerifyOption::MemoLog => self.memo_log = true,
+            VerifyOption::AnchorEvent => self.anchor_event = true,
+            VerifyOption::StructuredLog => self.structured_log = true,
+            VerifyOption::SlotLog => self.slot_log = true,
+            VerifyOption::Receipt => self.receipt = true,
+            VerifyOption::Telemetry => self.telemetry = true,
+            VerifyOption::MirrorAccount => {
+                self.require_context_accounts(
+                    invoke_context,
+                    1,
+                    "mirroring requires a proof context account",
+                )?;
+                self.mirror_account = Some(account_index);
+            }
+            VerifyOption::ContextAddress(_) => {
+                self.require_context_accounts(
+                    invoke_context,
+                    1,
+                    "context address requires a proof context account",
+                )?;
+                self.account_options.push((account_index, option));
+            }
+            VerifyOption::Token2022Layout => {
+                self.require_context_accounts(
+                    invoke_context,
+                    1,
+                    "token-2022 layout requires a proof context account",
+                )?;
+                self.token_2022_layout = true;
+            }
+            VerifyOption::ReferenceOnly => {
+                self.require_context_accounts(
+                    invoke_context,
+                    1,
+                    "reference requires a proof context account",
+                )?;
+                self.reference_only = true;
+            }
+            VerifyOption::Partial => self.partial = true,
+            VerifyOption::CreateContextAccount => {
+                self.require_context_accounts(
+                    invoke_context,
+                    2,
+                    "context account creation requires a proof context account",
+                )?;
+                self.create_context_account = Some(account_index);
+            }
+            VerifyOption::Predicate { .. } => {
+                self.require_context_accounts(
+                    invoke_context,
+                    1,
+                    "predicate requires a proof context account",
+                )?;
+                self.account_options.push((account_index, option));
+            }
+            VerifyOption::ProgramManaged(id) => {
+                if self.number_of_context_accounts > 0 {
+                    ic_msg!(
+                        invoke_context,
+                        "program-managed context cannot be combined with a proof context account"
+                    );
+                    return Err(InstructionError::InvalidInstructionData);
+                }
+                self.program_managed = Some((account_index, id));
+            }
+            VerifyOption::RouteCandidate(candidate_proof_type) => {
+                if self.number_of_context_accounts != 1 {
+                    ic_msg!(
+                        invoke_context,
+                        "routing requires the authority as the only proof context account"
+                    );
+                    return Err(InstructionError::InvalidInstructionData);
+                }
+                self.routed = true;
+                // the first candidate of the proof type receives the context state
+                if candidate_proof_type == proof_type as u8 && self.routed_context_account.is_none()
+                {
+                    self.routed_context_account = Some(account_index);
+                }
+            }
+            VerifyOption::Compressed => {
+                self.require_context_accounts(
+                    invoke_context,
+                    1,
+                    "compression requires a proof context account",
+                )?;
+                self.compressed = true;
+            }
+            VerifyOption::RetryNonce(nonce) => {
+                if self.retry_nonce.is_some() {
+                    ic_msg!(invoke_context, "at most one retry nonce is allowed");
+                    return Err(InstructionError::InvalidInstructionData);
+                }
+                self.retry_nonce = Some((account_index, nonce));
+            }
+            VerifyOption::PriorContext
+            | VerifyOption::DestinationAccount
+            | VerifyOption::UsageCounter
+            | VerifyOption::SlotWindows
+            | VerifyOption::MinSecurityLevel
+            | VerifyOption::KeyBlacklist
+            | VerifyOption::PriorInstruction { .. }
+            | VerifyOption::Nullifier
+            | VerifyOption::DistinctEndpoints
+            | VerifyOption::RevealCommitment
+            | VerifyOption::VerifierSignature
+            | VerifyOption::MinEpoch(_) => self.account_options.push((account_index, option)),
+        }
+
+        Ok(())
+    }
+
+    /// Check the combinations of options that cannot be checked one option at a time.
+    fn validate(
+        &self,
+        invoke_context: &InvokeContext,
+        proof_type: ProofType,
+    ) -> Result<(), InstructionError> {
+        if self.telemetry && self.receipt {
+            ic_msg!(
+                invoke_context,
+                "telemetry cannot be combined with a receipt"
+            );
+            return Err(InstructionError::InvalidInstructionData);
+        }
+
+        // the token-2022 layout is the default layout, which has no delegate, flags, or policies
+        if self.token_2022_layout
+            && (self.delegate.is_some()
+                || self.compressed
+                || self.reference_only
+                || self.partial
+                || self.policies != 0)
+        {
+            ic_msg!(
+                invoke_context,
+                "token-2022 layout cannot be combined with extended layout options"
+            );
+            return Err(InstructionError::InvalidInstructionData);
+        }
+
+        if self.reference_only && self.compressed {
+            ic_msg!(
+                invoke_context,
+                "reference cannot be combined with compression"
+            );
+            return Err(InstructionError::InvalidInstructionData);
+        }
+
+        if self.partial && self.delegate.is_none() {
+            ic_msg!(
+                invoke_context,
+                "partial context requires a proof context account and a delegate"
+            );
+            return Err(InstructionError::InvalidInstructionData);
+        }
+
+        if self.routed {
+            if self
+                .account_options
+                .iter()
+                .any(|(_, option)| matches!(option, VerifyOption::ContextAddress(_)))
+            {
+                ic_msg!(
+                    invoke_context,
+                    "routing cannot be combined with a context address"
+                );
+                return Err(InstructionError::InvalidInstructionData);
+            }
+            if self.routed_context_account.is_none() {
+                ic_msg!(
+                    invoke_context,
+                    "no candidate proof context account for {:?} proofs",
+                    proof_type
+                );
+                return Err(InstructionError::InvalidArgument);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn require_context_accounts(
+        &self,
+        invoke_context: &InvokeContext,
+        number_of_context_accounts: usize,
+        message: &str,
+    ) -> Result<(), InstructionError> {
+        if self.number_of_context_accounts < number_of_context_accounts {
+            ic_msg!(invoke_context, "{}", message);
+            return Err(InstructionError::InvalidInstructionData);
+        }
+        Ok(())
+    }
+
+    /// Whether a proof context state is created, unless a predicate does not hold
+    fn creates_context_state(&self) -> bool {
+        self.number_of_context_accounts > 0 || self.program_managed.is_some()
+    }
+
+    /// The index of the account that receives the context state.
+    ///
+    /// A program-managed or routed context state is written to the option's account instead of
+    /// the first.
+    fn context_account_index(&self) -> IndexOfAccount {
+        self.program_managed
+            .map(|(account_index, _)| account_index)
+            .or(self.routed_context_account)
+            .unwrap_or(0)
+    }
+
+    /// The index of the context state authority, which is the only proof context account of a
+    /// routed context state
+    fn authority_index(&self) -> IndexOfAccount {
+        if self.routed {
+            0
+        } else {
+            1
+        }
+    }
+
+    /// Encode the context state in the layout selected by the options, recording the policies
+    /// enforced with the policy accounts `policy_accounts`.
+    fn encode_context_state<T, U>(
+        &self,
+        context_state_authority: &Pubkey,
+        context_state_delegate: &Pubkey,
+        policy_accounts: &[Pubkey],
+        proof_data: &T,
+    ) -> Result<Vec<u8>, InstructionError>
+    where
+        T: Pod + ZkProofData<U>,
+        U: Pod,
+    {
+        let proof_context = proof_data.context_data();
+        let mut context_state_data = if self.reference_only {
+            ProofReference::encode(
+                context_state_authority,
+                context_state_delegate,
+                T::PROOF_TYPE,
+                ProofCommitment::hash(proof_data),
+            )
+        } else if self.token_2022_layout {
+            Token2022ProofContextState::encode(
+                context_state_authority,
+                T::PROOF_TYPE,
+                proof_context,
+            )
+        } else if self.compressed {
+            ProofContextState::encode_compressed(
+                context_state_authority,
+                context_state_delegate,
+                T::PROOF_TYPE,
+                proof_context,
+            )
+        } else if *context_state_delegate != Pubkey::default() || self.partial || self.policies != 0
+        {
+            ProofContextState::encode_with_delegate(
+                context_state_authority,
+                context_state_delegate,
+                T::PROOF_TYPE,
+                proof_context,
+            )
+        } else {
+            ProofContextState::encode(context_state_authority, T::PROOF_TYPE, proof_context)
+        };
+        if self.partial {
+            ExtendedProofContextStateMeta::try_from_bytes_mut(&mut context_state_data)?.flags |=
+                PROOF_CONTEXT_STATE_FLAG_INCOMPLETE;
+        }
+        if self.policies != 0 {
+            let meta = ExtendedProofContextStateMeta::try_from_bytes_mut(&mut context_state_data)?;
+            meta.policies = self.policies;
+            meta.policy_hash = context_state_policy_hash(policy_accounts);
+        }
+        Ok(context_state_data)
+    }
+}
+
+fn process_delegate_option(
+    invoke_context: &InvokeContext,
+    account_index: IndexOfAccount,
+) -> Result<Pubkey, InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+
+    Ok(*instruction_context
+        .try_borrow_instruction_account(transaction_context, account_index)?
+        .get_key())
+}
+
+fn process_context_address_option(
+    invoke_context: &InvokeContext,
+    address: &Pubkey,
+) -> Result<(), InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+
+    let proof_context_account_pubkey = *instruction_context
+        .try_borrow_instruction_account(transaction_context, 0)?
+        .get_key();
+    if proof_context_account_pubkey != *address {
+        ic_msg!(
+            invoke_context,
+            "proof context account {} does not match the committed address {}",
+            proof_context_account_pubkey,
+            address
+        );
+        return Err(InstructionError::InvalidArgument);
+    }
+
+    Ok(())
+}
+
+fn process_create_context_account_option(
+    invoke_context: &InvokeContext,
+    account_index: IndexOfAccount,
+) -> Result<(Pubkey, u64), InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+
+    let payer_account =
+        instruction_context.try_borrow_instruction_account(transaction_context, account_index)?;
+    if !payer_account.is_signer() {
+        return Err(InstructionError::MissingRequiredSignature);
+    }
+
+    Ok((*payer_account.get_key(), payer_account.get_lamports()))
+}
+
+fn process_predicate_option(
+    invoke_context: &InvokeContext,
+    account_index: IndexOfAccount,
+    offset: u32,
+    length: u8,
+    expected: &[u8],
+) -> Result<bool, InstructionError> {
+    if !predicate_matches(invoke_context, account_index, offset, length, expected)? {
+        ic_msg!(
+            invoke_context,
+            "predicate not satisfied, skipping context creation"
+        );
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+fn process_distinct_endpoints_option(
+    invoke_context: &InvokeContext,
+    proof_type: ProofType,
+    proof_context: &[u8],
+) -> Result<(), InstructionError> {
+    let source = source_pubkey(proof_type, proof_context);
+    let destination = destination_pubkey(proof_type, proof_context);
+    if source.is_some() && source == destination {
+        ic_msg!(
+            invoke_context,
+            "transfer source and destination pubkeys are equal"
+        );
+        return Err(InstructionError::InvalidArgument);
+    }
+    Ok(())
+}
+
+fn process_min_epoch_option(
+    invoke_context: &InvokeContext,
+    min_epoch: u64,
+) -> Result<(), InstructionError> {
+    let current_epoch = invoke_context.get_sysvar_cache().get_clock()?.epoch;
+    if current_epoch < min_epoch {
+        ic_msg!(
+            invoke_context,
+            "proof submitted in epoch {} before minimum epoch {}",
+            current_epoch,
+            min_epoch
+        );
+        return Err(InstructionError::InvalidArgument);
+    }
+    Ok(())
+}
+
+/// Process an option that checks or updates its own accounts once the proof is verified.
+///
+/// Returns whether the context state is still created.
+fn process_account_option(
+    invoke_context: &InvokeContext,
+    account_index: IndexOfAccount,
+    option: &VerifyOption,
+    proof_type: ProofType,
+    proof_context: &[u8],
+    proof_data: &[u8],
+) -> Result<bool, InstructionError> {
+    match option {
+        VerifyOption::PriorContext => {
+            process_prior_context_option(invoke_context, account_index, proof_type, proof_context)?
+        }
+        VerifyOption::DestinationAccount => process_destination_account_option(
+            invoke_context,
+            account_index,
+            proof_type,
+            proof_context,
+        )?,
+        VerifyOption::AuthorityAllowlist => {
+            process_authority_allowlist_option(invoke_context, account_index)?
+        }
+        VerifyOption::RateLimit => process_rate_limit_option(invoke_context, account_index)?,
+        VerifyOption::UsageCounter => process_usage_counter_option(invoke_context, account_index)?,
+        VerifyOption::SlotWindows => process_slot_windows_option(invoke_context, account_index)?,
+        VerifyOption::MinSecurityLevel => {
+            process_min_security_level_option(invoke_context, account_index, proof_type)?
+        }
+        VerifyOption::KeyBlacklist => {
+            process_key_blacklist_option(invoke_context, account_index, proof_type, proof_context)?
+        }
+        VerifyOption::ContextAddress(address) => {
+            process_context_address_option(invoke_context, address)?
+        }
+        VerifyOption::PriorInstruction {
+            program_id,
+            discriminant,
+        } => process_prior_instruction_option(
+            invoke_context,
+            account_index,
+            program_id,
+            *discriminant,
+        )?,
+        VerifyOption::Nullifier => {
+            process_nullifier_option(invoke_context, account_index, proof_type, proof_context)?
+        }
+        VerifyOption::Predicate {
+            offset,
+            length,
+            expected,
+        } => {
+            return process_predicate_option(
+                invoke_context,
+                account_index,
+                *offset,
+                *length,
+                expected,
+            )
+        }
+        VerifyOption::DistinctEndpoints => {
+            process_distinct_endpoints_option(invoke_context, proof_type, proof_context)?
+        }
+        VerifyOption::RevealCommitment => {
+            process_reveal_commitment_option(invoke_context, account_index, proof_data)?
+        }
+        VerifyOption::VerifierSignature => {
+            process_verifier_signature_option(invoke_context, account_index, proof_data)?
+        }
+        VerifyOption::MinEpoch(min_epoch) => process_min_epoch_option(invoke_context, *min_epoch)?,
+        // the remaining options are parsed into `VerifyOptions`
+        VerifyOption::Delegate
+        | VerifyOption::MemoLog
+        | VerifyOption::AnchorEvent
+        | VerifyOption::StructuredLog
+        | VerifyOption::SlotLog
+        | VerifyOption::Receipt
+        | VerifyOption::Telemetry
+        | VerifyOption::MirrorAccount
+        | VerifyOption::Token2022Layout
+        | VerifyOption::ReferenceOnly
+        | VerifyOption::Partial
+        | VerifyOption::CreateContextAccount
+        | VerifyOption::ProgramManaged(_)
+        | VerifyOption::RouteCandidate(_)
+        | VerifyOption::Compressed
+        | VerifyOption::RetryNonce(_) => {}
+    }
+    Ok(true)
+}
+
+fn process_memo_log_option(invoke_context: &InvokeContext, proof_context: &[u8]) {
+    let memo: String = hash(proof_context)
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    stable_log::program_log(
+        &invoke_context.get_log_collector(),
+        &format!("Memo (len {}): {:?}", memo.len(), memo),
+    );
+}
+
+fn process_slot_log_option(invoke_context: &InvokeContext) -> Result<(), InstructionError> {
+    let clock = invoke_context.get_sysvar_cache().get_clock()?;
+    stable_log::program_log(
+        &invoke_context.get_log_collector(),
+        &format!(
+            "proof verified in slot {} of epoch {}",
+            clock.slot, clock.epoch
+        ),
+    );
+    Ok(())
+}
+
+fn process_receipt_option(
+    invoke_context: &mut InvokeContext,
+    proof_type: ProofType,
+    proof_context: &[u8],
+) -> Result<(), InstructionError> {
+    let receipt = verification_receipt(
+        proof_type,
+        proof_context,
+        invoke_context.get_sysvar_cache().get_clock()?.slot,
+    );
+    invoke_context
+        .transaction_context
+        .set_return_data(id(), receipt.to_vec())
+}
+
+fn process_telemetry_option(
+    invoke_context: &mut InvokeContext,
+    instruction_data: &[u8],
+    proof_type: ProofType,
+    context_state_created: bool,
+    compute_units_remaining_at_start: u64,
+) -> Result<(), InstructionError> {
+    let compute_units_remaining = invoke_context.get_remaining();
+    let compute_units_charged = ProofInstruction::instruction_type(instruction_data)
+        .map_or(0, compute_units)
+        .saturating_add(compute_units_remaining_at_start.saturating_sub(compute_units_remaining));
+    let telemetry = VerifyTelemetry {
+        version: VERIFY_TELEMETRY_VERSION,
+        proof_type: proof_type as u8,
+        context_state_created: context_state_created.into(),
+        compute_units_charged: compute_units_charged.into(),
+        compute_units_remaining: compute_units_remaining.into(),
+    };
+    invoke_context
+        .transaction_context
+        .set_return_data(id(), bytes_of(&telemetry).to_vec())
+}
+
+/// Emit the `ProofVerified` event for the `AnchorEvent` and `StructuredLog` options.
+fn process_proof_verified_options(
+    invoke_context: &InvokeContext,
+    verify_options: &VerifyOptions,
+    proof_type: ProofType,
+    context_state_created: bool,
+) -> Result<(), InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let context_state_account = if context_state_created {
+        Some(
+            *instruction_context
+                .try_borrow_instruction_account(
+                    transaction_context,
+                    verify_options.context_account_index(),
+                )?
+                .get_key(),
+        )
+    } else {
+        None
+    };
+    let event = ProofVerified {
+        version: PROOF_VERIFIED_EVENT_VERSION,
+        proof_type: proof_type as u8,
+        context_state_account,
+        slot: invoke_context.get_sysvar_cache().get_clock()?.slot,
+    };
+    if verify_options.anchor_event {
+        stable_log::program_data(&invoke_context.get_log_collector(), &[&event.encode()]);
+    }
+    if verify_options.structured_log {
+        ic_msg!(invoke_context, "{:?} proof verified", proof_type);
+        stable_log::program_log(&invoke_context.get_log_collector(), &event.to_json());
+    }
+    Ok(())
+}
+
+fn process_verify_proof<T, U>(
+    invoke_context: &mut InvokeContext,
+    instruction_data: &[u8],
+) -> Result<(), InstructionError>
+where
+    T: Pod + ZkProofData<U>,
+    U: Pod,
+{
+    #[cfg(feature = "instrumented")]
+    let mut parse_time = Measure::start("parse_time");
+    let (proof_data, options) = ProofInstruction::proof_data_with_options::<T, U>(instruction_data)
+        .ok_or_else(|| {
+            ic_msg!(invoke_context, "invalid proof data");
+            InstructionError::InvalidInstructionData
+        })?;
+    #[cfg(feature = "instrumented")]
+    parse_time.stop();
+
+    let verify_options = VerifyOptions::parse(invoke_context, &options, T::PROOF_TYPE)?;
+
+    // a processed retry nonce turns the instruction into a no-op before the proof is charged for
+    if let Some((account_index, nonce)) = verify_options.retry_nonce {
+        if !process_retry_nonce_option(invoke_context, account_index, nonce)? {
+            invoke_context.consume_checked(RETRY_NONCE_REPLAY_COMPUTE_UNITS)?;
+            ic_msg!(
+                invoke_context,
+                "retry nonce {} already processed, skipping verification",
+                nonce
+            );
+            return Ok(());
+        }
+    }
+
+    // Consume compute units since proof verification is an expensive operation
+    invoke_context.consume_checked(
+        ProofInstruction::instruction_type(instruction_data).map_or(0, compute_units),
+    )?;
+
+    // the compute meter once the proof is charged for, for the `Telemetry` option
+    let compute_units_remaining_at_start = invoke_context.get_remaining();
+
+    #[cfg(feature = "instrumented")]
+    let mut verify_time = Measure::start("verify_time");
+    proof_data.verify_proof().map_err(|err| {
+        ic_msg!(invoke_context, "proof_verification failed: {:?}", err);
+        InstructionError::InvalidInstructionData
+    })?;
+    #[cfg(feature = "instrumented")]
+    verify_time.stop();
+
+    let proof_context = *proof_data.context_data();
+
+    // stays zero if no proof context state is created
+    #[cfg(feature = "instrumented")]
+    let mut encode_time_ns = 0;
+
+    let mut create_context_state = verify_options.creates_context_state();
+    for (account_index, option) in &verify_options.account_options {
+        if !process_account_option(
+            invoke_context,
+            *account_index,
+            option,
+            T::PROOF_TYPE,
+            bytes_of(&proof_context),
+            bytes_of(proof_data),
+        )? {
+            create_context_state = false;
+        }
+    }
+
+    let context_state_delegate = match verify_options.delegate {
+        Some(account_index) => process_delegate_option(invoke_context, account_index)?,
+        None => Pubkey::default(),
+    };
+    let context_account_payer = verify_options
+        .create_context_account
+        .map(|account_index| process_create_context_account_option(invoke_context, account_index))
+        .transpose()?;
+    let program_managed_context = verify_options
+        .program_managed
+        .map(|(account_index, id)| {
+            process_program_managed_option(invoke_context, account_index, id)
+        })
+        .transpose()?;
+    let context_account_index = verify_options.context_account_index();
+
+    // create context state if accounts are provided with the instruction and any predicate holds
+    if create_context_state {
+        let context_state_authority = match &program_managed_context {
+            Some(context) => context.controlling_program_id,
+            None => {
+                let authority_index = verify_options.authority_index();
+                if verify_options.number_of_context_accounts <= usize::from(authority_index) {
+                    ic_msg!(
+                        invoke_context,
+                        "authority account required for context creation"
+                    );
+                    return Err(InstructionError::NotEnoughAccountKeys);
+                }
+                let transaction_context = &invoke_context.transaction_context;
+                let instruction_context = transaction_context.get_current_instruction_context()?;
+                *instruction_context
+                    .try_borrow_instruction_account(transaction_context, authority_index)?
+                    .get_key()
+            }
+        };
+
+        let policy_accounts = {
+            let transaction_context = &invoke_context.transaction_context;
+            let instruction_context = transaction_context.get_current_instruction_context()?;
+            verify_options
+                .policy_accounts
+                .iter()
+                .map(|account_index| {
+                    Ok(*instruction_context
+                        .try_borrow_instruction_account(transaction_context, *account_index)?
+                        .get_key())
+                })
+                .collect::<Result<Vec<_>, InstructionError>>()?
+        };
+
+        #[cfg(feature = "instrumented")]
+        let mut encode_time = Measure::start("encode_time");
+        let context_state_data = verify_options.encode_context_state(
+            &context_state_authority,
+            &context_state_delegate,
+            &policy_accounts,
+            proof_data,
+        )?;
+        #[cfg(feature = "instrumented")]
+        {
+            encode_time.stop();
+            encode_time_ns = encode_time.as_ns();
+        }
+
+        if let Some((payer, payer_lamports)) = context_account_payer {
+            // both accounts have signed the instruction, so no signer privileges are granted
+            create_proof_context_account(
+                invoke_context,
+                0,
+                &payer,
+                payer_lamports,
+                context_state_data.len(),
+                &[],
+            )?;
+        }
+        if let Some(context) = program_managed_context {
+            create_proof_context_account(
+                invoke_context,
+                context.account_index,
+                &context.payer,
+                context.payer_lamports,
+                context_state_data.len(),
+                &[context.address],
+            )?;
+        }
+
+        let transaction_context = &invoke_context.transaction_context;
+        let instruction_context = transaction_context.get_current_instruction_context()?;
+
+        // the mirror account receives the same context state as the proof context account
+        if let Some(mirror_account_index) = verify_options.mirror_account {
+            write_context_state(invoke_context, mirror_account_index, &context_state_data)?;
+        }
+
+        let mut proof_context_account = instruction_context
+            .try_borrow_instruction_account(transaction_context, context_account_index)?;
+        check_uninitialized_context_account(
+            proof_context_account.get_owner(),
+            proof_context_account.get_data(),
+            context_state_data.len(),
+        )?;
+        proof_context_account.set_data(context_state_data)?;
+    }
+
+    if verify_options.memo_log {
+        process_memo_log_option(invoke_context, bytes_of(&proof_context));
+    }
+
+    if verify_options.slot_log {
+        process_slot_log_option(invoke_context)?;
+    }
+
+    if verify_options.receipt {
+        process_receipt_option(invoke_context, T::PROOF_TYPE, bytes_of(&proof_context))?;
+    }
+
+    if verify_options.telemetry {
+        process_telemetry_option(
+            invoke_context,
+            instruction_data,
+            T::PROOF_TYPE,
+            create_context_state,
+            compute_units_remaining_at_start,
+        )?;
+    }
+
+    if verify_options.anchor_event || verify_options.structured_log {
+        process_proof_verified_options(
+            invoke_context,
+            &verify_options,
+            T::PROOF_TYPE,
+            create_context_state,
+        )?;
+    }
+
+    #[cfg(feature = "instrumented")]
+    ic_msg!(
+        invoke_context,
+        "proof timings: parse {}ns, verify {}ns, encode {}ns",
+        parse_time.as_ns(),
+        verify_time.as_ns(),
+        encode_time_ns
+    );
+
+    Ok(())
+}
+
+/// Check that an account can receive an encoded context state of `context_state_length` bytes.
+fn check_uninitialized_context_account(
+    owner: &Pubkey,
+    data: &[u8],
+    context_state_length: usize,
+) -> Result<(), InstructionError> {
+    if *owner != id() {
+        return Err(InstructionError::InvalidAccountOwner);
+    }
+
+    // the byte at the offset of the proof type holds the marker of an extended context state, so
+    // an account in either layout is initialized once that byte is set
+    let proof_context_state_meta = ProofContextStateMeta::try_from_bytes(data)?;
+
+    if proof_context_state_meta.proof_type != ProofType::Uninitialized.into() {
+        return Err(InstructionError::AccountAlreadyInitialized);
+    }
+
+    if data.len() != context_state_length {
+        return Err(InstructionError::InvalidAccountData);
+    }
+
+    Ok(())
+}
+
+fn write_context_state(
+    invoke_context: &InvokeContext,
+    account_index: IndexOfAccount,
+    context_state_data: &[u8],
+) -> Result<(), InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let mut account =
+        instruction_context.try_borrow_instruction_account(transaction_context, account_index)?;
+    check_uninitialized_context_account(
+        account.get_owner(),
+        account.get_data(),
+        context_state_data.len(),
+    )?;
+    account.set_data_from_slice(context_state_data)
+}
+
+fn process_batch_entry<T, U>(
+    invoke_context: &mut InvokeContext,
+    entry: &BatchEntry,
+) -> Result<(), InstructionError>
 where
     T: Pod + ZkProofData<U>,
     U: Pod,
 {
-    let transaction_context = &invoke_context.transaction_context;
-    let instruction_context = transaction_context.get_current_instruction_context()?;
-    let instruction_data = instruction_context.get_instruction_data();
-    let proof_data = ProofInstruction::proof_data::<T, U>(instruction_data).ok_or_else(|| {
+    let proof_data = bytemuck::try_from_bytes::<T>(&entry.proof_data).map_err(|_| {
         ic_msg!(invoke_context, "invalid proof data");
         InstructionError::InvalidInstructionData
     })?;
@@ -33,56 +1770,269 @@ where
         InstructionError::InvalidInstructionData
     })?;
 
-    // create context state if accounts are provided with the instruction
-    if instruction_context.get_number_of_instruction_accounts() > 0 {
+    if let Some((context_account_index, context_state_authority_index)) = entry.context_accounts {
+        let transaction_context = &invoke_context.transaction_context;
+        let instruction_context = transaction_context.get_current_instruction_context()?;
         let context_state_authority = *instruction_context
-            .try_borrow_instruction_account(transaction_context, 1)?
+            .try_borrow_instruction_account(
+                transaction_context,
+                IndexOfAccount::from(context_state_authority_index),
+            )?
             .get_key();
 
-        let mut proof_context_account =
-            instruction_context.try_borrow_instruction_account(transaction_context, 0)?;
+        let context_state_data = ProofContextState::encode(
+            &context_state_authority,
+            T::PROOF_TYPE,
+            proof_data.context_data(),
+        );
+        write_context_state(
+            invoke_context,
+            IndexOfAccount::from(context_account_index),
+            &context_state_data,
+        )?;
+    }
 
-        if *proof_context_account.get_owner() != id() {
-            return Err(InstructionError::InvalidAccountOwner);
+    Ok(())
+}
+
+fn process_verify_batch(invoke_context: &mut InvokeContext) -> Result<(), InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+
+    // the envelope is bounded by the transaction size, so decoding it is cheap
+    let batch = BorshVerifyBatch::try_from_slice(&instruction_context.get_instruction_data()[1..])
+        .map_err(|_| {
+            ic_msg!(invoke_context, "invalid borsh batch data");
+            InstructionError::InvalidInstructionData
+        })?;
+
+    let mut number_of_created_context_states: u64 = 0;
+    for (index, entry) in batch.entries.iter().enumerate() {
+        let instruction =
+            ProofInstruction::instruction_type(&[entry.instruction]).ok_or_else(|| {
+                ic_msg!(
+                    invoke_context,
+                    "batch entry {} has unknown instruction {}",
+                    index,
+                    entry.instruction
+                );
+                InstructionError::InvalidInstructionData
+            })?;
+
+        // each entry is charged as its own verify instruction
+        invoke_context.consume_checked(compute_units(instruction))?;
+
+        let result = match instruction {
+            ProofInstruction::VerifyCloseAccount => process_batch_entry::<
+                CloseAccountData,
+                CloseAccountProofContext,
+            >(invoke_context, entry),
+            ProofInstruction::VerifyWithdraw => {
+                process_batch_entry::<WithdrawData, WithdrawProofContext>(invoke_context, entry)
+            }
+            ProofInstruction::VerifyWithdrawWithheldTokens => process_batch_entry::<
+                WithdrawWithheldTokensData,
+                WithdrawWithheldTokensProofContext,
+            >(invoke_context, entry),
+            ProofInstruction::VerifyTransfer => {
+                process_batch_entry::<TransferData, TransferProofContext>(invoke_context, entry)
+            }
+            ProofInstruction::VerifyTransferWithFee => process_batch_entry::<
+                TransferWithFeeData,
+                TransferWithFeeProofContext,
+            >(invoke_context, entry),
+            ProofInstruction::VerifyPubkeyValidity => process_batch_entry::<
+                PubkeyValidityData,
+                PubkeyValidityProofContext,
+            >(invoke_context, entry),
+            ProofInstruction::VerifyTransferSum => process_batch_entry::<
+                TransferSumData,
+                TransferSumProofContext,
+            >(invoke_context, entry),
+            ProofInstruction::VerifyMaxValue => {
+                process_batch_entry::<MaxValueData, MaxValueProofContext>(invoke_context, entry)
+            }
+            ProofInstruction::VerifyParity => {
+                process_batch_entry::<ParityData, ParityProofContext>(invoke_context, entry)
+            }
+            ProofInstruction::VerifyGreaterThan => process_batch_entry::<
+                GreaterThanData,
+                GreaterThanProofContext,
+            >(invoke_context, entry),
+            ProofInstruction::VerifyKeyPossession => process_batch_entry::<
+                KeyPossessionData,
+                KeyPossessionProofContext,
+            >(invoke_context, entry),
+            ProofInstruction::VerifyCommitmentEquality => process_batch_entry::<
+                CommitmentEqualityData,
+                CommitmentEqualityProofContext,
+            >(invoke_context, entry),
+            ProofInstruction::CloseContextState
+            | ProofInstruction::MigrateContextState
+            | ProofInstruction::VerifyBorsh
+            | ProofInstruction::CommitProof
+            | ProofInstruction::SweepContextStates
+            | ProofInstruction::VerifyBatch
+            | ProofInstruction::VerifyWithheldFeeSum
+            | ProofInstruction::CompleteContextState
+            | ProofInstruction::InitializePolicyAccount
+            | ProofInstruction::UpdatePolicyAccount => {
+                Err(InstructionError::InvalidInstructionData)
+            }
+        };
+        if let Err(err) = result {
+            ic_msg!(
+                invoke_context,
+                "batch entry {} ({:?}) failed: {}",
+                index,
+                instruction,
+                err
+            );
+            return Err(err);
+        }
+
+        if entry.context_accounts.is_some() {
+            number_of_created_context_states = number_of_created_context_states.saturating_add(1);
         }
+    }
+
+    invoke_context.transaction_context.set_return_data(
+        id(),
+        number_of_created_context_states.to_le_bytes().to_vec(),
+    )
+}
 
-        let proof_context_state_meta =
-            ProofContextStateMeta::try_from_bytes(proof_context_account.get_data())?;
+fn process_verify_withheld_fee_sum(
+    invoke_context: &mut InvokeContext,
+) -> Result<(), InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let instruction_data = instruction_context.get_instruction_data();
 
-        if proof_context_state_meta.proof_type != ProofType::Uninitialized.into() {
-            return Err(InstructionError::AccountAlreadyInitialized);
+    let fee_sum_data_end = size_of::<WithheldFeeSumData>().saturating_add(1);
+    let fee_sum_data = instruction_data
+        .get(1..fee_sum_data_end)
+        .and_then(|data| bytemuck::try_from_bytes::<WithheldFeeSumData>(data).ok())
+        .copied();
+    let proof_data = instruction_data
+        .get(fee_sum_data_end..)
+        .and_then(|data| bytemuck::try_cast_slice::<u8, WithdrawWithheldTokensData>(data).ok())
+        .filter(|proof_data| !proof_data.is_empty())
+        .map(|proof_data| proof_data.to_vec());
+    let (fee_sum_data, proof_data) = match (fee_sum_data, proof_data) {
+        (Some(fee_sum_data), Some(proof_data)) => (fee_sum_data, proof_data),
+        _ => {
+            ic_msg!(invoke_context, "invalid withheld fee sum data");
+            return Err(InstructionError::InvalidInstructionData);
         }
+    };
 
-        let context_state_data = ProofContextState::encode(
-            &context_state_authority,
-            T::PROOF_TYPE,
-            proof_data.context_data(),
-        );
+    invoke_context.consume_checked(withheld_fee_sum_compute_units(proof_data.len() as u64))?;
+
+    fee_sum_data.verify_proof(&proof_data).map_err(|err| {
+        ic_msg!(invoke_context, "proof_verification failed: {:?}", err);
+        InstructionError::InvalidInstructionData
+    })
+}
+
+/// Returns the id of the program that invoked the current instruction, or `None` if the
+/// instruction is a transaction-level instruction.
+fn invoking_program_id(invoke_context: &InvokeContext) -> Result<Option<Pubkey>, InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let nesting_level = match transaction_context
+        .get_instruction_context_stack_height()
+        .checked_sub(2)
+    {
+        Some(nesting_level) => nesting_level,
+        None => return Ok(None),
+    };
+    let invoking_instruction_context =
+        transaction_context.get_instruction_context_at_nesting_level(nesting_level)?;
+    Ok(Some(
+        *invoking_instruction_context.get_last_program_key(transaction_context)?,
+    ))
+}
+
+/// Returns whether the instruction account at `multisig_index` is an SPL multisig account whose
+/// threshold of signers sign the instruction among the instruction accounts starting at
+/// `first_signer_index`.
+fn is_signed_by_multisig(
+    invoke_context: &InvokeContext,
+    multisig_index: IndexOfAccount,
+    first_signer_index: IndexOfAccount,
+) -> Result<bool, InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
 
-        if proof_context_account.get_data().len() != context_state_data.len() {
-            return Err(InstructionError::InvalidAccountData);
+    let multisig = {
+        let multisig_account = instruction_context
+            .try_borrow_instruction_account(transaction_context, multisig_index)?;
+        if *multisig_account.get_owner() != inline_spl_token::id()
+            && *multisig_account.get_owner() != inline_spl_token_2022::id()
+        {
+            return Ok(false);
         }
+        match inline_spl_token::unpack_multisig(multisig_account.get_data()) {
+            Some(multisig) => multisig,
+            None => {
+                ic_msg!(invoke_context, "authority is not a valid multisig account");
+                return Ok(false);
+            }
+        }
+    }; // done with `multisig_account`, so drop it to prevent a potential double borrow
 
-        proof_context_account.set_data(context_state_data)?;
+    // each multisig signer counts at most once, as in the SPL token program
+    let mut is_matched = vec![false; multisig.signers.len()];
+    let mut number_of_signers: u8 = 0;
+    for index in first_signer_index..instruction_context.get_number_of_instruction_accounts() {
+        if !instruction_context.is_instruction_account_signer(index)? {
+            continue;
+        }
+        let signer_pubkey = transaction_context.get_key_of_account_at_index(
+            instruction_context.get_index_of_instruction_account_in_transaction(index)?,
+        )?;
+        if let Some(matched) = multisig
+            .signers
+            .iter()
+            .zip(is_matched.iter_mut())
+            .find_map(|(signer, matched)| (signer == signer_pubkey && !*matched).then_some(matched))
+        {
+            *matched = true;
+            number_of_signers = number_of_signers.saturating_add(1);
+        }
     }
 
-    Ok(())
+    if number_of_signers < multisig.m {
+        ic_msg!(
+            invoke_context,
+            "multisig authority has {} of {} required signers",
+            number_of_signers,
+            multisig.m
+        );
+        return Ok(false);
+    }
+    Ok(true)
 }
 
 fn process_close_proof_context(invoke_context: &mut InvokeContext) -> Result<(), InstructionError> {
     let transaction_context = &invoke_context.transaction_context;
     let instruction_context = transaction_context.get_current_instruction_context()?;
 
-    let owner_pubkey = {
+    let (owner_pubkey, owner_is_signer) = {
         let owner_account =
             instruction_context.try_borrow_instruction_account(transaction_context, 2)?;
-
-        if !owner_account.is_signer() {
-            return Err(InstructionError::MissingRequiredSignature);
-        }
-        *owner_account.get_key()
+        (*owner_account.get_key(), owner_account.is_signer())
     }; // done with `owner_account`, so drop it to prevent a potential double borrow
 
+    // a program can act as the owner by invoking the instruction itself, and an SPL multisig
+    // account by the signatures of its signers
+    if !owner_is_signer
+        && invoking_program_id(invoke_context)? != Some(owner_pubkey)
+        && !is_signed_by_multisig(invoke_context, 2, 3)?
+    {
+        return Err(InstructionError::MissingRequiredSignature);
+    }
+
     let proof_context_account_pubkey = *instruction_context
         .try_borrow_instruction_account(transaction_context, 0)?
         .get_key();
@@ -95,11 +2045,9 @@ fn process_close_proof_context(invoke_context: &mut InvokeContext) -> Result<(),
 
     let mut proof_context_account =
         instruction_context.try_borrow_instruction_account(transaction_context, 0)?;
-    let proof_context_state_meta =
-        ProofContextStateMeta::try_from_bytes(proof_context_account.get_data())?;
-    let expected_owner_pubkey = proof_context_state_meta.context_state_authority;
+    let proof_context_state_header = ContextStateHeader::decode(proof_context_account.get_data())?;
 
-    if owner_pubkey != expected_owner_pubkey {
+    if !proof_context_state_header.is_authorized(&owner_pubkey) {
         return Err(InstructionError::InvalidAccountOwner);
     }
 
@@ -113,24 +2061,382 @@ fn process_close_proof_context(invoke_context: &mut InvokeContext) -> Result<(),
     Ok(())
 }
 
-pub fn process_instruction(invoke_context: &mut InvokeContext) -> Result<(), InstructionError> {
-    if invoke_context.get_stack_height() != TRANSACTION_LEVEL_STACK_HEIGHT {
-        // Not supported as an inner instruction
-        return Err(InstructionError::UnsupportedProgramId);
+fn process_sweep_context_states(
+    invoke_context: &mut InvokeContext,
+) -> Result<(), InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+
+    let destination_account_pubkey = *instruction_context
+        .try_borrow_instruction_account(transaction_context, 0)?
+        .get_key();
+    let owner_pubkey = {
+        let owner_account =
+            instruction_context.try_borrow_instruction_account(transaction_context, 1)?;
+
+        if !owner_account.is_signer() {
+            return Err(InstructionError::MissingRequiredSignature);
+        }
+        *owner_account.get_key()
+    }; // done with `owner_account`, so drop it to prevent a potential double borrow
+
+    let mut number_of_closed_accounts: usize = 0;
+    for index in 2..instruction_context.get_number_of_instruction_accounts() {
+        let mut proof_context_account =
+            instruction_context.try_borrow_instruction_account(transaction_context, index)?;
+        if *proof_context_account.get_key() == destination_account_pubkey
+            || *proof_context_account.get_owner() != id()
+            || !is_closeable_by(proof_context_account.get_data(), &owner_pubkey)
+        {
+            continue;
+        }
+
+        let mut destination_account =
+            instruction_context.try_borrow_instruction_account(transaction_context, 0)?;
+        destination_account.checked_add_lamports(proof_context_account.get_lamports())?;
+        proof_context_account.set_lamports(0)?;
+        proof_context_account.set_data_length(0)?;
+        proof_context_account.set_owner(system_program::id().as_ref())?;
+        number_of_closed_accounts = number_of_closed_accounts.saturating_add(1);
     }
 
-    // Consume compute units since proof verification is an expensive operation
-    {
-        // TODO: Tune the number of units consumed.  The current value is just a rough estimate
-        invoke_context.consume_checked(100_000)?;
+    ic_msg!(
+        invoke_context,
+        "closed {} proof context accounts",
+        number_of_closed_accounts
+    );
+
+    Ok(())
+}
+
+fn process_migrate_context_state(
+    invoke_context: &mut InvokeContext,
+) -> Result<(), InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let (version, compressed) = match instruction_context.get_instruction_data() {
+        [_, version, 0] => (*version, false),
+        [_, version, PROOF_CONTEXT_STATE_FLAG_COMPRESSED] => (*version, true),
+        _ => return Err(InstructionError::InvalidInstructionData),
+    };
+
+    let owner_pubkey = {
+        let owner_account =
+            instruction_context.try_borrow_instruction_account(transaction_context, 1)?;
+
+        if !owner_account.is_signer() {
+            return Err(InstructionError::MissingRequiredSignature);
+        }
+        *owner_account.get_key()
+    }; // done with `owner_account`, so drop it to prevent a potential double borrow
+
+    let mut proof_context_account =
+        instruction_context.try_borrow_instruction_account(transaction_context, 0)?;
+
+    if *proof_context_account.get_owner() != id() {
+        return Err(InstructionError::InvalidAccountOwner);
+    }
+
+    let proof_context_state_header = ContextStateHeader::decode(proof_context_account.get_data())?;
+
+    if proof_context_state_header.proof_type == ProofType::Uninitialized.into() {
+        return Err(InstructionError::UninitializedAccount);
+    }
+
+    if proof_context_state_header.context_state_authority != owner_pubkey {
+        return Err(InstructionError::InvalidAccountOwner);
+    }
+
+    // fails with `InvalidArgument` if the migration is unsupported or a no-op
+    let context_state_data =
+        migrate_context_state_data(proof_context_account.get_data(), version, compressed).map_err(
+            |err| {
+                ic_msg!(
+                    invoke_context,
+                    "cannot migrate context state from version {} to version {}",
+                    proof_context_state_header.version,
+                    version
+                );
+                err
+            },
+        )?;
+
+    let rent = invoke_context.get_sysvar_cache().get_rent()?;
+    if !rent.is_exempt(
+        proof_context_account.get_lamports(),
+        context_state_data.len(),
+    ) {
+        return Err(InstructionError::InsufficientFunds);
+    }
+
+    proof_context_account.set_data(context_state_data)?;
+
+    Ok(())
+}
+
+fn process_complete_context_state(
+    invoke_context: &mut InvokeContext,
+) -> Result<(), InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+
+    let (delegate_pubkey, delegate_is_signer) = {
+        let delegate_account =
+            instruction_context.try_borrow_instruction_account(transaction_context, 1)?;
+        (*delegate_account.get_key(), delegate_account.is_signer())
+    }; // done with `delegate_account`, so drop it to prevent a potential double borrow
+
+    if !delegate_is_signer && !is_signed_by_multisig(invoke_context, 1, 2)? {
+        return Err(InstructionError::MissingRequiredSignature);
     }
 
+    let mut proof_context_account =
+        instruction_context.try_borrow_instruction_account(transaction_context, 0)?;
+
+    if *proof_context_account.get_owner() != id() {
+        return Err(InstructionError::InvalidAccountOwner);
+    }
+
+    let proof_context_state_header = ContextStateHeader::decode(proof_context_account.get_data())?;
+
+    if proof_context_state_header.is_complete() {
+        ic_msg!(invoke_context, "proof context state is already complete");
+        return Err(InstructionError::InvalidAccountData);
+    }
+
+    if proof_context_state_header.context_state_delegate != delegate_pubkey {
+        return Err(InstructionError::InvalidAccountOwner);
+    }
+
+    ExtendedProofContextStateMeta::try_from_bytes_mut(proof_context_account.get_data_mut()?)?
+        .flags &= !PROOF_CONTEXT_STATE_FLAG_INCOMPLETE;
+
+    Ok(())
+}
+
+fn process_commit_proof(invoke_context: &mut InvokeContext) -> Result<(), InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let proof_hash: [u8; 32] = instruction_context
+        .get_instruction_data()
+        .get(1..)
+        .and_then(|data| data.try_into().ok())
+        .ok_or(InstructionError::InvalidInstructionData)?;
+
+    let committer = {
+        let committer_account =
+            instruction_context.try_borrow_instruction_account(transaction_context, 1)?;
+
+        if !committer_account.is_signer() {
+            return Err(InstructionError::MissingRequiredSignature);
+        }
+        *committer_account.get_key()
+    }; // done with `committer_account`, so drop it to prevent a potential double borrow
+
+    let mut commitment_account =
+        instruction_context.try_borrow_instruction_account(transaction_context, 0)?;
+
+    if *commitment_account.get_owner() != id() {
+        return Err(InstructionError::InvalidAccountOwner);
+    }
+
+    if commitment_account.get_data().len() != size_of::<ProofCommitment>() {
+        return Err(InstructionError::InvalidAccountData);
+    }
+
+    if ProofCommitment::try_from_bytes(commitment_account.get_data()).is_ok() {
+        return Err(InstructionError::AccountAlreadyInitialized);
+    }
+
+    commitment_account
+        .set_data_from_slice(bytes_of(&ProofCommitment::new(&committer, proof_hash)))?;
+
+    Ok(())
+}
+
+fn process_initialize_policy_account(
+    invoke_context: &mut InvokeContext,
+) -> Result<(), InstructionError> {
     let transaction_context = &invoke_context.transaction_context;
     let instruction_context = transaction_context.get_current_instruction_context()?;
     let instruction_data = instruction_context.get_instruction_data();
-    let instruction = ProofInstruction::instruction_type(instruction_data)
+
+    let discriminator: [u8; 8] = instruction_data
+        .get(1..9)
+        .and_then(|data| data.try_into().ok())
+        .ok_or(InstructionError::InvalidInstructionData)?;
+    let policy_id = instruction_data
+        .get(9..17)
+        .and_then(|data| data.try_into().ok())
+        .map(u64::from_le_bytes)
+        .ok_or(InstructionError::InvalidInstructionData)?;
+    let body = &instruction_data[17..];
+    if validate_policy_account_body(&discriminator, body).is_err() {
+        ic_msg!(invoke_context, "invalid policy account body");
+        return Err(InstructionError::InvalidInstructionData);
+    }
+
+    let admin = {
+        let admin_account =
+            instruction_context.try_borrow_instruction_account(transaction_context, 1)?;
+        if !admin_account.is_signer() {
+            return Err(InstructionError::MissingRequiredSignature);
+        }
+        *admin_account.get_key()
+    };
+
+    let (address, bump) = find_policy_account_address(&discriminator, &admin, policy_id);
+    if *instruction_context
+        .try_borrow_instruction_account(transaction_context, 0)?
+        .get_key()
+        != address
+    {
+        ic_msg!(
+            invoke_context,
+            "policy account does not match the address of the admin and id"
+        );
+        return Err(InstructionError::InvalidArgument);
+    }
+
+    let mut policy_account_data = bytes_of(&PolicyAccountHeader {
+        discriminator,
+        admin,
+        id: policy_id.into(),
+        bump,
+    })
+    .to_vec();
+    policy_account_data.extend_from_slice(body);
+
+    let (payer, payer_lamports) = process_create_context_account_option(invoke_context, 2)?;
+    create_proof_context_account(
+        invoke_context,
+        0,
+        &payer,
+        payer_lamports,
+        policy_account_data.len(),
+        &[address],
+    )?;
+
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    instruction_context
+        .try_borrow_instruction_account(transaction_context, 0)?
+        .set_data_from_slice(&policy_account_data)
+}
+
+fn process_update_policy_account(
+    invoke_context: &mut InvokeContext,
+) -> Result<(), InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let body = instruction_context
+        .get_instruction_data()
+        .get(1..)
+        .ok_or(InstructionError::InvalidInstructionData)?;
+
+    let admin = {
+        let admin_account =
+            instruction_context.try_borrow_instruction_account(transaction_context, 1)?;
+        if !admin_account.is_signer() {
+            return Err(InstructionError::MissingRequiredSignature);
+        }
+        *admin_account.get_key()
+    };
+
+    let account = instruction_context.try_borrow_instruction_account(transaction_context, 0)?;
+    if *account.get_owner() != id() {
+        return Err(InstructionError::InvalidAccountOwner);
+    }
+    let header = *PolicyAccountHeader::try_from_bytes(account.get_data())?;
+    if header.address()? != *account.get_key() {
+        return Err(InstructionError::InvalidAccountData);
+    }
+    if header.admin != admin {
+        ic_msg!(invoke_context, "policy account admin mismatch");
+        return Err(InstructionError::InvalidAccountData);
+    }
+    if validate_policy_account_body(&header.discriminator, body).is_err() {
+        ic_msg!(invoke_context, "invalid policy account body");
+        return Err(InstructionError::InvalidInstructionData);
+    }
+
+    let mut policy_account_data = bytes_of(&header).to_vec();
+    policy_account_data.extend_from_slice(body);
+    let policy_account_pubkey = *account.get_key();
+    let shortfall = invoke_context
+        .get_sysvar_cache()
+        .get_rent()?
+        .minimum_balance(policy_account_data.len())
+        .saturating_sub(account.get_lamports());
+    drop(account);
+
+    // the payer signed this instruction, so the transfer needs no signature of the proof program
+    if shortfall > 0 {
+        let (payer, _) = process_create_context_account_option(invoke_context, 2)?;
+        invoke_context.native_invoke(
+            system_instruction::transfer(&payer, &policy_account_pubkey, shortfall).into(),
+            &[],
+        )?;
+    }
+
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    instruction_context
+        .try_borrow_instruction_account(transaction_context, 0)?
+        .set_data_from_slice(&policy_account_data)
+}
+
+pub fn process_instruction(invoke_context: &mut InvokeContext) -> Result<(), InstructionError> {
+    let transaction_context = &invoke_context.transaction_context;
+    let instruction_context = transaction_context.get_current_instruction_context()?;
+    let mut instruction_data = instruction_context.get_instruction_data().to_vec();
+    let mut instruction = ProofInstruction::instruction_type(&instruction_data)
         .ok_or(InstructionError::InvalidInstructionData)?;
 
+    // Only closing a context state and verifying a proof into a program-managed context state are
+    // supported as inner instructions, so that programs can act as context state authorities. The
+    // options of a verify instruction are checked by `process_verify_proof`.
+    if invoke_context.get_stack_height() != TRANSACTION_LEVEL_STACK_HEIGHT
+        && instruction != ProofInstruction::CloseContextState
+        && expected_proof_type(instruction) == ProofType::Uninitialized
+    {
+        return Err(InstructionError::UnsupportedProgramId);
+    }
+
+    // Consume compute units since proof verification is an expensive operation. Verify proof
+    // instructions are charged by `process_verify_proof`, which charges a replayed retry nonce
+    // less.
+    if expected_proof_type(instruction) == ProofType::Uninitialized
+        && instruction != ProofInstruction::VerifyBorsh
+    {
+        invoke_context.consume_checked(compute_units(instruction))?;
+    }
+
+    if instruction == ProofInstruction::VerifyBorsh {
+        ic_msg!(invoke_context, "VerifyBorsh");
+        // the envelope is bounded by the transaction size, so decoding it is cheap
+        instruction_data = BorshVerifyProof::try_from_slice(&instruction_data[1..])
+            .map(|envelope| envelope.to_instruction_data())
+            .map_err(|_| {
+                ic_msg!(invoke_context, "invalid borsh proof data");
+                InstructionError::InvalidInstructionData
+            })?;
+        instruction = match ProofInstruction::instruction_type(&instruction_data) {
+            Some(ProofInstruction::CloseContextState)
+            | Some(ProofInstruction::MigrateContextState)
+            | Some(ProofInstruction::VerifyBorsh)
+            | Some(ProofInstruction::CommitProof)
+            | Some(ProofInstruction::SweepContextStates)
+            | Some(ProofInstruction::VerifyBatch)
+            | Some(ProofInstruction::VerifyWithheldFeeSum)
+            | Some(ProofInstruction::CompleteContextState)
+            | Some(ProofInstruction::InitializePolicyAccount)
+            | Some(ProofInstruction::UpdatePolicyAccount)
+            | None => return Err(InstructionError::InvalidInstructionData),
+            Some(instruction) => instruction,
+        };
+    }
+
     match instruction {
         ProofInstruction::CloseContextState => {
             ic_msg!(invoke_context, "CloseContextState");
@@ -138,29 +2444,121 @@ pub fn process_instruction(invoke_context: &mut InvokeContext) -> Result<(), Ins
         }
         ProofInstruction::VerifyCloseAccount => {
             ic_msg!(invoke_context, "VerifyCloseAccount");
-            process_verify_proof::<CloseAccountData, CloseAccountProofContext>(invoke_context)
+            process_verify_proof::<CloseAccountData, CloseAccountProofContext>(
+                invoke_context,
+                &instruction_data,
+            )
         }
         ProofInstruction::VerifyWithdraw => {
             ic_msg!(invoke_context, "VerifyWithdraw");
-            process_verify_proof::<WithdrawData, WithdrawProofContext>(invoke_context)
+            process_verify_proof::<WithdrawData, WithdrawProofContext>(
+                invoke_context,
+                &instruction_data,
+            )
         }
         ProofInstruction::VerifyWithdrawWithheldTokens => {
             ic_msg!(invoke_context, "VerifyWithdrawWithheldTokens");
             process_verify_proof::<WithdrawWithheldTokensData, WithdrawWithheldTokensProofContext>(
                 invoke_context,
+                &instruction_data,
             )
         }
         ProofInstruction::VerifyTransfer => {
             ic_msg!(invoke_context, "VerifyTransfer");
-            process_verify_proof::<TransferData, TransferProofContext>(invoke_context)
+            process_verify_proof::<TransferData, TransferProofContext>(
+                invoke_context,
+                &instruction_data,
+            )
         }
         ProofInstruction::VerifyTransferWithFee => {
             ic_msg!(invoke_context, "VerifyTransferWithFee");
-            process_verify_proof::<TransferWithFeeData, TransferWithFeeProofContext>(invoke_context)
+            process_verify_proof::<TransferWithFeeData, TransferWithFeeProofContext>(
+                invoke_context,
+                &instruction_data,
+            )
         }
         ProofInstruction::VerifyPubkeyValidity => {
             ic_msg!(invoke_context, "VerifyPubkeyValidity");
-            process_verify_proof::<PubkeyValidityData, PubkeyValidityProofContext>(invoke_context)
+            process_verify_proof::<PubkeyValidityData, PubkeyValidityProofContext>(
+                invoke_context,
+                &instruction_data,
+            )
+        }
+        ProofInstruction::VerifyTransferSum => {
+            ic_msg!(invoke_context, "VerifyTransferSum");
+            process_verify_proof::<TransferSumData, TransferSumProofContext>(
+                invoke_context,
+                &instruction_data,
+            )
+        }
+        ProofInstruction::VerifyMaxValue => {
+            ic_msg!(invoke_context, "VerifyMaxValue");
+            process_verify_proof::<MaxValueData, MaxValueProofContext>(
+                invoke_context,
+                &instruction_data,
+            )
+        }
+        ProofInstruction::VerifyParity => {
+            ic_msg!(invoke_context, "VerifyParity");
+            process_verify_proof::<ParityData, ParityProofContext>(
+                invoke_context,
+                &instruction_data,
+            )
+        }
+        ProofInstruction::VerifyGreaterThan => {
+            ic_msg!(invoke_context, "VerifyGreaterThan");
+            process_verify_proof::<GreaterThanData, GreaterThanProofContext>(
+                invoke_context,
+                &instruction_data,
+            )
+        }
+        ProofInstruction::VerifyKeyPossession => {
+            ic_msg!(invoke_context, "VerifyKeyPossession");
+            process_verify_proof::<KeyPossessionData, KeyPossessionProofContext>(
+                invoke_context,
+                &instruction_data,
+            )
+        }
+        ProofInstruction::VerifyCommitmentEquality => {
+            ic_msg!(invoke_context, "VerifyCommitmentEquality");
+            process_verify_proof::<CommitmentEqualityData, CommitmentEqualityProofContext>(
+                invoke_context,
+                &instruction_data,
+            )
+        }
+        ProofInstruction::MigrateContextState => {
+            ic_msg!(invoke_context, "MigrateContextState");
+            process_migrate_context_state(invoke_context)
+        }
+        ProofInstruction::CommitProof => {
+            ic_msg!(invoke_context, "CommitProof");
+            process_commit_proof(invoke_context)
+        }
+        ProofInstruction::SweepContextStates => {
+            ic_msg!(invoke_context, "SweepContextStates");
+            process_sweep_context_states(invoke_context)
+        }
+        ProofInstruction::VerifyBatch => {
+            ic_msg!(invoke_context, "VerifyBatch");
+            process_verify_batch(invoke_context)
+        }
+        ProofInstruction::VerifyWithheldFeeSum => {
+            ic_msg!(invoke_context, "VerifyWithheldFeeSum");
+            process_verify_withheld_fee_sum(invoke_context)
+        }
+        ProofInstruction::CompleteContextState => {
+            ic_msg!(invoke_context, "CompleteContextState");
+            process_complete_context_state(invoke_context)
+        }
+        ProofInstruction::InitializePolicyAccount => {
+            ic_msg!(invoke_context, "InitializePolicyAccount");
+            process_initialize_policy_account(invoke_context)
+        }
+        ProofInstruction::UpdatePolicyAccount => {
+            ic_msg!(invoke_context, "UpdatePolicyAccount");
+            process_update_policy_account(invoke_context)
         }
+        // nested envelopes are rejected when the envelope is decoded
+        ProofInstruction::VerifyBorsh => Err(InstructionError::InvalidInstructionData),
     }
 }
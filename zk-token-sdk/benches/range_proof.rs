@@ -0,0 +1,114 @@
+#![feature(test)]
+
+extern crate test;
+use {
+    solana_zk_token_sdk::{
+        encryption::elgamal::ElGamalKeypair,
+        instruction::{
+            FeeParameters, Pod, TransferData, TransferWithFeeData, WithdrawData, ZkProofData,
+        },
+        BulletproofGens,
+    },
+    test::Bencher,
+};
+
+// The withdraw, transfer, and transfer with fee proofs verify range proofs over 64, 128, and 256
+// bits respectively. Each pair of benches verifies the same proof, once borrowing the shared
+// generators and once deriving the generators of the range proof as it did before they were
+// shared, so the difference between the two is the cost of deriving the generators per proof.
+
+fn withdraw_data() -> WithdrawData {
+    let keypair = ElGamalKeypair::new_rand();
+    let current_balance: u64 = 77;
+    let current_ciphertext = keypair.public.encrypt(current_balance);
+    WithdrawData::new(55, &keypair, current_balance, &current_ciphertext).unwrap()
+}
+
+fn transfer_data() -> TransferData {
+    let source_keypair = ElGamalKeypair::new_rand();
+    let destination_pubkey = ElGamalKeypair::new_rand().public;
+    let auditor_pubkey = ElGamalKeypair::new_rand().public;
+    let spendable_balance: u64 = 77;
+    let spendable_ciphertext = source_keypair.public.encrypt(spendable_balance);
+    TransferData::new(
+        55,
+        (spendable_balance, &spendable_ciphertext),
+        &source_keypair,
+        (&destination_pubkey, &auditor_pubkey),
+    )
+    .unwrap()
+}
+
+fn transfer_with_fee_data() -> TransferWithFeeData {
+    let source_keypair = ElGamalKeypair::new_rand();
+    let destination_pubkey = ElGamalKeypair::new_rand().public;
+    let auditor_pubkey = ElGamalKeypair::new_rand().public;
+    let withdraw_withheld_authority_pubkey = ElGamalKeypair::new_rand().public;
+    let spendable_balance: u64 = 120;
+    let spendable_ciphertext = source_keypair.public.encrypt(spendable_balance);
+    let fee_parameters = FeeParameters {
+        fee_rate_basis_points: 400,
+        maximum_fee: 3,
+    };
+    TransferWithFeeData::new(
+        100,
+        (spendable_balance, &spendable_ciphertext),
+        &source_keypair,
+        (&destination_pubkey, &auditor_pubkey),
+        fee_parameters,
+        &withdraw_withheld_authority_pubkey,
+    )
+    .unwrap()
+}
+
+fn bench_verify_with_shared_generators<T, U>(bencher: &mut Bencher, proof_data: &T, nm: usize)
+where
+    T: ZkProofData<U>,
+    U: Pod,
+{
+    bencher.iter(|| {
+        test::black_box(BulletproofGens::shared(nm));
+        proof_data.verify_proof().unwrap();
+    });
+}
+
+fn bench_verify_with_fresh_generators<T, U>(bencher: &mut Bencher, proof_data: &T, nm: usize)
+where
+    T: ZkProofData<U>,
+    U: Pod,
+{
+    bencher.iter(|| {
+        test::black_box(BulletproofGens::new(nm));
+        proof_data.verify_proof().unwrap();
+    });
+}
+
+#[bench]
+fn bench_range_proof_u64_shared_generators(bencher: &mut Bencher) {
+    bench_verify_with_shared_generators(bencher, &withdraw_data(), 64);
+}
+
+#[bench]
+fn bench_range_proof_u64_fresh_generators(bencher: &mut Bencher) {
+    bench_verify_with_fresh_generators(bencher, &withdraw_data(), 64);
+}
+
+#[bench]
+fn bench_range_proof_u128_shared_generators(bencher: &mut Bencher) {
+    bench_verify_with_shared_generators(bencher, &transfer_data(), 128);
+}
+
+#[bench]
+fn bench_range_proof_u128_fresh_generators(bencher: &mut Bencher) {
+    bench_verify_with_fresh_generators(bencher, &transfer_data(), 128);
+}
+
+#[bench]
+fn bench_range_proof_u256_shared_generators(bencher: &mut Bencher) {
+    bench_verify_with_shared_generators(bencher, &transfer_with_fee_data(), 256);
+}
+
+#[bench]
+fn bench_range_proof_u256_fresh_generators(bencher: &mut Bencher) {
+    bench_verify_with_fresh_generators(bencher, &transfer_with_fee_data(), 256);
+}
@@ -20,6 +20,14 @@ pub enum ProofError {
     PubkeyDeserialization,
     #[error("ciphertext does not exist in instruction data")]
     MissingCiphertext,
+    #[error("transfers are not from the same source")]
+    TransferSourceMismatch,
+    #[error("parity is neither 0 nor 1")]
+    InvalidParity,
+    #[error("withheld fees are not of the same withdraw withheld authority")]
+    WithdrawWithheldAuthorityMismatch,
+    #[error("withheld fees do not sum to the total")]
+    WithheldFeeSumMismatch,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
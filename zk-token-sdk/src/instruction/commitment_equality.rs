@@ -0,0 +1,158 @@
+#[cfg(not(target_os = "solana"))]
+use {
+    crate::{
+        encryption::pedersen::{Pedersen, PedersenCommitment, PedersenOpening},
+        errors::ProofError,
+        sigma_proofs::equality_proof::CommCommEqualityProof,
+        transcript::TranscriptProtocol,
+    },
+    merlin::Transcript,
+    std::convert::TryInto,
+};
+use {
+    crate::{
+        instruction::{ProofType, ZkProofData},
+        zk_token_elgamal::pod,
+    },
+    bytemuck::{Pod, Zeroable},
+};
+
+/// This struct includes the cryptographic proof *and* the account data information needed to
+/// verify the proof
+///
+/// - The pre-instruction should call CommitmentEqualityData::verify_proof(&self)
+/// - The actual program should check that the commitments in this struct are consistent with the
+///   values whose equality it requires
+///
+/// The proof shows that the two commitments encode the same value without revealing the value.
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct CommitmentEqualityData {
+    /// The context data for the commitment equality proof
+    pub context: CommitmentEqualityProofContext,
+
+    /// Proof that the commitments encode the same value
+    pub proof: CommitmentEqualityProof,
+}
+
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct CommitmentEqualityProofContext {
+    /// The first Pedersen commitment
+    pub first_commitment: pod::PedersenCommitment, // 32 bytes
+
+    /// The second Pedersen commitment
+    pub second_commitment: pod::PedersenCommitment, // 32 bytes
+}
+
+#[cfg(not(target_os = "solana"))]
+impl CommitmentEqualityData {
+    pub fn new(
+        value: u64,
+        first_opening: &PedersenOpening,
+        second_opening: &PedersenOpening,
+    ) -> Result<Self, ProofError> {
+        let pod_first_commitment: pod::PedersenCommitment =
+            Pedersen::with(value, first_opening).into();
+        let pod_second_commitment: pod::PedersenCommitment =
+            Pedersen::with(value, second_opening).into();
+
+        let context = CommitmentEqualityProofContext {
+            first_commitment: pod_first_commitment,
+            second_commitment: pod_second_commitment,
+        };
+
+        let mut transcript =
+            CommitmentEqualityProof::transcript_new(&pod_first_commitment, &pod_second_commitment);
+        let proof = CommitmentEqualityProof::new(first_opening, second_opening, &mut transcript);
+
+        Ok(Self { context, proof })
+    }
+}
+
+impl ZkProofData<CommitmentEqualityProofContext> for CommitmentEqualityData {
+    const PROOF_TYPE: ProofType = ProofType::CommitmentEquality;
+
+    fn context_data(&self) -> &CommitmentEqualityProofContext {
+        &self.context
+    }
+
+    #[cfg(not(target_os = "solana"))]
+    fn verify_proof(&self) -> Result<(), ProofError> {
+        let mut transcript = CommitmentEqualityProof::transcript_new(
+            &self.context.first_commitment,
+            &self.context.second_commitment,
+        );
+
+        let first_commitment = self.context.first_commitment.try_into()?;
+        let second_commitment = self.context.second_commitment.try_into()?;
+        self.proof
+            .verify(&first_commitment, &second_commitment, &mut transcript)
+    }
+}
+
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct CommitmentEqualityProof {
+    /// Associated commitment equality sigma proof
+    pub proof: pod::CommCommEqualityProof, // 64 bytes
+}
+
+#[cfg(not(target_os = "solana"))]
+impl CommitmentEqualityProof {
+    fn transcript_new(
+        first_commitment: &pod::PedersenCommitment,
+        second_commitment: &pod::PedersenCommitment,
+    ) -> Transcript {
+        let mut transcript = Transcript::new(b"CommitmentEqualityProof");
+
+        transcript.append_commitment(b"first-commitment", first_commitment);
+        transcript.append_commitment(b"second-commitment", second_commitment);
+
+        transcript
+    }
+
+    pub fn new(
+        first_opening: &PedersenOpening,
+        second_opening: &PedersenOpening,
+        transcript: &mut Transcript,
+    ) -> Self {
+        let proof = CommCommEqualityProof::new(first_opening, second_opening, transcript);
+        Self {
+            proof: proof.into(),
+        }
+    }
+
+    pub fn verify(
+        &self,
+        first_commitment: &PedersenCommitment,
+        second_commitment: &PedersenCommitment,
+        transcript: &mut Transcript,
+    ) -> Result<(), ProofError> {
+        let proof: CommCommEqualityProof = self.proof.try_into()?;
+        proof.verify(first_commitment, second_commitment, transcript)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_commitment_equality_correctness() {
+        let first_opening = PedersenOpening::new_rand();
+        let second_opening = PedersenOpening::new_rand();
+
+        let commitment_equality_data =
+            CommitmentEqualityData::new(55, &first_opening, &second_opening).unwrap();
+        assert!(commitment_equality_data.verify_proof().is_ok());
+
+        // a proof for a second commitment to a different value
+        let mut commitment_equality_data =
+            CommitmentEqualityData::new(55, &first_opening, &second_opening).unwrap();
+        commitment_equality_data.context.second_commitment =
+            Pedersen::with(77_u64, &second_opening).into();
+        assert!(commitment_equality_data.verify_proof().is_err());
+    }
+}
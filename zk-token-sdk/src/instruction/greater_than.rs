@@ -0,0 +1,177 @@
+#[cfg(not(target_os = "solana"))]
+use {
+    crate::{
+        encryption::pedersen::{Pedersen, PedersenCommitment, PedersenOpening},
+        errors::ProofError,
+        range_proof::RangeProof,
+        transcript::TranscriptProtocol,
+    },
+    merlin::Transcript,
+    std::convert::TryInto,
+};
+use {
+    crate::{
+        instruction::{ProofType, ZkProofData},
+        zk_token_elgamal::pod,
+    },
+    bytemuck::{Pod, Zeroable},
+};
+
+#[cfg(not(target_os = "solana"))]
+const GREATER_THAN_BIT_LENGTH: usize = 64;
+
+/// This struct includes the cryptographic proof *and* the account data information needed to
+/// verify the proof
+///
+/// - The pre-instruction should call GreaterThanData::verify_proof(&self)
+/// - The actual program should check that the commitments in this struct are consistent with the
+///   values that it compares
+///
+/// The proof shows that the committed value minus the committed threshold minus one is a 64-bit
+/// number. It does not show that the committed values themselves are non-negative, so they should
+/// be range proven separately if they are not already known to be 64-bit numbers.
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct GreaterThanData {
+    /// The context data for the greater-than proof
+    pub context: GreaterThanProofContext,
+
+    /// Proof that the committed value is greater than the committed threshold
+    pub proof: GreaterThanProof,
+}
+
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct GreaterThanProofContext {
+    /// The Pedersen commitment to the compared value
+    pub commitment: pod::PedersenCommitment, // 32 bytes
+
+    /// The Pedersen commitment to the threshold that the value exceeds
+    pub threshold_commitment: pod::PedersenCommitment, // 32 bytes
+}
+
+#[cfg(not(target_os = "solana"))]
+impl GreaterThanData {
+    pub fn new(
+        value: u64,
+        opening: &PedersenOpening,
+        threshold: u64,
+        threshold_opening: &PedersenOpening,
+    ) -> Result<Self, ProofError> {
+        // errors if the value does not exceed the threshold
+        let remaining_value = value
+            .checked_sub(threshold)
+            .and_then(|difference| difference.checked_sub(1))
+            .ok_or(ProofError::Generation)?;
+
+        let pod_commitment: pod::PedersenCommitment = Pedersen::with(value, opening).into();
+        let pod_threshold_commitment: pod::PedersenCommitment =
+            Pedersen::with(threshold, threshold_opening).into();
+
+        // `commitment - threshold_commitment - G` is a commitment to the remaining value under the
+        // difference of the openings
+        let remaining_opening = opening - threshold_opening;
+
+        let mut transcript =
+            GreaterThanProof::transcript_new(&pod_commitment, &pod_threshold_commitment);
+        let range_proof = RangeProof::new(
+            vec![remaining_value],
+            vec![GREATER_THAN_BIT_LENGTH],
+            vec![&remaining_opening],
+            &mut transcript,
+        );
+
+        let context = GreaterThanProofContext {
+            commitment: pod_commitment,
+            threshold_commitment: pod_threshold_commitment,
+        };
+
+        let proof = GreaterThanProof {
+            range_proof: range_proof.try_into().map_err(|_| ProofError::Generation)?,
+        };
+
+        Ok(Self { context, proof })
+    }
+}
+
+impl ZkProofData<GreaterThanProofContext> for GreaterThanData {
+    const PROOF_TYPE: ProofType = ProofType::GreaterThan;
+
+    fn context_data(&self) -> &GreaterThanProofContext {
+        &self.context
+    }
+
+    #[cfg(not(target_os = "solana"))]
+    fn verify_proof(&self) -> Result<(), ProofError> {
+        let mut transcript = GreaterThanProof::transcript_new(
+            &self.context.commitment,
+            &self.context.threshold_commitment,
+        );
+
+        let commitment: PedersenCommitment = self.context.commitment.try_into()?;
+        let threshold_commitment: PedersenCommitment =
+            self.context.threshold_commitment.try_into()?;
+        let remaining_commitment =
+            &(&commitment - &threshold_commitment) - &Pedersen::encode(1_u64);
+
+        let range_proof: RangeProof = self.proof.range_proof.try_into()?;
+        range_proof.verify(
+            vec![&remaining_commitment],
+            vec![GREATER_THAN_BIT_LENGTH],
+            &mut transcript,
+        )?;
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct GreaterThanProof {
+    /// Range proof of the committed value minus the committed threshold minus one
+    pub range_proof: pod::RangeProof64, // 672 bytes
+}
+
+#[cfg(not(target_os = "solana"))]
+impl GreaterThanProof {
+    fn transcript_new(
+        commitment: &pod::PedersenCommitment,
+        threshold_commitment: &pod::PedersenCommitment,
+    ) -> Transcript {
+        let mut transcript = Transcript::new(b"greater-than-proof");
+
+        transcript.append_commitment(b"commitment", commitment);
+        transcript.append_commitment(b"threshold-commitment", threshold_commitment);
+
+        transcript
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_greater_than_correctness() {
+        let opening = PedersenOpening::new_rand();
+        let threshold_opening = PedersenOpening::new_rand();
+
+        // a value greater than the threshold
+        let greater_than_data = GreaterThanData::new(55, &opening, 54, &threshold_opening).unwrap();
+        assert!(greater_than_data.verify_proof().is_ok());
+
+        let greater_than_data = GreaterThanData::new(55, &opening, 0, &threshold_opening).unwrap();
+        assert!(greater_than_data.verify_proof().is_ok());
+
+        // a value equal to or less than the threshold
+        assert!(GreaterThanData::new(55, &opening, 55, &threshold_opening).is_err());
+        assert!(GreaterThanData::new(54, &opening, 55, &threshold_opening).is_err());
+
+        // a proof against a larger threshold
+        let mut greater_than_data =
+            GreaterThanData::new(55, &opening, 50, &threshold_opening).unwrap();
+        greater_than_data.context.threshold_commitment =
+            Pedersen::with(60_u64, &threshold_opening).into();
+        assert!(greater_than_data.verify_proof().is_err());
+    }
+}
@@ -0,0 +1,152 @@
+#[cfg(not(target_os = "solana"))]
+use {
+    crate::{
+        encryption::elgamal::{ElGamalKeypair, ElGamalPubkey},
+        errors::ProofError,
+        sigma_proofs::pubkey_proof::PubkeySigmaProof,
+        transcript::TranscriptProtocol,
+    },
+    merlin::Transcript,
+    std::convert::TryInto,
+};
+use {
+    crate::{
+        instruction::{ProofType, ZkProofData},
+        zk_token_elgamal::pod,
+    },
+    bytemuck::{Pod, Zeroable},
+};
+
+/// This struct includes the cryptographic proof *and* the account data information needed to
+/// verify the proof
+///
+/// - The pre-instruction should call KeyPossessionData::verify_proof(&self)
+/// - The actual program should check that the public key in this struct is the key whose
+///   possession it requires, and that the challenge is the one it issued
+///
+/// The proof is a Schnorr-style proof of knowledge of the secret key of the public key, like the
+/// `PubkeyValidityData` proof, but under its own transcript, so that the two proofs cannot be
+/// used in place of each other. The transcript binds a challenge chosen by the verifying
+/// application, so that a proof cannot be replayed where a different challenge is expected.
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct KeyPossessionData {
+    /// The context data for the key possession proof
+    pub context: KeyPossessionProofContext,
+
+    /// Proof that the prover knows the secret key of the public key
+    pub proof: KeyPossessionProof, // 64 bytes
+}
+
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct KeyPossessionProofContext {
+    /// The public key whose secret key is known
+    pub pubkey: pod::ElGamalPubkey, // 32 bytes
+
+    /// The challenge that the proof is bound to
+    pub challenge: [u8; 32], // 32 bytes
+}
+
+#[cfg(not(target_os = "solana"))]
+impl KeyPossessionData {
+    pub fn new(keypair: &ElGamalKeypair, challenge: &[u8; 32]) -> Result<Self, ProofError> {
+        let pod_pubkey = pod::ElGamalPubkey(keypair.public.to_bytes());
+
+        let context = KeyPossessionProofContext {
+            pubkey: pod_pubkey,
+            challenge: *challenge,
+        };
+
+        let mut transcript = KeyPossessionProof::transcript_new(&pod_pubkey, challenge);
+        let proof = KeyPossessionProof::new(keypair, &mut transcript);
+
+        Ok(KeyPossessionData { context, proof })
+    }
+}
+
+impl ZkProofData<KeyPossessionProofContext> for KeyPossessionData {
+    const PROOF_TYPE: ProofType = ProofType::KeyPossession;
+
+    fn context_data(&self) -> &KeyPossessionProofContext {
+        &self.context
+    }
+
+    #[cfg(not(target_os = "solana"))]
+    fn verify_proof(&self) -> Result<(), ProofError> {
+        let mut transcript =
+            KeyPossessionProof::transcript_new(&self.context.pubkey, &self.context.challenge);
+        let pubkey = self.context.pubkey.try_into()?;
+        self.proof.verify(&pubkey, &mut transcript)
+    }
+}
+
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct KeyPossessionProof {
+    /// Associated public-key sigma proof
+    pub proof: pod::PubkeySigmaProof,
+}
+
+#[cfg(not(target_os = "solana"))]
+impl KeyPossessionProof {
+    fn transcript_new(pubkey: &pod::ElGamalPubkey, challenge: &[u8; 32]) -> Transcript {
+        let mut transcript = Transcript::new(b"KeyPossessionProof");
+        transcript.append_pubkey(b"pubkey", pubkey);
+        transcript.append_message(b"challenge", challenge);
+        transcript
+    }
+
+    pub fn new(keypair: &ElGamalKeypair, transcript: &mut Transcript) -> Self {
+        let proof = PubkeySigmaProof::new(keypair, transcript);
+        Self {
+            proof: proof.into(),
+        }
+    }
+
+    pub fn verify(
+        &self,
+        pubkey: &ElGamalPubkey,
+        transcript: &mut Transcript,
+    ) -> Result<(), ProofError> {
+        let proof: PubkeySigmaProof = self.proof.try_into()?;
+        proof.verify(pubkey, transcript)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, crate::instruction::PubkeyValidityData};
+
+    #[test]
+    fn test_key_possession_correctness() {
+        let keypair = ElGamalKeypair::new_rand();
+        let challenge = [7; 32];
+
+        let key_possession_data = KeyPossessionData::new(&keypair, &challenge).unwrap();
+        assert!(key_possession_data.verify_proof().is_ok());
+
+        // a proof for another public key
+        let mut key_possession_data = KeyPossessionData::new(&keypair, &challenge).unwrap();
+        key_possession_data.context.pubkey =
+            pod::ElGamalPubkey(ElGamalKeypair::new_rand().public.to_bytes());
+        assert!(key_possession_data.verify_proof().is_err());
+
+        // a proof replayed under another challenge
+        let mut key_possession_data = KeyPossessionData::new(&keypair, &challenge).unwrap();
+        key_possession_data.context.challenge = [8; 32];
+        assert!(key_possession_data.verify_proof().is_err());
+
+        // a pubkey validity proof is not a key possession proof
+        let pubkey_validity_data = PubkeyValidityData::new(&keypair).unwrap();
+        let key_possession_data = KeyPossessionData {
+            context: KeyPossessionProofContext {
+                pubkey: pubkey_validity_data.context.pubkey,
+                challenge,
+            },
+            proof: bytemuck::cast(pubkey_validity_data.proof),
+        };
+        assert!(key_possession_data.verify_proof().is_err());
+    }
+}
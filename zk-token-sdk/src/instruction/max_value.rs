@@ -0,0 +1,154 @@
+#[cfg(not(target_os = "solana"))]
+use {
+    crate::{
+        encryption::pedersen::{Pedersen, PedersenCommitment, PedersenOpening},
+        errors::ProofError,
+        range_proof::RangeProof,
+        transcript::TranscriptProtocol,
+    },
+    merlin::Transcript,
+    std::convert::TryInto,
+};
+use {
+    crate::{
+        instruction::{ProofType, ZkProofData},
+        zk_token_elgamal::pod,
+    },
+    bytemuck::{Pod, Zeroable},
+};
+
+#[cfg(not(target_os = "solana"))]
+const MAX_VALUE_BIT_LENGTH: usize = 64;
+
+/// This struct includes the cryptographic proof *and* the account data information needed to
+/// verify the proof
+///
+/// - The pre-instruction should call MaxValueData::verify_proof(&self)
+/// - The actual program should check that the commitment in this struct is consistent with the
+///   value that it caps
+///
+/// The proof shows that `max_value` minus the committed value is a 64-bit number. It does not show
+/// that the committed value itself is non-negative, so the committed value should be range proven
+/// separately if it is not already known to be a 64-bit number.
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct MaxValueData {
+    /// The context data for the maximum value proof
+    pub context: MaxValueProofContext,
+
+    /// Proof that the committed value does not exceed the maximum value
+    pub proof: MaxValueProof,
+}
+
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct MaxValueProofContext {
+    /// The Pedersen commitment to the capped value
+    pub commitment: pod::PedersenCommitment, // 32 bytes
+
+    /// The public maximum of the committed value
+    pub max_value: pod::PodU64, // 8 bytes
+}
+
+#[cfg(not(target_os = "solana"))]
+impl MaxValueData {
+    pub fn new(value: u64, opening: &PedersenOpening, max_value: u64) -> Result<Self, ProofError> {
+        // errors if the value exceeds the maximum value
+        let remaining_value = max_value.checked_sub(value).ok_or(ProofError::Generation)?;
+
+        let pod_commitment: pod::PedersenCommitment = Pedersen::with(value, opening).into();
+
+        // `max_value * G - commitment` is a commitment to the remaining value under the negated
+        // opening
+        let remaining_opening = &PedersenOpening::default() - opening;
+
+        let mut transcript = MaxValueProof::transcript_new(&pod_commitment, max_value);
+        let range_proof = RangeProof::new(
+            vec![remaining_value],
+            vec![MAX_VALUE_BIT_LENGTH],
+            vec![&remaining_opening],
+            &mut transcript,
+        );
+
+        let context = MaxValueProofContext {
+            commitment: pod_commitment,
+            max_value: max_value.into(),
+        };
+
+        let proof = MaxValueProof {
+            range_proof: range_proof.try_into().map_err(|_| ProofError::Generation)?,
+        };
+
+        Ok(Self { context, proof })
+    }
+}
+
+impl ZkProofData<MaxValueProofContext> for MaxValueData {
+    const PROOF_TYPE: ProofType = ProofType::MaxValue;
+
+    fn context_data(&self) -> &MaxValueProofContext {
+        &self.context
+    }
+
+    #[cfg(not(target_os = "solana"))]
+    fn verify_proof(&self) -> Result<(), ProofError> {
+        let max_value = u64::from(self.context.max_value);
+        let mut transcript = MaxValueProof::transcript_new(&self.context.commitment, max_value);
+
+        let commitment: PedersenCommitment = self.context.commitment.try_into()?;
+        let remaining_commitment = &Pedersen::encode(max_value) - &commitment;
+
+        let range_proof: RangeProof = self.proof.range_proof.try_into()?;
+        range_proof.verify(
+            vec![&remaining_commitment],
+            vec![MAX_VALUE_BIT_LENGTH],
+            &mut transcript,
+        )?;
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct MaxValueProof {
+    /// Range proof of the maximum value minus the committed value
+    pub range_proof: pod::RangeProof64, // 672 bytes
+}
+
+#[cfg(not(target_os = "solana"))]
+impl MaxValueProof {
+    fn transcript_new(commitment: &pod::PedersenCommitment, max_value: u64) -> Transcript {
+        let mut transcript = Transcript::new(b"max-value-proof");
+
+        transcript.append_commitment(b"commitment", commitment);
+        transcript.append_u64(b"max-value", max_value);
+
+        transcript
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_max_value_correctness() {
+        let opening = PedersenOpening::new_rand();
+
+        // values up to the maximum value
+        let max_value_data = MaxValueData::new(55, &opening, 100).unwrap();
+        assert!(max_value_data.verify_proof().is_ok());
+
+        let max_value_data = MaxValueData::new(100, &opening, 100).unwrap();
+        assert!(max_value_data.verify_proof().is_ok());
+
+        // a value over the maximum value
+        assert!(MaxValueData::new(101, &opening, 100).is_err());
+
+        // a proof for a larger maximum value
+        let mut max_value_data = MaxValueData::new(55, &opening, 100).unwrap();
+        max_value_data.context.max_value = 50_u64.into();
+        assert!(max_value_data.verify_proof().is_err());
+    }
+}
@@ -1,9 +1,16 @@
 pub mod close_account;
+pub mod commitment_equality;
+pub mod greater_than;
+pub mod key_possession;
+pub mod max_value;
+pub mod parity;
 pub mod pubkey_validity;
 pub mod transfer;
+pub mod transfer_sum;
 pub mod transfer_with_fee;
 pub mod withdraw;
 pub mod withdraw_withheld;
+pub mod withheld_fee_sum;
 
 use num_derive::{FromPrimitive, ToPrimitive};
 #[cfg(not(target_os = "solana"))]
@@ -20,11 +27,18 @@ use {
 pub use {
     bytemuck::Pod,
     close_account::{CloseAccountData, CloseAccountProofContext},
+    commitment_equality::{CommitmentEqualityData, CommitmentEqualityProofContext},
+    greater_than::{GreaterThanData, GreaterThanProofContext},
+    key_possession::{KeyPossessionData, KeyPossessionProofContext},
+    max_value::{MaxValueData, MaxValueProofContext},
+    parity::{ParityData, ParityProofContext},
     pubkey_validity::{PubkeyValidityData, PubkeyValidityProofContext},
     transfer::{TransferData, TransferProofContext},
+    transfer_sum::{TransferSumData, TransferSumProofContext},
     transfer_with_fee::{FeeParameters, TransferWithFeeData, TransferWithFeeProofContext},
     withdraw::{WithdrawData, WithdrawProofContext},
     withdraw_withheld::{WithdrawWithheldTokensData, WithdrawWithheldTokensProofContext},
+    withheld_fee_sum::WithheldFeeSumData,
 };
 
 #[derive(Clone, Copy, Debug, FromPrimitive, ToPrimitive, PartialEq, Eq)]
@@ -38,6 +52,12 @@ pub enum ProofType {
     Transfer,
     TransferWithFee,
     PubkeyValidity,
+    TransferSum,
+    MaxValue,
+    Parity,
+    GreaterThan,
+    KeyPossession,
+    CommitmentEquality,
 }
 
 pub trait ZkProofData<T: Pod> {
@@ -0,0 +1,169 @@
+#[cfg(not(target_os = "solana"))]
+use {
+    crate::{
+        encryption::pedersen::{Pedersen, PedersenCommitment, PedersenOpening},
+        errors::ProofError,
+        range_proof::RangeProof,
+        transcript::TranscriptProtocol,
+    },
+    curve25519_dalek::scalar::Scalar,
+    merlin::Transcript,
+    std::convert::TryInto,
+};
+use {
+    crate::{
+        instruction::{ProofType, ZkProofData},
+        zk_token_elgamal::pod,
+    },
+    bytemuck::{Pod, Zeroable},
+};
+
+#[cfg(not(target_os = "solana"))]
+const HALF_VALUE_BIT_LENGTH: usize = 64;
+
+/// This struct includes the cryptographic proof *and* the account data information needed to
+/// verify the proof
+///
+/// - The pre-instruction should call ParityData::verify_proof(&self)
+/// - The actual program should check that the commitment in this struct is consistent with the
+///   value whose parity it attests
+///
+/// The proof shows that the committed value minus its parity is twice a 64-bit number, that is,
+/// that the committed value is a number of at most 65 bits with the claimed parity.
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct ParityData {
+    /// The context data for the parity proof
+    pub context: ParityProofContext,
+
+    /// Proof that the committed value has the claimed parity
+    pub proof: ParityProof,
+}
+
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct ParityProofContext {
+    /// The Pedersen commitment to the value
+    pub commitment: pod::PedersenCommitment, // 32 bytes
+
+    /// The parity of the committed value, 0 if it is even and 1 if it is odd
+    pub parity: u8, // 1 byte
+}
+
+#[cfg(not(target_os = "solana"))]
+impl ParityData {
+    pub fn new(value: u64, opening: &PedersenOpening) -> Result<Self, ProofError> {
+        let parity = (value % 2) as u8;
+        let half_value = value / 2;
+
+        let pod_commitment: pod::PedersenCommitment = Pedersen::with(value, opening).into();
+
+        // `(commitment - parity * G) / 2` is a commitment to the half value under the halved
+        // opening
+        let half_opening = opening * &Scalar::from(2_u64).invert();
+
+        let mut transcript = ParityProof::transcript_new(&pod_commitment, parity);
+        let range_proof = RangeProof::new(
+            vec![half_value],
+            vec![HALF_VALUE_BIT_LENGTH],
+            vec![&half_opening],
+            &mut transcript,
+        );
+
+        let context = ParityProofContext {
+            commitment: pod_commitment,
+            parity,
+        };
+
+        let proof = ParityProof {
+            range_proof: range_proof.try_into().map_err(|_| ProofError::Generation)?,
+        };
+
+        Ok(Self { context, proof })
+    }
+}
+
+impl ZkProofData<ParityProofContext> for ParityData {
+    const PROOF_TYPE: ProofType = ProofType::Parity;
+
+    fn context_data(&self) -> &ParityProofContext {
+        &self.context
+    }
+
+    #[cfg(not(target_os = "solana"))]
+    fn verify_proof(&self) -> Result<(), ProofError> {
+        let parity = self.context.parity;
+        if parity > 1 {
+            return Err(ProofError::InvalidParity);
+        }
+        let mut transcript = ParityProof::transcript_new(&self.context.commitment, parity);
+
+        let commitment: PedersenCommitment = self.context.commitment.try_into()?;
+        let half_commitment =
+            &(&commitment - &Pedersen::encode(parity)) * &Scalar::from(2_u64).invert();
+
+        let range_proof: RangeProof = self.proof.range_proof.try_into()?;
+        range_proof.verify(
+            vec![&half_commitment],
+            vec![HALF_VALUE_BIT_LENGTH],
+            &mut transcript,
+        )?;
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct ParityProof {
+    /// Range proof of half of the committed value minus its parity
+    pub range_proof: pod::RangeProof64, // 672 bytes
+}
+
+#[cfg(not(target_os = "solana"))]
+impl ParityProof {
+    fn transcript_new(commitment: &pod::PedersenCommitment, parity: u8) -> Transcript {
+        let mut transcript = Transcript::new(b"parity-proof");
+
+        transcript.append_commitment(b"commitment", commitment);
+        transcript.append_u64(b"parity", parity.into());
+
+        transcript
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parity_correctness() {
+        let opening = PedersenOpening::new_rand();
+
+        // an even and an odd value
+        let parity_data = ParityData::new(54, &opening).unwrap();
+        assert_eq!(parity_data.context.parity, 0);
+        assert!(parity_data.verify_proof().is_ok());
+
+        let parity_data = ParityData::new(55, &opening).unwrap();
+        assert_eq!(parity_data.context.parity, 1);
+        assert!(parity_data.verify_proof().is_ok());
+
+        let parity_data = ParityData::new(u64::MAX, &opening).unwrap();
+        assert!(parity_data.verify_proof().is_ok());
+
+        // an incorrect parity claim
+        let mut parity_data = ParityData::new(54, &opening).unwrap();
+        parity_data.context.parity = 1;
+        assert!(parity_data.verify_proof().is_err());
+
+        let mut parity_data = ParityData::new(55, &opening).unwrap();
+        parity_data.context.parity = 0;
+        assert!(parity_data.verify_proof().is_err());
+
+        // a parity claim other than 0 and 1
+        let mut parity_data = ParityData::new(55, &opening).unwrap();
+        parity_data.context.parity = 3;
+        assert!(parity_data.verify_proof().is_err());
+    }
+}
@@ -195,6 +195,18 @@ impl TransferData {
         }
     }
 
+    /// Extracts the combined lo and hi ciphertexts associated with a transfer data
+    pub(crate) fn ciphertext(&self, role: Role) -> Result<ElGamalCiphertext, ProofError> {
+        let ciphertext_lo = self.ciphertext_lo(role)?;
+        let ciphertext_hi = self.ciphertext_hi(role)?;
+
+        Ok(combine_lo_hi_ciphertexts(
+            &ciphertext_lo,
+            &ciphertext_hi,
+            TRANSFER_AMOUNT_LO_BITS,
+        ))
+    }
+
     /// Decrypts transfer amount from transfer data
     pub fn decrypt_amount(&self, role: Role, sk: &ElGamalSecretKey) -> Result<u64, ProofError> {
         let ciphertext_lo = self.ciphertext_lo(role)?;
@@ -0,0 +1,234 @@
+#[cfg(not(target_os = "solana"))]
+use {
+    crate::{
+        encryption::elgamal::{ElGamalCiphertext, ElGamalKeypair, ElGamalPubkey},
+        errors::ProofError,
+        instruction::{transfer::TransferData, Role},
+        sigma_proofs::zero_balance_proof::ZeroBalanceProof,
+        transcript::TranscriptProtocol,
+    },
+    merlin::Transcript,
+    std::convert::TryInto,
+};
+use {
+    crate::{
+        instruction::{
+            transfer::{TransferProof, TransferProofContext},
+            ProofType, ZkProofData,
+        },
+        zk_token_elgamal::pod,
+    },
+    bytemuck::{Pod, Zeroable},
+};
+
+/// This struct includes the cryptographic proof *and* the account data information needed to
+/// verify the proof
+///
+/// - The pre-instruction should call TransferSumData::verify_proof(&self)
+/// - The actual program should check that the transfers in this struct are consistent with what
+///   is being credited to the destination accounts
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct TransferSumData {
+    /// The context data for the transfer sum proof
+    pub context: TransferSumProofContext,
+
+    /// Zero-knowledge proofs for the two transfers and their sum
+    pub proof: TransferSumProof,
+}
+
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct TransferSumProofContext {
+    /// The context data for the first transfer
+    pub first_transfer: TransferProofContext, // 416 bytes
+
+    /// The context data for the second transfer
+    pub second_transfer: TransferProofContext, // 416 bytes
+
+    /// The public total of the two transfer amounts
+    pub total: pod::PodU64, // 8 bytes
+}
+
+#[cfg(not(target_os = "solana"))]
+impl TransferSumData {
+    /// Creates two transfers from the same source where the second transfer spends from the
+    /// balance that remains after the first.
+    pub fn new(
+        (first_amount, second_amount): (u64, u64),
+        total: u64,
+        (spendable_balance, ciphertext_old_source): (u64, &ElGamalCiphertext),
+        source_keypair: &ElGamalKeypair,
+        (first_destination_pubkey, second_destination_pubkey): (&ElGamalPubkey, &ElGamalPubkey),
+        auditor_pubkey: &ElGamalPubkey,
+    ) -> Result<Self, ProofError> {
+        let first_transfer = TransferData::new(
+            first_amount,
+            (spendable_balance, ciphertext_old_source),
+            source_keypair,
+            (first_destination_pubkey, auditor_pubkey),
+        )?;
+
+        let remaining_balance = spendable_balance
+            .checked_sub(first_amount)
+            .ok_or(ProofError::Generation)?;
+        let remaining_ciphertext: ElGamalCiphertext =
+            first_transfer.context.new_source_ciphertext.try_into()?;
+
+        let second_transfer = TransferData::new(
+            second_amount,
+            (remaining_balance, &remaining_ciphertext),
+            source_keypair,
+            (second_destination_pubkey, auditor_pubkey),
+        )?;
+
+        // the summed source ciphertexts minus the total is an encryption of zero exactly when the
+        // transfer amounts add up to the total
+        let sum_ciphertext = (first_transfer.ciphertext(Role::Source)?
+            + second_transfer.ciphertext(Role::Source)?)
+        .subtract_amount(total);
+
+        let pod_source_pubkey = first_transfer.context.transfer_pubkeys.source_pubkey;
+        let pod_sum_ciphertext: pod::ElGamalCiphertext = sum_ciphertext.into();
+
+        let mut transcript =
+            TransferSumProof::transcript_new(&pod_source_pubkey, &pod_sum_ciphertext, total);
+        let sum_proof = ZeroBalanceProof::new(source_keypair, &sum_ciphertext, &mut transcript);
+
+        let context = TransferSumProofContext {
+            first_transfer: first_transfer.context,
+            second_transfer: second_transfer.context,
+            total: total.into(),
+        };
+
+        let proof = TransferSumProof {
+            first_transfer_proof: first_transfer.proof,
+            second_transfer_proof: second_transfer.proof,
+            sum_proof: sum_proof.into(),
+        };
+
+        Ok(Self { context, proof })
+    }
+
+    fn first_transfer(&self) -> TransferData {
+        TransferData {
+            context: self.context.first_transfer,
+            proof: self.proof.first_transfer_proof,
+        }
+    }
+
+    fn second_transfer(&self) -> TransferData {
+        TransferData {
+            context: self.context.second_transfer,
+            proof: self.proof.second_transfer_proof,
+        }
+    }
+}
+
+impl ZkProofData<TransferSumProofContext> for TransferSumData {
+    const PROOF_TYPE: ProofType = ProofType::TransferSum;
+
+    fn context_data(&self) -> &TransferSumProofContext {
+        &self.context
+    }
+
+    #[cfg(not(target_os = "solana"))]
+    fn verify_proof(&self) -> Result<(), ProofError> {
+        let first_transfer = self.first_transfer();
+        let second_transfer = self.second_transfer();
+
+        first_transfer.verify_proof()?;
+        second_transfer.verify_proof()?;
+
+        // the source ciphertexts can only be summed if they are encrypted under the same pubkey
+        let pod_source_pubkey = first_transfer.context.transfer_pubkeys.source_pubkey;
+        if pod_source_pubkey != second_transfer.context.transfer_pubkeys.source_pubkey {
+            return Err(ProofError::TransferSourceMismatch);
+        }
+
+        let total = u64::from(self.context.total);
+        let sum_ciphertext = (first_transfer.ciphertext(Role::Source)?
+            + second_transfer.ciphertext(Role::Source)?)
+        .subtract_amount(total);
+        let pod_sum_ciphertext: pod::ElGamalCiphertext = sum_ciphertext.into();
+
+        let mut transcript =
+            TransferSumProof::transcript_new(&pod_source_pubkey, &pod_sum_ciphertext, total);
+
+        let source_pubkey = pod_source_pubkey.try_into()?;
+        let sum_proof: ZeroBalanceProof = self.proof.sum_proof.try_into()?;
+        sum_proof.verify(&source_pubkey, &sum_ciphertext, &mut transcript)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct TransferSumProof {
+    /// Proof for the first transfer
+    pub first_transfer_proof: TransferProof,
+
+    /// Proof for the second transfer
+    pub second_transfer_proof: TransferProof,
+
+    /// Proof that the summed source ciphertexts minus the total encrypts zero
+    pub sum_proof: pod::ZeroBalanceProof, // 96 bytes
+}
+
+#[cfg(not(target_os = "solana"))]
+impl TransferSumProof {
+    fn transcript_new(
+        source_pubkey: &pod::ElGamalPubkey,
+        sum_ciphertext: &pod::ElGamalCiphertext,
+        total: u64,
+    ) -> Transcript {
+        let mut transcript = Transcript::new(b"transfer-sum-proof");
+
+        transcript.append_pubkey(b"pubkey-source", source_pubkey);
+        transcript.append_ciphertext(b"ciphertext-sum", sum_ciphertext);
+        transcript.append_u64(b"total", total);
+
+        transcript
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_transfer_sum_correctness() {
+        let source_keypair = ElGamalKeypair::new_rand();
+        let first_destination_pubkey = ElGamalKeypair::new_rand().public;
+        let second_destination_pubkey = ElGamalKeypair::new_rand().public;
+        let auditor_pubkey = ElGamalKeypair::new_rand().public;
+
+        let spendable_balance: u64 = 120;
+        let spendable_ciphertext = source_keypair.public.encrypt(spendable_balance);
+
+        // amounts that sum to the total
+        let transfer_sum_data = TransferSumData::new(
+            (30, 55),
+            85,
+            (spendable_balance, &spendable_ciphertext),
+            &source_keypair,
+            (&first_destination_pubkey, &second_destination_pubkey),
+            &auditor_pubkey,
+        )
+        .unwrap();
+        assert!(transfer_sum_data.verify_proof().is_ok());
+
+        // amounts that do not sum to the total
+        let transfer_sum_data = TransferSumData::new(
+            (30, 55),
+            86,
+            (spendable_balance, &spendable_ciphertext),
+            &source_keypair,
+            (&first_destination_pubkey, &second_destination_pubkey),
+            &auditor_pubkey,
+        )
+        .unwrap();
+        assert!(transfer_sum_data.verify_proof().is_err());
+    }
+}
@@ -0,0 +1,162 @@
+#[cfg(not(target_os = "solana"))]
+use {
+    crate::{
+        encryption::elgamal::{ElGamalCiphertext, ElGamalKeypair},
+        errors::ProofError,
+        instruction::ZkProofData,
+    },
+    std::convert::TryInto,
+};
+use {
+    crate::{
+        instruction::{CloseAccountData, WithdrawWithheldTokensData},
+        zk_token_elgamal::{ops, pod},
+    },
+    bytemuck::{Pod, Zeroable},
+};
+
+/// This struct includes the public total of a set of withdrawn withheld fees *and* the proof that
+/// the fees sum to the total
+///
+/// - The pre-instruction should call WithheldFeeSumData::verify_proof(&self, proof_data) with the
+///   `WithdrawWithheldTokensData` proofs of the fees
+/// - The actual program should check that the ciphertexts of the proofs are consistent with the
+///   withheld fees that it collects
+///
+/// The fees are summed homomorphically over the withdraw withheld authority ciphertexts of the
+/// proofs, so all proofs must be for the same withdraw withheld authority. The sum proof is a
+/// zero-balance proof that the sum minus the total encrypts zero.
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct WithheldFeeSumData {
+    /// The public total of the withheld fees
+    pub total: pod::PodU64, // 8 bytes
+
+    /// Proof that the sum of the withheld fees minus the total encrypts zero
+    pub sum_proof: CloseAccountData,
+}
+
+impl WithheldFeeSumData {
+    /// Returns the ciphertext that the sum proof shows to encrypt zero, which is the sum of the
+    /// withdraw withheld authority ciphertexts of the proofs minus the total, or `None` if there
+    /// are no proofs.
+    pub fn sum_ciphertext(
+        total: u64,
+        proof_data: &[WithdrawWithheldTokensData],
+    ) -> Option<pod::ElGamalCiphertext> {
+        let (first, rest) = proof_data.split_first()?;
+        let sum = rest.iter().try_fold(
+            first.context.withdraw_withheld_authority_ciphertext,
+            |sum, proof_data| {
+                ops::add(
+                    &sum,
+                    &proof_data.context.withdraw_withheld_authority_ciphertext,
+                )
+            },
+        )?;
+        ops::subtract_from(&sum, total)
+    }
+}
+
+#[cfg(not(target_os = "solana"))]
+impl WithheldFeeSumData {
+    pub fn new(
+        withdraw_withheld_authority_keypair: &ElGamalKeypair,
+        total: u64,
+        proof_data: &[WithdrawWithheldTokensData],
+    ) -> Result<Self, ProofError> {
+        let sum_ciphertext: ElGamalCiphertext = Self::sum_ciphertext(total, proof_data)
+            .ok_or(ProofError::Generation)?
+            .try_into()?;
+        let sum_proof =
+            CloseAccountData::new(withdraw_withheld_authority_keypair, &sum_ciphertext)?;
+
+        Ok(Self {
+            total: total.into(),
+            sum_proof,
+        })
+    }
+
+    /// Verify the proofs of the withheld fees and that the fees sum to the total.
+    pub fn verify_proof(
+        &self,
+        proof_data: &[WithdrawWithheldTokensData],
+    ) -> Result<(), ProofError> {
+        let withdraw_withheld_authority_pubkey = self.sum_proof.context.pubkey;
+        if proof_data.iter().any(|proof_data| {
+            proof_data.context.withdraw_withheld_authority_pubkey
+                != withdraw_withheld_authority_pubkey
+        }) {
+            return Err(ProofError::WithdrawWithheldAuthorityMismatch);
+        }
+
+        let sum_ciphertext = Self::sum_ciphertext(self.total.into(), proof_data)
+            .ok_or(ProofError::MissingCiphertext)?;
+        if sum_ciphertext != self.sum_proof.context.ciphertext {
+            return Err(ProofError::WithheldFeeSumMismatch);
+        }
+
+        for proof_data in proof_data {
+            proof_data.verify_proof()?;
+        }
+        self.sum_proof.verify_proof()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_withheld_fee_sum_correctness() {
+        let withdraw_withheld_authority_keypair = ElGamalKeypair::new_rand();
+        let destination_pubkey = ElGamalKeypair::new_rand().public;
+
+        let proof_data: Vec<_> = [55_u64, 77]
+            .iter()
+            .map(|amount| {
+                let ciphertext = withdraw_withheld_authority_keypair.public.encrypt(*amount);
+                WithdrawWithheldTokensData::new(
+                    &withdraw_withheld_authority_keypair,
+                    &destination_pubkey,
+                    &ciphertext,
+                    *amount,
+                )
+                .unwrap()
+            })
+            .collect();
+
+        // the correct total
+        let fee_sum_data =
+            WithheldFeeSumData::new(&withdraw_withheld_authority_keypair, 132, &proof_data)
+                .unwrap();
+        assert!(fee_sum_data.verify_proof(&proof_data).is_ok());
+
+        // a proof for an incorrect total
+        let fee_sum_data =
+            WithheldFeeSumData::new(&withdraw_withheld_authority_keypair, 131, &proof_data)
+                .unwrap();
+        assert!(fee_sum_data.verify_proof(&proof_data).is_err());
+
+        // a correct proof verified against a subset of the fees
+        let fee_sum_data =
+            WithheldFeeSumData::new(&withdraw_withheld_authority_keypair, 132, &proof_data)
+                .unwrap();
+        assert_eq!(
+            fee_sum_data.verify_proof(&proof_data[..1]).unwrap_err(),
+            ProofError::WithheldFeeSumMismatch
+        );
+
+        // fees of a different withdraw withheld authority
+        let other_keypair = ElGamalKeypair::new_rand();
+        let ciphertext = other_keypair.public.encrypt(77_u64);
+        let other_proof_data =
+            WithdrawWithheldTokensData::new(&other_keypair, &destination_pubkey, &ciphertext, 77)
+                .unwrap();
+        let mixed_proof_data = [proof_data[0], other_proof_data];
+        assert_eq!(
+            fee_sum_data.verify_proof(&mixed_proof_data).unwrap_err(),
+            ProofError::WithdrawWithheldAuthorityMismatch
+        );
+    }
+}
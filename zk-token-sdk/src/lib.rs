@@ -31,10 +31,19 @@ mod sigma_proofs;
 #[cfg(not(target_os = "solana"))]
 mod transcript;
 
+/// The range proof generators, exposed for the benchmarks that compare the shared generators with
+/// freshly derived ones
+#[cfg(not(target_os = "solana"))]
+#[doc(hidden)]
+pub use range_proof::generators::BulletproofGens;
+
 // TODO: re-organize visibility
 pub mod curve25519;
 pub mod instruction;
+#[cfg(all(feature = "test-vectors", not(target_os = "solana")))]
+pub mod test_vectors;
 pub mod zk_token_elgamal;
+pub mod zk_token_proof_event;
 pub mod zk_token_proof_instruction;
 pub mod zk_token_proof_program;
 pub mod zk_token_proof_state;
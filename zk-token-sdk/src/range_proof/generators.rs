@@ -4,8 +4,20 @@ use {
         ristretto::RistrettoPoint,
     },
     sha3::{Sha3XofReader, Shake256},
+    std::borrow::Cow,
 };
 
+/// The capacity of the shared generators, which covers the largest range proofs of the proof
+/// instructions.
+pub const SHARED_GENERATORS_CAPACITY: usize = 256;
+
+lazy_static::lazy_static! {
+    /// Generators shared by all range proofs within `SHARED_GENERATORS_CAPACITY`, so that they are
+    /// derived once per process instead of once per proof.
+    static ref SHARED_GENERATORS: BulletproofGens =
+        BulletproofGens::new(SHARED_GENERATORS_CAPACITY);
+}
+
 /// Generators for Pedersen vector commitments.
 ///
 /// The code is copied from https://github.com/dalek-cryptography/bulletproofs for now...
@@ -80,6 +92,19 @@ impl BulletproofGens {
         gens
     }
 
+    /// Returns generators with at least `gens_capacity` capacity, borrowing the shared generators
+    /// if they suffice.
+    ///
+    /// The generators of a capacity are a prefix of the generators of any larger capacity, so the
+    /// shared generators yield the same proofs as generators of the exact capacity.
+    pub fn shared(gens_capacity: usize) -> Cow<'static, Self> {
+        if gens_capacity <= SHARED_GENERATORS_CAPACITY {
+            Cow::Borrowed(&SHARED_GENERATORS)
+        } else {
+            Cow::Owned(Self::new(gens_capacity))
+        }
+    }
+
     // pub fn new_aggregate(gens_capacities: Vec<usize>) -> Vec<BulletproofGens> {
     //     let mut gens_vector = Vec::new();
     //     for (capacity, i) in gens_capacities.iter().enumerate() {
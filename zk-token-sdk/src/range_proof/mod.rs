@@ -69,9 +69,8 @@ impl RangeProof {
         let nm: usize = bit_lengths.iter().sum();
         assert!(nm.is_power_of_two());
 
-        // TODO: precompute generators
         // TODO: double check Pedersen generators and range proof generators does not interfere
-        let bp_gens = BulletproofGens::new(nm);
+        let bp_gens = BulletproofGens::shared(nm);
 
         // bit-decompose values and generate their Pedersen vector commitment
         let a_blinding = Scalar::random(&mut OsRng);
@@ -230,7 +229,7 @@ impl RangeProof {
 
         let m = bit_lengths.len();
         let nm: usize = bit_lengths.iter().sum();
-        let bp_gens = BulletproofGens::new(nm);
+        let bp_gens = BulletproofGens::shared(nm);
 
         if !nm.is_power_of_two() {
             return Err(ProofVerificationError::InvalidBitSize.into());
@@ -432,5 +431,21 @@ mod tests {
             .is_ok());
     }
 
+    #[test]
+    fn test_shared_generators() {
+        // the shared generators coincide with generators of the exact capacity
+        for capacity in [64, 128, 256] {
+            let shared_gens = BulletproofGens::shared(capacity);
+            let gens = BulletproofGens::new(capacity);
+            assert!(shared_gens.G(capacity).eq(gens.G(capacity)));
+            assert!(shared_gens.H(capacity).eq(gens.H(capacity)));
+        }
+
+        // larger capacities are derived on demand
+        let gens = BulletproofGens::shared(512);
+        assert_eq!(gens.gens_capacity, 512);
+        assert!(gens.G(512).eq(BulletproofGens::new(512).G(512)));
+    }
+
     // TODO: write test for serialization/deserialization
 }
@@ -5,6 +5,10 @@
 //! commitment pair encrypts/encodes the same message. To generate the proof, a prover must provide
 //! the decryption key for the ciphertext and the Pedersen opening for the commitment.
 //!
+//! A commitment equality proof is defined with respect to two Pedersen commitments. The proof
+//! certifies that the two commitments encode the same message. To generate the proof, a prover
+//! must provide the Pedersen openings for both commitments.
+//!
 //! TODO: verify with respect to ciphertext
 //!
 //! The protocol guarantees computationally soundness (by the hardness of discrete log) and perfect
@@ -486,6 +490,131 @@ impl CtxtCtxtEqualityProof {
     }
 }
 
+/// Commitment equality proof.
+///
+/// Two Pedersen commitments `C_0 = x_0 G + r_0 H` and `C_1 = x_1 G + r_1 H` encode the same
+/// message if and only if `C_0 - C_1 = (r_0 - r_1) H`, so the proof is a Schnorr proof of knowledge
+/// of the discrete log of `C_0 - C_1` with respect to `H`.
+#[allow(non_snake_case)]
+#[derive(Clone)]
+pub struct CommCommEqualityProof {
+    Y: CompressedRistretto,
+    z: Scalar,
+}
+
+#[allow(non_snake_case)]
+#[cfg(not(target_os = "solana"))]
+impl CommCommEqualityProof {
+    /// Commitment equality proof constructor.
+    ///
+    /// The function does *not* hash the commitments into the transcript. For security, the
+    /// caller (the main protocol) should hash these public components prior to invoking this
+    /// constructor.
+    ///
+    /// This function is randomized. It uses `OsRng` internally to generate random scalars.
+    ///
+    /// * `first_opening` - The opening associated with the first Pedersen commitment
+    /// * `second_opening` - The opening associated with the second Pedersen commitment
+    /// * `transcript` - The transcript that does the bookkeeping for the Fiat-Shamir heuristic
+    pub fn new(
+        first_opening: &PedersenOpening,
+        second_opening: &PedersenOpening,
+        transcript: &mut Transcript,
+    ) -> Self {
+        transcript.commitment_equality_proof_domain_sep();
+
+        let r = first_opening.get_scalar() - second_opening.get_scalar();
+
+        // generate a random masking factor that also serves as a nonce
+        let mut y = Scalar::random(&mut OsRng);
+        let Y = (&y * &(*H)).compress();
+
+        // record masking factor in the transcript
+        transcript.append_point(b"Y", &Y);
+
+        let c = transcript.challenge_scalar(b"c");
+
+        // compute the masked value
+        let z = &(&c * &r) + &y;
+
+        // zeroize random scalar
+        y.zeroize();
+
+        CommCommEqualityProof { Y, z }
+    }
+
+    /// Commitment equality proof verifier.
+    ///
+    /// * `first_commitment` - The first Pedersen commitment to be proved
+    /// * `second_commitment` - The second Pedersen commitment to be proved
+    /// * `transcript` - The transcript that does the bookkeeping for the Fiat-Shamir heuristic
+    pub fn verify(
+        self,
+        first_commitment: &PedersenCommitment,
+        second_commitment: &PedersenCommitment,
+        transcript: &mut Transcript,
+    ) -> Result<(), EqualityProofError> {
+        transcript.commitment_equality_proof_domain_sep();
+
+        // extract the relevant Ristretto points from the inputs
+        let C_0 = first_commitment.get_point();
+        let C_1 = second_commitment.get_point();
+
+        // include Y to transcript and extract challenge
+        transcript.validate_and_append_point(b"Y", &self.Y)?;
+
+        let c = transcript.challenge_scalar(b"c");
+
+        // check that the required algebraic condition holds
+        let Y = self
+            .Y
+            .decompress()
+            .ok_or(ProofVerificationError::Deserialization)?;
+
+        let check = RistrettoPoint::vartime_multiscalar_mul(
+            vec![
+                &self.z,            // z
+                &(-&c),             // -c
+                &c,                 // c
+                &(-&Scalar::one()), // -identity
+            ],
+            vec![
+                &(*H), // H
+                C_0,   // C_0
+                C_1,   // C_1
+                &Y,    // Y
+            ],
+        );
+
+        if check.is_identity() {
+            Ok(())
+        } else {
+            Err(ProofVerificationError::AlgebraicRelation.into())
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut buf = [0_u8; 64];
+        buf[..32].copy_from_slice(self.Y.as_bytes());
+        buf[32..64].copy_from_slice(self.z.as_bytes());
+        buf
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, EqualityProofError> {
+        if bytes.len() != 64 {
+            return Err(ProofVerificationError::Deserialization.into());
+        }
+
+        let bytes = array_ref![bytes, 0, 64];
+        let (Y, z) = array_refs![bytes, 32, 32];
+
+        let Y = CompressedRistretto::from_slice(Y);
+        let z = Scalar::from_canonical_bytes(*z).ok_or(ProofVerificationError::Deserialization)?;
+
+        Ok(CommCommEqualityProof { Y, z })
+    }
+}
+
 #[cfg(test)]
 mod test {
     use {
@@ -739,4 +868,43 @@ mod test {
             )
             .is_err());
     }
+
+    #[test]
+    fn test_commitment_commitment_equality_proof_correctness() {
+        // success case
+        let message: u64 = 55;
+        let (first_commitment, first_opening) = Pedersen::new(message);
+        let (second_commitment, second_opening) = Pedersen::new(message);
+
+        let mut prover_transcript = Transcript::new(b"Test");
+        let mut verifier_transcript = Transcript::new(b"Test");
+
+        let proof =
+            CommCommEqualityProof::new(&first_opening, &second_opening, &mut prover_transcript);
+
+        assert!(proof
+            .verify(
+                &first_commitment,
+                &second_commitment,
+                &mut verifier_transcript
+            )
+            .is_ok());
+
+        // fail case: committed messages are different
+        let (second_commitment, second_opening) = Pedersen::new(77_u64);
+
+        let mut prover_transcript = Transcript::new(b"Test");
+        let mut verifier_transcript = Transcript::new(b"Test");
+
+        let proof =
+            CommCommEqualityProof::new(&first_opening, &second_opening, &mut prover_transcript);
+
+        assert!(proof
+            .verify(
+                &first_commitment,
+                &second_commitment,
+                &mut verifier_transcript
+            )
+            .is_err());
+    }
 }
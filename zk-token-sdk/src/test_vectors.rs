@@ -0,0 +1,166 @@
+//! Valid proof data for each proof instruction, for downstream crates that test their
+//! integration with the proof program.
+//!
+//! Each function returns the `Pod` bytes of a proof data that verifies, which is the instruction
+//! data of the corresponding verify instruction without its leading discriminant. The keys and
+//! amounts of the proofs are fixed and the keys are derived from fixed seeds, so the proof
+//! contexts are the same across calls wherever they do not contain ciphertexts. The proofs
+//! themselves are generated with fresh randomness, so their bytes differ between calls.
+
+use {
+    crate::{
+        encryption::{elgamal::ElGamalKeypair, pedersen::PedersenOpening},
+        instruction::{
+            CloseAccountData, CommitmentEqualityData, FeeParameters, GreaterThanData,
+            KeyPossessionData, MaxValueData, ParityData, PubkeyValidityData, TransferData,
+            TransferSumData, TransferWithFeeData, WithdrawData, WithdrawWithheldTokensData,
+        },
+    },
+    bytemuck::bytes_of,
+    solana_sdk::{pubkey::Pubkey, signer::keypair::keypair_from_seed},
+};
+
+const SPENDABLE_BALANCE: u64 = 120;
+
+/// Derive the ElGamal keypair of the test vectors with the given index.
+fn elgamal_keypair(index: u8) -> ElGamalKeypair {
+    let signer = keypair_from_seed(&[index; 32]).unwrap();
+    ElGamalKeypair::new(&signer, &Pubkey::new_from_array([index; 32])).unwrap()
+}
+
+/// Returns valid `CloseAccountData` bytes for a ciphertext of zero.
+pub fn close_account_test_vector() -> Vec<u8> {
+    let keypair = elgamal_keypair(0);
+    let ciphertext = keypair.public.encrypt(0_u64);
+    bytes_of(&CloseAccountData::new(&keypair, &ciphertext).unwrap()).to_vec()
+}
+
+/// Returns valid `WithdrawData` bytes for a withdrawal of 55 from a balance of 120.
+pub fn withdraw_test_vector() -> Vec<u8> {
+    let keypair = elgamal_keypair(0);
+    let ciphertext = keypair.public.encrypt(SPENDABLE_BALANCE);
+    bytes_of(&WithdrawData::new(55, &keypair, SPENDABLE_BALANCE, &ciphertext).unwrap()).to_vec()
+}
+
+/// Returns valid `WithdrawWithheldTokensData` bytes for a withdrawal of 55 withheld tokens.
+pub fn withdraw_withheld_tokens_test_vector() -> Vec<u8> {
+    let keypair = elgamal_keypair(0);
+    let destination_pubkey = elgamal_keypair(1).public;
+    let ciphertext = keypair.public.encrypt(55_u64);
+    bytes_of(
+        &WithdrawWithheldTokensData::new(&keypair, &destination_pubkey, &ciphertext, 55).unwrap(),
+    )
+    .to_vec()
+}
+
+/// Returns valid `TransferData` bytes for a transfer of 55 from a balance of 120.
+pub fn transfer_test_vector() -> Vec<u8> {
+    let source_keypair = elgamal_keypair(0);
+    let destination_pubkey = elgamal_keypair(1).public;
+    let auditor_pubkey = elgamal_keypair(2).public;
+    let ciphertext = source_keypair.public.encrypt(SPENDABLE_BALANCE);
+    bytes_of(
+        &TransferData::new(
+            55,
+            (SPENDABLE_BALANCE, &ciphertext),
+            &source_keypair,
+            (&destination_pubkey, &auditor_pubkey),
+        )
+        .unwrap(),
+    )
+    .to_vec()
+}
+
+/// Returns valid `TransferWithFeeData` bytes for a transfer of 55 from a balance of 120 with a
+/// 4% fee of at most 3.
+pub fn transfer_with_fee_test_vector() -> Vec<u8> {
+    let source_keypair = elgamal_keypair(0);
+    let destination_pubkey = elgamal_keypair(1).public;
+    let auditor_pubkey = elgamal_keypair(2).public;
+    let withdraw_withheld_authority_pubkey = elgamal_keypair(3).public;
+    let ciphertext = source_keypair.public.encrypt(SPENDABLE_BALANCE);
+    let fee_parameters = FeeParameters {
+        fee_rate_basis_points: 400,
+        maximum_fee: 3,
+    };
+    bytes_of(
+        &TransferWithFeeData::new(
+            55,
+            (SPENDABLE_BALANCE, &ciphertext),
+            &source_keypair,
+            (&destination_pubkey, &auditor_pubkey),
+            fee_parameters,
+            &withdraw_withheld_authority_pubkey,
+        )
+        .unwrap(),
+    )
+    .to_vec()
+}
+
+/// Returns valid `PubkeyValidityData` bytes.
+pub fn pubkey_validity_test_vector() -> Vec<u8> {
+    bytes_of(&PubkeyValidityData::new(&elgamal_keypair(0)).unwrap()).to_vec()
+}
+
+/// Returns valid `TransferSumData` bytes for transfers of 30 and 55 from a balance of 120.
+pub fn transfer_sum_test_vector() -> Vec<u8> {
+    let source_keypair = elgamal_keypair(0);
+    let first_destination_pubkey = elgamal_keypair(1).public;
+    let second_destination_pubkey = elgamal_keypair(2).public;
+    let auditor_pubkey = elgamal_keypair(3).public;
+    let ciphertext = source_keypair.public.encrypt(SPENDABLE_BALANCE);
+    bytes_of(
+        &TransferSumData::new(
+            (30, 55),
+            85,
+            (SPENDABLE_BALANCE, &ciphertext),
+            &source_keypair,
+            (&first_destination_pubkey, &second_destination_pubkey),
+            &auditor_pubkey,
+        )
+        .unwrap(),
+    )
+    .to_vec()
+}
+
+/// Returns valid `MaxValueData` bytes for a value of 55 capped at 100.
+pub fn max_value_test_vector() -> Vec<u8> {
+    bytes_of(&MaxValueData::new(55, &PedersenOpening::new_rand(), 100).unwrap()).to_vec()
+}
+
+/// Returns valid `ParityData` bytes for the odd value 55.
+pub fn parity_test_vector() -> Vec<u8> {
+    bytes_of(&ParityData::new(55, &PedersenOpening::new_rand()).unwrap()).to_vec()
+}
+
+/// Returns valid `GreaterThanData` bytes for a value of 55 compared against a threshold of 54.
+pub fn greater_than_test_vector() -> Vec<u8> {
+    bytes_of(
+        &GreaterThanData::new(
+            55,
+            &PedersenOpening::new_rand(),
+            54,
+            &PedersenOpening::new_rand(),
+        )
+        .unwrap(),
+    )
+    .to_vec()
+}
+
+/// Returns valid `KeyPossessionData` bytes for the challenge `[0; 32]`.
+pub fn key_possession_test_vector() -> Vec<u8> {
+    bytes_of(&KeyPossessionData::new(&elgamal_keypair(0), &[0; 32]).unwrap()).to_vec()
+}
+
+/// Returns valid `CommitmentEqualityData` bytes for two commitments to the value 55.
+pub fn commitment_equality_test_vector() -> Vec<u8> {
+    bytes_of(
+        &CommitmentEqualityData::new(
+            55,
+            &PedersenOpening::new_rand(),
+            &PedersenOpening::new_rand(),
+        )
+        .unwrap(),
+    )
+    .to_vec()
+}
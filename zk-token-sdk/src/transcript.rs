@@ -46,6 +46,9 @@ pub trait TranscriptProtocol {
     /// Append a domain separator for equality proof.
     fn equality_proof_domain_sep(&mut self);
 
+    /// Append a domain separator for commitment equality proof.
+    fn commitment_equality_proof_domain_sep(&mut self);
+
     /// Append a domain separator for zero-balance proof.
     fn zero_balance_proof_domain_sep(&mut self);
 
@@ -149,6 +152,10 @@ impl TranscriptProtocol for Transcript {
         self.append_message(b"dom-sep", b"equality-proof")
     }
 
+    fn commitment_equality_proof_domain_sep(&mut self) {
+        self.append_message(b"dom-sep", b"commitment-equality-proof")
+    }
+
     fn zero_balance_proof_domain_sep(&mut self) {
         self.append_message(b"dom-sep", b"zero-balance-proof")
     }
@@ -64,7 +64,9 @@ mod target_arch {
             },
             range_proof::{errors::RangeProofError, RangeProof},
             sigma_proofs::{
-                equality_proof::{CtxtCommEqualityProof, CtxtCtxtEqualityProof},
+                equality_proof::{
+                    CommCommEqualityProof, CtxtCommEqualityProof, CtxtCtxtEqualityProof,
+                },
                 errors::*,
                 fee_proof::FeeSigmaProof,
                 pubkey_proof::PubkeySigmaProof,
@@ -205,6 +207,20 @@ mod target_arch {
         }
     }
 
+    impl From<CommCommEqualityProof> for pod::CommCommEqualityProof {
+        fn from(proof: CommCommEqualityProof) -> Self {
+            Self(proof.to_bytes())
+        }
+    }
+
+    impl TryFrom<pod::CommCommEqualityProof> for CommCommEqualityProof {
+        type Error = EqualityProofError;
+
+        fn try_from(pod: pod::CommCommEqualityProof) -> Result<Self, Self::Error> {
+            Self::from_bytes(&pod.0)
+        }
+    }
+
     impl From<CtxtCtxtEqualityProof> for pod::CtxtCtxtEqualityProof {
         fn from(proof: CtxtCtxtEqualityProof) -> Self {
             Self(proof.to_bytes())
@@ -132,6 +132,11 @@ pub struct CtxtCtxtEqualityProof(pub [u8; 224]);
 unsafe impl Zeroable for CtxtCtxtEqualityProof {}
 unsafe impl Pod for CtxtCtxtEqualityProof {}
 
+/// Serialization of `CommCommEqualityProof`
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(transparent)]
+pub struct CommCommEqualityProof(pub [u8; 64]);
+
 /// Serialization of validity proofs
 #[derive(Clone, Copy)]
 #[repr(transparent)]
@@ -0,0 +1,169 @@
+//! Events emitted by the ZkToken Proof program
+use {
+    crate::zk_token_elgamal::pod::PodU64,
+    borsh::{BorshDeserialize, BorshSerialize},
+    bytemuck::{Pod, Zeroable},
+    solana_program::{hash::hash, pubkey::Pubkey},
+    std::mem::size_of,
+};
+
+/// The current version of the `ProofVerified` event schema
+pub const PROOF_VERIFIED_EVENT_VERSION: u8 = 1;
+
+/// The current version of the `VerifyTelemetry` layout
+pub const VERIFY_TELEMETRY_VERSION: u8 = 1;
+
+/// The event that the `AnchorEvent` verify option emits once a proof is verified.
+///
+/// The event follows the convention of Anchor's `emit!` and is logged as `Program data: <base64>`,
+/// where the data is the event discriminator followed by the borsh-encoded event. New schema
+/// versions only append fields.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ProofVerified {
+    /// The schema version of the event
+    pub version: u8,
+    /// The proof type of the verified proof
+    pub proof_type: u8,
+    /// The created proof context account, if any
+    pub context_state_account: Option<Pubkey>,
+    /// The slot in which the proof was verified
+    pub slot: u64,
+}
+
+impl ProofVerified {
+    /// The Anchor event discriminator, the first 8 bytes of `sha256("event:ProofVerified")`
+    pub fn discriminator() -> [u8; 8] {
+        let event_hash = hash(b"event:ProofVerified");
+        let mut discriminator = [0; 8];
+        discriminator.copy_from_slice(&event_hash.as_ref()[..8]);
+        discriminator
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Self::discriminator().to_vec();
+        // serializing into a `Vec` cannot fail
+        buf.extend_from_slice(&self.try_to_vec().unwrap());
+        buf
+    }
+
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let event = data.strip_prefix(&Self::discriminator())?;
+        Self::try_from_slice(event).ok()
+    }
+
+    /// Encode the event as a single-line JSON object whose keys are the field names in
+    /// declaration order, with the context state account as a base58 string or `null`.
+    pub fn to_json(&self) -> String {
+        let context_state_account = match self.context_state_account {
+            Some(context_state_account) => format!("\"{}\"", context_state_account),
+            None => "null".to_string(),
+        };
+        format!(
+            "{{\"version\":{},\"proof_type\":{},\"context_state_account\":{},\"slot\":{}}}",
+            self.version, self.proof_type, context_state_account, self.slot
+        )
+    }
+}
+
+/// The telemetry that the `Telemetry` verify option sets as the return data of a verify
+/// instruction.
+///
+/// The return data is the `Pod` bytes of the struct. The layout is versioned by its first byte,
+/// and new versions only append fields, so a decoder of an older version can read the prefix that
+/// it knows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Pod, Zeroable)]
+#[repr(C)]
+pub struct VerifyTelemetry {
+    /// The layout version of the telemetry
+    pub version: u8,
+    /// The proof type of the verified proof
+    pub proof_type: u8,
+    /// 1 if a proof context state was created and 0 otherwise
+    pub context_state_created: u8,
+    /// The compute units charged for the instruction up to the point the telemetry is recorded,
+    /// including any units consumed by invoked programs
+    pub compute_units_charged: PodU64,
+    /// The compute units remaining in the transaction when the telemetry is recorded
+    pub compute_units_remaining: PodU64,
+}
+
+impl VerifyTelemetry {
+    /// Decode telemetry of the current or a later version from return data.
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let telemetry: Self = bytemuck::pod_read_unaligned(data.get(..size_of::<Self>())?);
+        (telemetry.version >= VERIFY_TELEMETRY_VERSION).then_some(telemetry)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_proof_verified_event_encoding() {
+        let event = ProofVerified {
+            version: PROOF_VERIFIED_EVENT_VERSION,
+            proof_type: 1,
+            context_state_account: Some(Pubkey::new_unique()),
+            slot: 42,
+        };
+        let data = event.encode();
+        assert_eq!(data[..8], hash(b"event:ProofVerified").to_bytes()[..8]);
+        assert_eq!(ProofVerified::decode(&data), Some(event));
+
+        // data with a different discriminator is not the event
+        let mut other_data = data;
+        other_data[0] ^= 1;
+        assert_eq!(ProofVerified::decode(&other_data), None);
+    }
+
+    #[test]
+    fn test_proof_verified_event_json() {
+        let context_state_account = Pubkey::new_unique();
+        let event = ProofVerified {
+            version: PROOF_VERIFIED_EVENT_VERSION,
+            proof_type: 1,
+            context_state_account: Some(context_state_account),
+            slot: 42,
+        };
+        assert_eq!(
+            event.to_json(),
+            format!(
+                "{{\"version\":1,\"proof_type\":1,\"context_state_account\":\"{}\",\"slot\":42}}",
+                context_state_account
+            )
+        );
+
+        let event = ProofVerified {
+            context_state_account: None,
+            ..event
+        };
+        let json: serde_json::Value = serde_json::from_str(&event.to_json()).unwrap();
+        assert_eq!(json["context_state_account"], serde_json::Value::Null);
+        assert_eq!(json["slot"], 42);
+    }
+
+    #[test]
+    fn test_verify_telemetry_decoding() {
+        let telemetry = VerifyTelemetry {
+            version: VERIFY_TELEMETRY_VERSION,
+            proof_type: 1,
+            context_state_created: 1,
+            compute_units_charged: 100_000_u64.into(),
+            compute_units_remaining: 50_000_u64.into(),
+        };
+        let mut data = bytemuck::bytes_of(&telemetry).to_vec();
+        assert_eq!(VerifyTelemetry::decode(&data), Some(telemetry));
+
+        // fields appended by a later version are ignored
+        data.push(7);
+        assert_eq!(VerifyTelemetry::decode(&data), Some(telemetry));
+
+        assert_eq!(
+            VerifyTelemetry::decode(&data[..size_of::<VerifyTelemetry>() - 1]),
+            None
+        );
+        data[0] = 0;
+        assert_eq!(VerifyTelemetry::decode(&data), None);
+    }
+}
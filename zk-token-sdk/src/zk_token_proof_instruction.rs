@@ -1,24 +1,58 @@
 ///! Instructions provided by the ZkToken Proof program
 pub use crate::instruction::*;
+#[cfg(not(target_os = "solana"))]
+use solana_sdk::compute_budget::{self, ComputeBudgetInstruction};
 use {
+    crate::zk_token_proof_state::{
+        find_policy_account_address, ProofCommitment, PROOF_CONTEXT_STATE_FLAG_COMPRESSED,
+        PROOF_CONTEXT_STATE_POLICY_AUTHORITY_ALLOWLIST, PROOF_CONTEXT_STATE_POLICY_KEY_BLACKLIST,
+        PROOF_CONTEXT_STATE_POLICY_MIN_SECURITY_LEVEL, PROOF_CONTEXT_STATE_POLICY_RATE_LIMIT,
+        PROOF_CONTEXT_STATE_POLICY_SLOT_WINDOWS, PROOF_CONTEXT_STATE_POLICY_VERIFIER_SIGNATURE,
+    },
+    borsh::{BorshDeserialize, BorshSerialize},
     bytemuck::bytes_of,
     num_derive::{FromPrimitive, ToPrimitive},
     num_traits::{FromPrimitive, ToPrimitive},
     solana_program::{
         instruction::{AccountMeta, Instruction},
         pubkey::Pubkey,
+        system_program,
     },
+    std::mem::size_of,
 };
 
+/// The compute unit limit of a transaction that does not request one, per non-compute-budget
+/// instruction. Mirrors the runtime's `DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT`.
+#[cfg(not(target_os = "solana"))]
+const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u64 = 200_000;
+
+/// The maximum compute unit limit of a transaction. Mirrors the runtime's
+/// `MAX_COMPUTE_UNIT_LIMIT`.
+#[cfg(not(target_os = "solana"))]
+const MAX_COMPUTE_UNIT_LIMIT: u64 = 1_400_000;
+
 #[derive(Clone, Copy, Debug, FromPrimitive, ToPrimitive, PartialEq, Eq)]
 #[repr(u8)]
 pub enum ProofInstruction {
     /// Close a zero-knowledge proof context state.
     ///
+    /// Unlike the other instructions, this instruction can be invoked by another program, which
+    /// lets program-controlled accounts act as context state authorities: when invoked by a
+    /// program whose id is the context account's owner or delegate, the owner or delegate does not
+    /// need to sign.
+    ///
+    /// The owner or delegate can also be an SPL multisig account, in which case the threshold of
+    /// its signers must sign the instruction.
+    ///
     /// Accounts expected by this instruction:
     ///   0. `[writable]` The proof context account to close
     ///   1. `[writable]` The destination account for lamports
-    ///   2. `[signer]` The context account's owner
+    ///   2. `[signer]` The context account's owner or delegate, or the id of the invoking
+    ///      program
+    ///
+    ///   * Multisig owner or delegate
+    ///   2. `[]` The context account's multisig owner or delegate
+    ///   3. ..3+M `[signer]` M signer accounts of the multisig
     ///
     /// Data expected by this instruction:
     ///   None
@@ -132,126 +166,1782 @@ pub enum ProofInstruction {
     ///   `PubkeyValidityData`
     ///
     VerifyPubkeyValidity,
-}
 
-/// Pubkeys associated with a context state account to be used as parameters to functions.
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub struct ContextStateInfo<'a> {
-    pub context_state_account: &'a Pubkey,
-    pub context_state_authority: &'a Pubkey,
-}
+    /// Verify a pair of transfer zero-knowledge proofs from the same source whose transfer
+    /// amounts sum to a public total.
+    ///
+    /// This instruction can be configured to optionally create a proof context state account.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Creating a proof context account
+    ///   0. `[writable]` The proof context account
+    ///   1. `[]` The proof context account owner
+    ///
+    ///   * Otherwise
+    ///   None
+    ///
+    /// Data expected by this instruction:
+    ///   `TransferSumData`
+    ///
+    VerifyTransferSum,
 
-/// Create a `CloseContextState` instruction.
-pub fn close_context_state(
-    context_state_info: ContextStateInfo,
-    destination_account: &Pubkey,
-) -> Instruction {
-    let accounts = vec![
-        AccountMeta::new(*context_state_info.context_state_account, false),
-        AccountMeta::new(*destination_account, false),
-        AccountMeta::new_readonly(*context_state_info.context_state_authority, true),
-    ];
+    /// Migrate a proof context state to a layout version, re-encoding its proof context with or
+    /// without compression.
+    ///
+    /// A context state in the default layout can be migrated to the extended layout, and a context
+    /// state in the extended layout can change the encoding of its proof context, as given by
+    /// `migrated_layout_version`. Migrations to an older or unknown layout version, to compression
+    /// in the default layout, or that leave the context state unchanged are rejected. The
+    /// proof context account is resized to the length of the new encoding and must remain
+    /// rent-exempt.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[writable]` The proof context account to migrate
+    ///   1. `[signer]` The context account's owner
+    ///
+    /// Data expected by this instruction:
+    ///   `u8` The layout version to migrate to
+    ///   `u8` `PROOF_CONTEXT_STATE_FLAG_COMPRESSED` to compress the proof context, or 0
+    ///
+    MigrateContextState,
 
-    let data = vec![ToPrimitive::to_u8(&ProofInstruction::CloseContextState).unwrap()];
+    /// Verify a zero-knowledge proof whose instruction data is borsh-encoded.
+    ///
+    /// The instruction otherwise behaves as the verify instruction in the envelope, which must not
+    /// be `CloseContextState`, `MigrateContextState`, `CommitProof`, `SweepContextStates`,
+    /// `VerifyBatch`, `VerifyWithheldFeeSum`, `CompleteContextState`, `InitializePolicyAccount`,
+    /// `UpdatePolicyAccount`, or another `VerifyBorsh`.
+    ///
+    /// Accounts expected by this instruction:
+    ///   The accounts of the verify instruction in the envelope
+    ///
+    /// Data expected by this instruction:
+    ///   `BorshVerifyProof`
+    ///
+    VerifyBorsh,
 
-    Instruction {
-        program_id: crate::zk_token_proof_program::id(),
-        accounts,
-        data,
-    }
-}
+    /// Commit to a proof that a later verify instruction reveals with the
+    /// `VerifyOption::RevealCommitment` option, so that the proof data is not exposed before the
+    /// committer is ready to use it.
+    ///
+    /// The commitment account must be owned by the proof program, uninitialized, and allocated
+    /// with the length of a `ProofCommitment`.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[writable]` The proof commitment account
+    ///   1. `[signer]` The committer
+    ///
+    /// Data expected by this instruction:
+    ///   `[u8; 32]` The SHA-256 hash of the proof data, as returned by `ProofCommitment::hash`
+    ///
+    CommitProof,
 
-/// Create a `VerifyCloseAccount` instruction.
-pub fn verify_close_account(
-    context_state_info: Option<ContextStateInfo>,
-    proof_data: &CloseAccountData,
-) -> Instruction {
-    ProofInstruction::VerifyCloseAccount.encode_verify_proof(context_state_info, proof_data)
-}
+    /// Close the proof context states among the given accounts that the authority can close,
+    /// skipping all other accounts.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[writable]` The destination account for lamports
+    ///   1. `[signer]` The context accounts' owner or delegate
+    ///   2..2+N. `[writable]` The N proof context accounts to sweep
+    ///
+    /// Data expected by this instruction:
+    ///   None
+    ///
+    SweepContextStates,
 
-/// Create a `VerifyWithdraw` instruction.
-pub fn verify_withdraw(
-    context_state_info: Option<ContextStateInfo>,
-    proof_data: &WithdrawData,
-) -> Instruction {
-    ProofInstruction::VerifyWithdraw.encode_verify_proof(context_state_info, proof_data)
-}
+    /// Verify a zero-knowledge proof that a Pedersen commitment is to a value that does not exceed
+    /// a public maximum value.
+    ///
+    /// This instruction can be configured to optionally create a proof context state account.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Creating a proof context account
+    ///   0. `[writable]` The proof context account
+    ///   1. `[]` The proof context account owner
+    ///
+    ///   * Otherwise
+    ///   None
+    ///
+    /// Data expected by this instruction:
+    ///   `MaxValueData`
+    ///
+    VerifyMaxValue,
 
-/// Create a `VerifyWithdrawWithheldTokens` instruction.
-pub fn verify_withdraw_withheld_tokens(
-    context_state_info: Option<ContextStateInfo>,
-    proof_data: &WithdrawWithheldTokensData,
-) -> Instruction {
-    ProofInstruction::VerifyWithdrawWithheldTokens
-        .encode_verify_proof(context_state_info, proof_data)
-}
+    /// Verify a zero-knowledge proof that a Pedersen commitment is to a value of a public parity.
+    ///
+    /// This instruction can be configured to optionally create a proof context state account.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Creating a proof context account
+    ///   0. `[writable]` The proof context account
+    ///   1. `[]` The proof context account owner
+    ///
+    ///   * Otherwise
+    ///   None
+    ///
+    /// Data expected by this instruction:
+    ///   `ParityData`
+    ///
+    VerifyParity,
 
-/// Create a `VerifyTransfer` instruction.
-pub fn verify_transfer(
-    context_state_info: Option<ContextStateInfo>,
-    proof_data: &TransferData,
-) -> Instruction {
-    ProofInstruction::VerifyTransfer.encode_verify_proof(context_state_info, proof_data)
-}
+    /// Verify a batch of zero-knowledge proofs of possibly different types, optionally creating a
+    /// proof context state for each.
+    ///
+    /// The entries are processed in order and each is charged the compute units of its verify
+    /// instruction. Entries must be verify instructions other than `VerifyBorsh` and do not take
+    /// verify options. If an entry fails, the instruction fails and the index and instruction of
+    /// the entry are logged.
+    ///
+    /// The return data of the instruction is set to the number of proof context states created,
+    /// as a little-endian `u64`.
+    ///
+    /// Accounts expected by this instruction:
+    ///   The proof context accounts and their owners, referenced by index from the entries
+    ///
+    /// Data expected by this instruction:
+    ///   `BorshVerifyBatch`
+    ///
+    VerifyBatch,
 
-/// Create a `VerifyTransferWithFee` instruction.
-pub fn verify_transfer_with_fee(
-    context_state_info: Option<ContextStateInfo>,
-    proof_data: &TransferWithFeeData,
-) -> Instruction {
-    ProofInstruction::VerifyTransferWithFee.encode_verify_proof(context_state_info, proof_data)
-}
+    /// Verify a zero-knowledge proof that a Pedersen commitment is to a value that is greater than
+    /// the value of a second Pedersen commitment, without revealing either value.
+    ///
+    /// This instruction can be configured to optionally create a proof context state account.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Creating a proof context account
+    ///   0. `[writable]` The proof context account
+    ///   1. `[]` The proof context account owner
+    ///
+    ///   * Otherwise
+    ///   None
+    ///
+    /// Data expected by this instruction:
+    ///   `GreaterThanData`
+    ///
+    VerifyGreaterThan,
 
-/// Create a `VerifyPubkeyValidity` instruction.
-pub fn verify_pubkey_validity(
-    context_state_info: Option<ContextStateInfo>,
-    proof_data: &PubkeyValidityData,
-) -> Instruction {
-    ProofInstruction::VerifyPubkeyValidity.encode_verify_proof(context_state_info, proof_data)
-}
+    /// Verify zero-knowledge proofs of withdrawn withheld fees and that the fees sum to a public
+    /// total, without revealing the individual fees.
+    ///
+    /// The proofs must be for the same withdraw withheld authority. Each proof, and the sum proof,
+    /// is charged the compute units of a `VerifyCloseAccount` instruction, see
+    /// `withheld_fee_sum_compute_units`.
+    ///
+    /// Accounts expected by this instruction:
+    ///   None
+    ///
+    /// Data expected by this instruction:
+    ///   `WithheldFeeSumData`
+    ///   `[WithdrawWithheldTokensData]` The proofs of the withheld fees
+    ///
+    VerifyWithheldFeeSum,
 
-impl ProofInstruction {
-    pub fn encode_verify_proof<T, U>(
-        &self,
-        context_state_info: Option<ContextStateInfo>,
-        proof_data: &T,
-    ) -> Instruction
-    where
-        T: Pod + ZkProofData<U>,
-        U: Pod,
-    {
-        let accounts = if let Some(context_state_info) = context_state_info {
-            vec![
-                AccountMeta::new(*context_state_info.context_state_account, false),
-                AccountMeta::new_readonly(*context_state_info.context_state_authority, false),
-            ]
-        } else {
-            vec![]
-        };
+    /// Complete a partial proof context state created with the `VerifyOption::Partial` option,
+    /// making it usable by consumers.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[writable]` The partial proof context account
+    ///   1. `[signer]` The context account's delegate, which completes the context state
+    ///
+    ///   * Multisig delegate
+    ///   1. `[]` The context account's multisig delegate
+    ///   2. ..2+M `[signer]` M signer accounts of the multisig
+    ///
+    /// Data expected by this instruction:
+    ///   None
+    ///
+    CompleteContextState,
 
-        let mut data = vec![ToPrimitive::to_u8(self).unwrap()];
-        data.extend_from_slice(bytes_of(proof_data));
+    /// Verify a zero-knowledge proof that the prover knows the secret key of an ElGamal public
+    /// key, without revealing it.
+    ///
+    /// Unlike `VerifyPubkeyValidity`, whose proof shows that a public key is well-formed for a
+    /// confidential token account, this instruction is intended for applications that require
+    /// the possession of a key. The proofs use different transcripts and are not interchangeable.
+    ///
+    /// The proof is bound to a challenge chosen by the application, which is recorded in the
+    /// proof context. An application must check that the challenge is the one it expects, so
+    /// that a proof made for another challenge cannot be replayed.
+    ///
+    /// This instruction can be configured to optionally create a proof context state account.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Creating a proof context account
+    ///   0. `[writable]` The proof context account
+    ///   1. `[]` The proof context account owner
+    ///
+    ///   * Otherwise
+    ///   None
+    ///
+    /// Data expected by this instruction:
+    ///   `KeyPossessionData`
+    ///
+    VerifyKeyPossession,
 
-        Instruction {
-            program_id: crate::zk_token_proof_program::id(),
-            accounts,
-            data,
-        }
-    }
+    /// Verify a zero-knowledge proof that two Pedersen commitments encode the same value, without
+    /// revealing the value.
+    ///
+    /// This instruction can be configured to optionally create a proof context state account.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    ///   * Creating a proof context account
+    ///   0. `[writable]` The proof context account
+    ///   1. `[]` The proof context account owner
+    ///
+    ///   * Otherwise
+    ///   None
+    ///
+    /// Data expected by this instruction:
+    ///   `CommitmentEqualityData`
+    ///
+    VerifyCommitmentEquality,
 
-    pub fn instruction_type(input: &[u8]) -> Option<Self> {
-        input
-            .first()
-            .and_then(|instruction| FromPrimitive::from_u8(*instruction))
-    }
+    /// Create a policy account, the account of a verify option such as an `AuthorityAllowlist`
+    /// or a `RateLimitState`, recording the signing admin in its `PolicyAccountHeader`.
+    ///
+    /// The proof program creates the policy account at the address returned by
+    /// `zk_token_proof_state::find_policy_account_address` for the discriminator, the admin, and
+    /// the id, signing for the address and funding its rent-exempt balance from the payer. The
+    /// body must be well-formed for the kind of account given by the discriminator.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[writable]` The policy account
+    ///   1. `[signer]` The admin of the policy account
+    ///   2. `[writable, signer]` The payer of the policy account
+    ///   3. `[]` The system program
+    ///
+    /// Data expected by this instruction:
+    ///   `[u8; 8]` The discriminator of the kind of policy account
+    ///   `u64` The id of the policy account, as little-endian bytes
+    ///   `[u8]` The body of the policy account
+    ///
+    InitializePolicyAccount,
 
-    pub fn proof_data<T, U>(input: &[u8]) -> Option<&T>
-    where
-        T: Pod + ZkProofData<U>,
-        U: Pod,
-    {
-        input
-            .get(1..)
-            .and_then(|data| bytemuck::try_from_bytes(data).ok())
+    /// Replace the body of a policy account created by `InitializePolicyAccount`. The admin
+    /// recorded in the account must sign the instruction, and the body must be well-formed for the
+    /// kind of the account. If the new body is longer, the payer funds the rent-exempt balance of
+    /// the account.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[writable]` The policy account
+    ///   1. `[signer]` The admin of the policy account
+    ///   2. `[writable, signer]` The payer
+    ///   3. `[]` The system program
+    ///
+    /// Data expected by this instruction:
+    ///   `[u8]` The new body of the policy account
+    ///
+    UpdatePolicyAccount,
+}
+
+/// Optional verification policies that can be appended to the proof data of a verify instruction.
+///
+/// Each option is encoded as a one-byte tag followed by its payload, if any. Options that read
+/// accounts consume them in the order that the options are encoded, from the instruction accounts
+/// that follow any proof context accounts.
+///
+/// The borsh encoding of an option, as used by `VerifyBorsh`, coincides with its encoding here.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerifyOption {
+    /// Require the ciphertext consumed by the proof to equal the ciphertext produced by a
+    /// previously verified proof context, linking the two proofs.
+    ///
+    /// Accounts expected by this option:
+    ///   0. `[]` The prior proof context account
+    ///
+    PriorContext,
+
+    /// Record a delegate in the created proof context state that can close the account in
+    /// addition to the context state authority.
+    ///
+    /// The created proof context state is written in the extended layout, an
+    /// `ExtendedProofContextStateMeta` followed by the proof context, and the proof context
+    /// account must be allocated with that length. This option requires a proof context account
+    /// to be created.
+    ///
+    /// Accounts expected by this option:
+    ///   0. `[]` The proof context account delegate
+    ///
+    Delegate,
+
+    /// Reject the proof if the current epoch, as reported by the `Clock` sysvar, is earlier
+    /// than the given epoch.
+    ///
+    /// This option does not take any accounts.
+    ///
+    MinEpoch(u64),
+
+    /// Store the proof context in the created proof context state compressed, reducing the size
+    /// of the account and thus its rent. The proof context state is written in the extended
+    /// layout, and the proof context account must be allocated with the length of an
+    /// `ExtendedProofContextStateMeta` followed by the compressed encoding.
+    ///
+    /// This option requires a proof context account to be created.
+    ///
+    Compressed,
+
+    /// Require the destination of a transfer proof to be the confidential transfer extension of
+    /// the given SPL Token 2022 account, binding the proof to the account that will be credited.
+    /// The account's ElGamal pubkey must equal the proof's destination pubkey and its pending
+    /// balance must be well-formed.
+    ///
+    /// This option is only supported by `VerifyTransfer` and `VerifyTransferWithFee`.
+    ///
+    /// Accounts expected by this option:
+    ///   0. `[]` The destination token account
+    ///
+    DestinationAccount,
+
+    /// Require the context state authority to be a member of the given authority allowlist, a
+    /// policy account holding an `AuthorityAllowlist`.
+    ///
+    /// This option requires a proof context account to be created, in the extended layout that
+    /// records the policy.
+    ///
+    /// Accounts expected by this option:
+    ///   0. `[]` The authority allowlist account
+    ///
+    AuthorityAllowlist,
+
+    /// Count the creation of the proof context state against the context state authority's rate
+    /// limit, rejecting the proof if the authority has reached the limit of the current window.
+    /// Both accounts must be policy accounts of the same admin, and the rate limit state must
+    /// belong to the config and the context state authority.
+    ///
+    /// This option requires a proof context account to be created, in the extended layout that
+    /// records the policy.
+    ///
+    /// Accounts expected by this option:
+    ///   0. `[]` The `RateLimitConfig` account
+    ///   1. `[writable]` The `RateLimitState` account of the context state authority
+    ///
+    RateLimit,
+
+    /// Log the hex-encoded SHA-256 hash of the proof context in the format of the SPL Memo
+    /// program, `Program log: Memo (len <length>): "<hash>"`, so that memo indexers pick it up.
+    ///
+    /// This option does not take any accounts.
+    ///
+    MemoLog,
+
+    /// Emit a `ProofVerified` event in the convention of Anchor's `emit!` once the proof is
+    /// verified, so that Anchor-based indexers can decode it.
+    ///
+    /// This option does not take any accounts.
+    ///
+    AnchorEvent,
+
+    /// Create the proof context account through the system program instead of requiring it to
+    /// exist, funding its rent-exempt balance from the payer. The proof context account must sign
+    /// the instruction.
+    ///
+    /// This option requires a proof context account to be created.
+    ///
+    /// Accounts expected by this option:
+    ///   0. `[writable, signer]` The payer of the proof context account
+    ///   1. `[]` The system program
+    ///
+    CreateContextAccount,
+
+    /// Only create the proof context state if the `length` bytes of the predicate account's data
+    /// at `offset` equal the first `length` bytes of `expected`. If they do not, or the data is
+    /// too short, the proof is still verified but no proof context state is written.
+    ///
+    /// The `length` must not exceed `MAX_PREDICATE_LENGTH`. This option requires a proof context
+    /// account to be provided.
+    ///
+    /// Accounts expected by this option:
+    ///   0. `[]` The predicate account
+    ///
+    Predicate {
+        offset: u32,
+        length: u8,
+        expected: [u8; MAX_PREDICATE_LENGTH],
+    },
+
+    /// Reject a transfer proof whose source and destination pubkeys are equal, as a transfer to
+    /// the source account itself is almost always a mistake.
+    ///
+    /// This option only has an effect on `VerifyTransfer` and `VerifyTransferWithFee` and does
+    /// not take any accounts.
+    ///
+    DistinctEndpoints,
+
+    /// Reveal the proof committed to in a `ProofCommitment` account by a `CommitProof`
+    /// instruction. The proof data must hash to the committed hash, and the committer must sign
+    /// the instruction. The commitment is consumed, leaving the commitment account uninitialized.
+    ///
+    /// Accounts expected by this option:
+    ///   0. `[writable]` The proof commitment account
+    ///   1. `[signer]` The committer
+    ///
+    RevealCommitment,
+
+    /// Require an Ed25519 program instruction in the same transaction to carry a signature over
+    /// the proof data by a verifier in the given allowlist, in addition to the proof itself. The
+    /// allowlist account must be a policy account holding an `AuthorityAllowlist` of the verifier
+    /// keys. Only signatures whose pubkey and message are stored in the Ed25519 instruction's own
+    /// data are considered.
+    ///
+    /// Accounts expected by this option:
+    ///   0. `[]` The verifier allowlist account
+    ///   1. `[]` The instructions sysvar
+    ///
+    VerifierSignature,
+
+    /// Log the slot and epoch in which the proof was verified, as
+    /// `Program log: proof verified in slot <slot> of epoch <epoch>`, so that audits can establish
+    /// which validator processed the verification.
+    ///
+    /// The identity of the validator is not available to programs, since every validator that
+    /// replays the slot must produce the same result. The leader of the logged slot can instead be
+    /// looked up in the leader schedule of the logged epoch, for example with the
+    /// `getSlotLeaders` RPC method.
+    ///
+    /// This option does not take any accounts.
+    ///
+    SlotLog,
+
+    /// Increment the count of the given usage counter once the proof is verified, for on-chain
+    /// usage accounting of metered services. The counter account must be a policy account holding
+    /// a `UsageCounter`, and the proof is rejected if the count would overflow.
+    ///
+    /// Accounts expected by this option:
+    ///   0. `[writable]` The usage counter account
+    ///
+    UsageCounter,
+
+    /// Reject the proof unless the current slot, as reported by the `Clock` sysvar, falls within
+    /// one of the windows of the given slot windows account, restricting processing to configured
+    /// time windows. The account must be a policy account holding `SlotWindows`.
+    ///
+    /// Accounts expected by this option:
+    ///   0. `[]` The slot windows account
+    ///
+    SlotWindows,
+
+    /// Set the return data of the instruction to the receipt of the verification, as computed by
+    /// `verification_receipt`, giving a compact receipt of the proof without creating a proof
+    /// context state.
+    ///
+    /// This option does not take any accounts.
+    ///
+    Receipt,
+
+    /// Record the nullifier of the proof in the given nullifier set, rejecting the proof if the
+    /// set already contains the nullifier so that each proof can only be used once. The nullifier
+    /// of a proof is derived from its proof type and proof context by `NullifierSet::nullifier`.
+    /// The account must be a policy account holding a `NullifierSet`.
+    ///
+    /// Accounts expected by this option:
+    ///   0. `[writable]` The nullifier set account
+    ///
+    Nullifier,
+
+    /// Write the created proof context state to the given mirror account as well, so that
+    /// consumers can read the context state from either account. The mirror account must be owned
+    /// by the proof program, uninitialized, and of the same length as the proof context account.
+    ///
+    /// This option requires a proof context account to be created. If the predicate of a
+    /// `Predicate` option does not hold, the mirror account is not written either.
+    ///
+    /// Accounts expected by this option:
+    ///   0. `[writable]` The mirror proof context account
+    ///
+    MirrorAccount,
+
+    /// Set the return data of the instruction to a `VerifyTelemetry` summarizing the verification:
+    /// the proof type, whether a proof context state was created, and the compute units charged
+    /// and remaining once the proof is verified.
+    ///
+    /// This option cannot be combined with `Receipt` and does not take any accounts.
+    ///
+    Telemetry,
+
+    /// Require the proof context account to have the given address, binding the instruction to
+    /// the account that the caller intends the proof context state to be written to so that a
+    /// different account cannot be substituted.
+    ///
+    /// This option requires a proof context account to be provided and does not take any
+    /// accounts.
+    ///
+    ContextAddress(Pubkey),
+
+    /// Write the created proof context state in the layout that SPL Token 2022 reads, a
+    /// `Token2022ProofContextState`, so that Token 2022 can consume the proof context state
+    /// directly. The proof context account must be allocated with the length of that layout.
+    ///
+    /// The layout has no delegate, flags, or policies, so this option cannot be combined with
+    /// `Delegate`, `Compressed`, or the options whose policy the context state records, as given by
+    /// `VerifyOption::context_state_policy`. It requires a proof context account to be created and
+    /// does not take any accounts.
+    ///
+    Token2022Layout,
+
+    /// Require an instruction of the given program whose data starts with the given discriminant
+    /// to precede the verify instruction in the transaction, enforcing that the proof is only
+    /// verified after, for example, an approval instruction.
+    ///
+    /// Accounts expected by this option:
+    ///   0. `[]` The instructions sysvar
+    ///
+    PriorInstruction {
+        program_id: Pubkey,
+        discriminant: u8,
+    },
+
+    /// Log a human-readable line followed by the `ProofVerified` event as a single-line JSON
+    /// object, as encoded by `ProofVerified::to_json`, once the proof is verified, so that both
+    /// human-oriented and machine-oriented log consumers are served.
+    ///
+    /// This option does not take any accounts.
+    ///
+    StructuredLog,
+
+    /// Store only a reference to the proof in the created proof context state, a `ProofReference`
+    /// holding the hash of the proof data, instead of the proof context itself. The proof context
+    /// account must be allocated with the length of a `ProofReference`, which is the same for all
+    /// proof types, and the full proof is recovered off-chain from the transaction that created
+    /// the context state.
+    ///
+    /// The reference is written in the extended layout. It cannot be read as a proof context, so
+    /// this option cannot be combined with `Compressed` or `Token2022Layout`. It requires a proof
+    /// context account to be created and does not take any accounts.
+    ///
+    ReferenceOnly,
+
+    /// Create the proof context state as a partial context state that consumers reject until its
+    /// delegate completes it with a `CompleteContextState` instruction, so that the context state
+    /// is only usable once a second authority has approved it. The partial context state can be
+    /// closed as usual.
+    ///
+    /// This option requires a proof context account to be created with a `Delegate`, the
+    /// completing authority, and cannot be combined with `Token2022Layout`, whose layout has no
+    /// room for the completeness flag. It does not take any accounts.
+    ///
+    Partial,
+
+    /// Reject the proof if the security level of its parameters, as returned by
+    /// `zk_token_proof_state::security_level`, is below the minimum security level of the given
+    /// policy account holding a `SecurityLevelConfig`.
+    ///
+    /// Accounts expected by this option:
+    ///   0. `[]` The `SecurityLevelConfig` account
+    ///
+    MinSecurityLevel,
+
+    /// Create the proof context state at a program-managed address instead of a client-provided
+    /// proof context account: the proof program creates the account at the address returned by
+    /// `zk_token_proof_state::find_program_managed_context_address` for the invoking program and
+    /// the given id, signing for the address and funding its rent-exempt balance from the payer,
+    /// and sets the invoking program as the context state authority, so that only the program can
+    /// close the context state by invoking `CloseContextState`.
+    ///
+    /// This option must be used by a program invoking the verify instruction and cannot be
+    /// combined with a proof context account or the options that require one. Verify
+    /// instructions invoked by a program are rejected unless they carry this option.
+    ///
+    /// Accounts expected by this option:
+    ///   0. `[writable]` The program-managed proof context account
+    ///   1. `[writable, signer]` The payer of the proof context account
+    ///   2. `[]` The system program
+    ///
+    ProgramManaged(u64),
+
+    /// Reject the proof if any of its ElGamal pubkeys, as returned by
+    /// `zk_token_proof_state::extract_elgamal_pubkeys`, is in the given key blacklist, a policy
+    /// account holding a `KeyBlacklist`.
+    ///
+    /// Accounts expected by this option:
+    ///   0. `[]` The key blacklist account
+    ///
+    KeyBlacklist,
+
+    /// Offer the option's account as a candidate proof context account for proofs of the given
+    /// proof type, encoded as a `ProofType` byte. Of the candidates of an instruction, the proof
+    /// context state is written to the first whose proof type is that of the verified proof, and
+    /// the proof is rejected if there is none, so that a single instruction can route proofs of
+    /// different types to different accounts.
+    ///
+    /// With candidates, the proof context accounts consist only of the context state authority.
+    /// Candidates cannot be combined with `ContextAddress` or the options that read the context
+    /// state authority from the proof context accounts.
+    ///
+    /// Accounts expected by this option:
+    ///   0. `[writable]` The candidate proof context account
+    ///
+    RouteCandidate(u8),
+
+    /// Make the instruction idempotent under the given client-supplied nonce: if the nonce is
+    /// remembered by the given retry nonce tracker with the same instruction data and accounts,
+    /// the instruction succeeds as a no-op without verifying the proof or processing any other
+    /// option, and is only charged `RETRY_NONCE_REPLAY_COMPUTE_UNITS`. If the nonce is remembered
+    /// with a different instruction, the instruction fails. Otherwise the nonce is recorded with
+    /// the hash of the full instruction data and the keys of the instruction accounts, and the
+    /// instruction is processed as usual, so that a retried transaction takes effect exactly once.
+    /// The tracker account must be a policy account holding a `RetryNonceTracker`, and its admin
+    /// must sign the instruction.
+    ///
+    /// A replayed instruction does not set any return data or emit any logs besides a note of the
+    /// replay.
+    ///
+    /// Accounts expected by this option:
+    ///   0. `[writable]` The retry nonce tracker account
+    ///   1. `[signer]` The admin of the retry nonce tracker
+    ///
+    RetryNonce(u64),
+}
+
+/// The maximum number of bytes compared by a `VerifyOption::Predicate`
+pub const MAX_PREDICATE_LENGTH: usize = 32;
+
+impl VerifyOption {
+    const PRIOR_CONTEXT_TAG: u8 = 0;
+    const DELEGATE_TAG: u8 = 1;
+    const MIN_EPOCH_TAG: u8 = 2;
+    const COMPRESSED_TAG: u8 = 3;
+    const DESTINATION_ACCOUNT_TAG: u8 = 4;
+    const AUTHORITY_ALLOWLIST_TAG: u8 = 5;
+    const RATE_LIMIT_TAG: u8 = 6;
+    const MEMO_LOG_TAG: u8 = 7;
+    const ANCHOR_EVENT_TAG: u8 = 8;
+    const CREATE_CONTEXT_ACCOUNT_TAG: u8 = 9;
+    const PREDICATE_TAG: u8 = 10;
+    const DISTINCT_ENDPOINTS_TAG: u8 = 11;
+    const REVEAL_COMMITMENT_TAG: u8 = 12;
+    const VERIFIER_SIGNATURE_TAG: u8 = 13;
+    const SLOT_LOG_TAG: u8 = 14;
+    const USAGE_COUNTER_TAG: u8 = 15;
+    const SLOT_WINDOWS_TAG: u8 = 16;
+    const RECEIPT_TAG: u8 = 17;
+    const NULLIFIER_TAG: u8 = 18;
+    const MIRROR_ACCOUNT_TAG: u8 = 19;
+    const TELEMETRY_TAG: u8 = 20;
+    const CONTEXT_ADDRESS_TAG: u8 = 21;
+    const TOKEN_2022_LAYOUT_TAG: u8 = 22;
+    const PRIOR_INSTRUCTION_TAG: u8 = 23;
+    const STRUCTURED_LOG_TAG: u8 = 24;
+    const REFERENCE_ONLY_TAG: u8 = 25;
+    const MIN_SECURITY_LEVEL_TAG: u8 = 26;
+    const PARTIAL_TAG: u8 = 27;
+    const PROGRAM_MANAGED_TAG: u8 = 28;
+    const KEY_BLACKLIST_TAG: u8 = 29;
+    const ROUTE_CANDIDATE_TAG: u8 = 30;
+    const RETRY_NONCE_TAG: u8 = 31;
+
+    /// The number of instruction accounts that the option consumes.
+    pub fn number_of_accounts(&self) -> usize {
+        match self {
+            Self::PriorContext
+            | Self::Delegate
+            | Self::DestinationAccount
+            | Self::AuthorityAllowlist
+            | Self::Predicate { .. }
+            | Self::UsageCounter
+            | Self::SlotWindows
+            | Self::Nullifier
+            | Self::MirrorAccount
+            | Self::PriorInstruction { .. }
+            | Self::MinSecurityLevel
+            | Self::KeyBlacklist
+            | Self::RouteCandidate(_) => 1,
+            Self::RateLimit
+            | Self::CreateContextAccount
+            | Self::RevealCommitment
+            | Self::VerifierSignature
+            | Self::RetryNonce(_) => 2,
+            Self::ProgramManaged(_) => 3,
+            Self::MinEpoch(_)
+            | Self::Compressed
+            | Self::MemoLog
+            | Self::AnchorEvent
+            | Self::DistinctEndpoints
+            | Self::SlotLog
+            | Self::Receipt
+            | Self::Telemetry
+            | Self::ContextAddress(_)
+            | Self::Token2022Layout
+            | Self::StructuredLog
+            | Self::ReferenceOnly
+            | Self::Partial => 0,
+        }
+    }
+
+    /// The `PROOF_CONTEXT_STATE_POLICY_*` policy that the option records in the context state, or
+    /// zero if the option does not enforce a recorded policy. The first account of the option is
+    /// its policy account. A context state created with such an option is in the extended layout.
+    pub fn context_state_policy(&self) -> u8 {
+        match self {
+            Self::AuthorityAllowlist => PROOF_CONTEXT_STATE_POLICY_AUTHORITY_ALLOWLIST,
+            Self::RateLimit => PROOF_CONTEXT_STATE_POLICY_RATE_LIMIT,
+            Self::SlotWindows => PROOF_CONTEXT_STATE_POLICY_SLOT_WINDOWS,
+            Self::MinSecurityLevel => PROOF_CONTEXT_STATE_POLICY_MIN_SECURITY_LEVEL,
+            Self::KeyBlacklist => PROOF_CONTEXT_STATE_POLICY_KEY_BLACKLIST,
+            Self::VerifierSignature => PROOF_CONTEXT_STATE_POLICY_VERIFIER_SIGNATURE,
+            _ => 0,
+        }
+    }
+
+    fn encode(&self, buf: &mut Vec<u8>) {
+        match self {
+            Self::PriorContext => buf.push(Self::PRIOR_CONTEXT_TAG),
+            Self::Delegate => buf.push(Self::DELEGATE_TAG),
+            Self::MinEpoch(epoch) => {
+                buf.push(Self::MIN_EPOCH_TAG);
+                buf.extend_from_slice(&epoch.to_le_bytes());
+            }
+            Self::Compressed => buf.push(Self::COMPRESSED_TAG),
+            Self::DestinationAccount => buf.push(Self::DESTINATION_ACCOUNT_TAG),
+            Self::AuthorityAllowlist => buf.push(Self::AUTHORITY_ALLOWLIST_TAG),
+            Self::RateLimit => buf.push(Self::RATE_LIMIT_TAG),
+            Self::MemoLog => buf.push(Self::MEMO_LOG_TAG),
+            Self::AnchorEvent => buf.push(Self::ANCHOR_EVENT_TAG),
+            Self::CreateContextAccount => buf.push(Self::CREATE_CONTEXT_ACCOUNT_TAG),
+            Self::Predicate {
+                offset,
+                length,
+                expected,
+            } => {
+                buf.push(Self::PREDICATE_TAG);
+                buf.extend_from_slice(&offset.to_le_bytes());
+                buf.push(*length);
+                buf.extend_from_slice(expected);
+            }
+            Self::DistinctEndpoints => buf.push(Self::DISTINCT_ENDPOINTS_TAG),
+            Self::RevealCommitment => buf.push(Self::REVEAL_COMMITMENT_TAG),
+            Self::VerifierSignature => buf.push(Self::VERIFIER_SIGNATURE_TAG),
+            Self::SlotLog => buf.push(Self::SLOT_LOG_TAG),
+            Self::UsageCounter => buf.push(Self::USAGE_COUNTER_TAG),
+            Self::SlotWindows => buf.push(Self::SLOT_WINDOWS_TAG),
+            Self::Receipt => buf.push(Self::RECEIPT_TAG),
+            Self::Nullifier => buf.push(Self::NULLIFIER_TAG),
+            Self::MirrorAccount => buf.push(Self::MIRROR_ACCOUNT_TAG),
+            Self::Telemetry => buf.push(Self::TELEMETRY_TAG),
+            Self::ContextAddress(address) => {
+                buf.push(Self::CONTEXT_ADDRESS_TAG);
+                buf.extend_from_slice(address.as_ref());
+            }
+            Self::Token2022Layout => buf.push(Self::TOKEN_2022_LAYOUT_TAG),
+            Self::PriorInstruction {
+                program_id,
+                discriminant,
+            } => {
+                buf.push(Self::PRIOR_INSTRUCTION_TAG);
+                buf.extend_from_slice(program_id.as_ref());
+                buf.push(*discriminant);
+            }
+            Self::StructuredLog => buf.push(Self::STRUCTURED_LOG_TAG),
+            Self::ReferenceOnly => buf.push(Self::REFERENCE_ONLY_TAG),
+            Self::MinSecurityLevel => buf.push(Self::MIN_SECURITY_LEVEL_TAG),
+            Self::Partial => buf.push(Self::PARTIAL_TAG),
+            Self::ProgramManaged(id) => {
+                buf.push(Self::PROGRAM_MANAGED_TAG);
+                buf.extend_from_slice(&id.to_le_bytes());
+            }
+            Self::KeyBlacklist => buf.push(Self::KEY_BLACKLIST_TAG),
+            Self::RouteCandidate(proof_type) => {
+                buf.push(Self::ROUTE_CANDIDATE_TAG);
+                buf.push(*proof_type);
+            }
+            Self::RetryNonce(nonce) => {
+                buf.push(Self::RETRY_NONCE_TAG);
+                buf.extend_from_slice(&nonce.to_le_bytes());
+            }
+        }
+    }
+
+    /// Decode a sequence of encoded options, returning `None` if the input is malformed.
+    pub fn decode_all(mut input: &[u8]) -> Option<Vec<Self>> {
+        let mut options = vec![];
+        while let Some((tag, rest)) = input.split_first() {
+            let (option, rest) = match *tag {
+                Self::PRIOR_CONTEXT_TAG => (Self::PriorContext, rest),
+                Self::DELEGATE_TAG => (Self::Delegate, rest),
+                Self::MIN_EPOCH_TAG => {
+                    let (epoch, rest) = decode_u64(rest)?;
+                    (Self::MinEpoch(epoch), rest)
+                }
+                Self::COMPRESSED_TAG => (Self::Compressed, rest),
+                Self::DESTINATION_ACCOUNT_TAG => (Self::DestinationAccount, rest),
+                Self::AUTHORITY_ALLOWLIST_TAG => (Self::AuthorityAllowlist, rest),
+                Self::RATE_LIMIT_TAG => (Self::RateLimit, rest),
+                Self::MEMO_LOG_TAG => (Self::MemoLog, rest),
+                Self::ANCHOR_EVENT_TAG => (Self::AnchorEvent, rest),
+                Self::CREATE_CONTEXT_ACCOUNT_TAG => (Self::CreateContextAccount, rest),
+                Self::PREDICATE_TAG => {
+                    let offset = u32::from_le_bytes(rest.get(..4)?.try_into().ok()?);
+                    let length = *rest.get(4)?;
+                    if usize::from(length) > MAX_PREDICATE_LENGTH {
+                        return None;
+                    }
+                    let expected = rest.get(5..5 + MAX_PREDICATE_LENGTH)?.try_into().ok()?;
+                    (
+                        Self::Predicate {
+                            offset,
+                            length,
+                            expected,
+                        },
+                        rest.get(5 + MAX_PREDICATE_LENGTH..)?,
+                    )
+                }
+                Self::DISTINCT_ENDPOINTS_TAG => (Self::DistinctEndpoints, rest),
+                Self::REVEAL_COMMITMENT_TAG => (Self::RevealCommitment, rest),
+                Self::VERIFIER_SIGNATURE_TAG => (Self::VerifierSignature, rest),
+                Self::SLOT_LOG_TAG => (Self::SlotLog, rest),
+                Self::USAGE_COUNTER_TAG => (Self::UsageCounter, rest),
+                Self::SLOT_WINDOWS_TAG => (Self::SlotWindows, rest),
+                Self::RECEIPT_TAG => (Self::Receipt, rest),
+                Self::NULLIFIER_TAG => (Self::Nullifier, rest),
+                Self::MIRROR_ACCOUNT_TAG => (Self::MirrorAccount, rest),
+                Self::TELEMETRY_TAG => (Self::Telemetry, rest),
+                Self::CONTEXT_ADDRESS_TAG => {
+                    let address = Pubkey::try_from(rest.get(..32)?).ok()?;
+                    (Self::ContextAddress(address), rest.get(32..)?)
+                }
+                Self::TOKEN_2022_LAYOUT_TAG => (Self::Token2022Layout, rest),
+                Self::PRIOR_INSTRUCTION_TAG => {
+                    let program_id = Pubkey::try_from(rest.get(..32)?).ok()?;
+                    let discriminant = *rest.get(32)?;
+                    (
+                        Self::PriorInstruction {
+                            program_id,
+                            discriminant,
+                        },
+                        rest.get(33..)?,
+                    )
+                }
+                Self::STRUCTURED_LOG_TAG => (Self::StructuredLog, rest),
+                Self::REFERENCE_ONLY_TAG => (Self::ReferenceOnly, rest),
+                Self::MIN_SECURITY_LEVEL_TAG => (Self::MinSecurityLevel, rest),
+                Self::PARTIAL_TAG => (Self::Partial, rest),
+                Self::PROGRAM_MANAGED_TAG => {
+                    let (id, rest) = decode_u64(rest)?;
+                    (Self::ProgramManaged(id), rest)
+                }
+                Self::KEY_BLACKLIST_TAG => (Self::KeyBlacklist, rest),
+                Self::ROUTE_CANDIDATE_TAG => {
+                    let (proof_type, rest) = rest.split_first()?;
+                    (Self::RouteCandidate(*proof_type), rest)
+                }
+                Self::RETRY_NONCE_TAG => {
+                    let (nonce, rest) = decode_u64(rest)?;
+                    (Self::RetryNonce(nonce), rest)
+                }
+                _ => return None,
+            };
+            options.push(option);
+            input = rest;
+        }
+        Some(options)
+    }
+}
+
+fn decode_u64(input: &[u8]) -> Option<(u64, &[u8])> {
+    let bytes = input.get(..8)?.try_into().ok()?;
+    Some((u64::from_le_bytes(bytes), input.get(8..)?))
+}
+
+/// The borsh-encoded instruction data of a `VerifyBorsh` instruction
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BorshVerifyProof {
+    /// The verify instruction to process
+    pub instruction: u8,
+    /// The `Pod` bytes of the proof data
+    pub proof_data: Vec<u8>,
+    /// The verify options
+    pub options: Vec<VerifyOption>,
+}
+
+impl BorshVerifyProof {
+    /// Returns the equivalent instruction data of the verify instruction in the envelope.
+    pub fn to_instruction_data(&self) -> Vec<u8> {
+        let mut data = vec![self.instruction];
+        data.extend_from_slice(&self.proof_data);
+        for option in &self.options {
+            option.encode(&mut data);
+        }
+        data
+    }
+}
+
+/// An entry of a `VerifyBatch` instruction
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BatchEntry {
+    /// The verify instruction of the proof
+    pub instruction: u8,
+    /// The `Pod` bytes of the proof data
+    pub proof_data: Vec<u8>,
+    /// The indices of the proof context account and its owner among the instruction accounts, if
+    /// a proof context state is to be created
+    pub context_accounts: Option<(u8, u8)>,
+}
+
+/// The borsh-encoded instruction data of a `VerifyBatch` instruction
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BorshVerifyBatch {
+    /// The entries to process, in order
+    pub entries: Vec<BatchEntry>,
+}
+
+/// Pubkeys associated with a context state account to be used as parameters to functions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ContextStateInfo<'a> {
+    pub context_state_account: &'a Pubkey,
+    pub context_state_authority: &'a Pubkey,
+}
+
+/// Returns the proof type of the context state created by a verify instruction.
+///
+/// `CloseContextState`, `MigrateContextState`, `CommitProof`, `SweepContextStates`,
+/// `VerifyWithheldFeeSum`, `CompleteContextState`, `InitializePolicyAccount`, and
+/// `UpdatePolicyAccount` do not create a context state and map to
+/// `ProofType::Uninitialized`, as do `VerifyBorsh` and `VerifyBatch`, whose proof types depend on
+/// their envelopes.
+pub fn expected_proof_type(instruction: ProofInstruction) -> ProofType {
+    match instruction {
+        ProofInstruction::CloseContextState
+        | ProofInstruction::MigrateContextState
+        | ProofInstruction::VerifyBorsh
+        | ProofInstruction::CommitProof
+        | ProofInstruction::SweepContextStates
+        | ProofInstruction::VerifyBatch
+        | ProofInstruction::VerifyWithheldFeeSum
+        | ProofInstruction::CompleteContextState
+        | ProofInstruction::InitializePolicyAccount
+        | ProofInstruction::UpdatePolicyAccount => ProofType::Uninitialized,
+        ProofInstruction::VerifyCloseAccount => {
+            <CloseAccountData as ZkProofData<CloseAccountProofContext>>::PROOF_TYPE
+        }
+        ProofInstruction::VerifyWithdraw => {
+            <WithdrawData as ZkProofData<WithdrawProofContext>>::PROOF_TYPE
+        }
+        ProofInstruction::VerifyWithdrawWithheldTokens => {
+            <WithdrawWithheldTokensData as ZkProofData<WithdrawWithheldTokensProofContext>>::PROOF_TYPE
+        }
+        ProofInstruction::VerifyTransfer => {
+            <TransferData as ZkProofData<TransferProofContext>>::PROOF_TYPE
+        }
+        ProofInstruction::VerifyTransferWithFee => {
+            <TransferWithFeeData as ZkProofData<TransferWithFeeProofContext>>::PROOF_TYPE
+        }
+        ProofInstruction::VerifyPubkeyValidity => {
+            <PubkeyValidityData as ZkProofData<PubkeyValidityProofContext>>::PROOF_TYPE
+        }
+        ProofInstruction::VerifyTransferSum => {
+            <TransferSumData as ZkProofData<TransferSumProofContext>>::PROOF_TYPE
+        }
+        ProofInstruction::VerifyMaxValue => {
+            <MaxValueData as ZkProofData<MaxValueProofContext>>::PROOF_TYPE
+        }
+        ProofInstruction::VerifyParity => {
+            <ParityData as ZkProofData<ParityProofContext>>::PROOF_TYPE
+        }
+        ProofInstruction::VerifyGreaterThan => {
+            <GreaterThanData as ZkProofData<GreaterThanProofContext>>::PROOF_TYPE
+        }
+        ProofInstruction::VerifyKeyPossession => {
+            <KeyPossessionData as ZkProofData<KeyPossessionProofContext>>::PROOF_TYPE
+        }
+        ProofInstruction::VerifyCommitmentEquality => {
+            <CommitmentEqualityData as ZkProofData<CommitmentEqualityProofContext>>::PROOF_TYPE
+        }
+    }
+}
+
+/// Returns the number of compute units that the proof program consumes to process an instruction.
+pub fn compute_units(instruction: ProofInstruction) -> u64 {
+    // TODO: Tune the number of units consumed.  The current values are just rough estimates
+    match instruction {
+        ProofInstruction::CloseContextState
+        | ProofInstruction::VerifyCloseAccount
+        | ProofInstruction::VerifyWithdraw
+        | ProofInstruction::VerifyWithdrawWithheldTokens
+        | ProofInstruction::VerifyTransfer
+        | ProofInstruction::VerifyTransferWithFee
+        | ProofInstruction::VerifyPubkeyValidity
+        | ProofInstruction::VerifyTransferSum
+        | ProofInstruction::MigrateContextState
+        | ProofInstruction::VerifyBorsh
+        | ProofInstruction::CommitProof
+        | ProofInstruction::SweepContextStates
+        | ProofInstruction::VerifyMaxValue
+        | ProofInstruction::VerifyParity
+        | ProofInstruction::VerifyGreaterThan
+        | ProofInstruction::CompleteContextState
+        | ProofInstruction::InitializePolicyAccount
+        | ProofInstruction::UpdatePolicyAccount
+        | ProofInstruction::VerifyKeyPossession
+        | ProofInstruction::VerifyCommitmentEquality => 100_000,
+        // the entries of a batch are charged individually, see `batch_compute_units`
+        ProofInstruction::VerifyBatch => 0,
+        // the proofs of a fee sum are charged individually, see `withheld_fee_sum_compute_units`
+        ProofInstruction::VerifyWithheldFeeSum => 0,
+    }
+}
+
+/// The number of compute units that the proof program consumes to process a verify instruction
+/// whose `VerifyOption::RetryNonce` has already been processed, in place of `compute_units`
+pub const RETRY_NONCE_REPLAY_COMPUTE_UNITS: u64 = 1_000;
+
+/// Returns the number of compute units that the proof program consumes to process the proofs of a
+/// `VerifyWithheldFeeSum` instruction with the given number of withheld fees.
+pub fn withheld_fee_sum_compute_units(number_of_fees: u64) -> u64 {
+    // the sum proof is charged as one more proof of the same kind
+    compute_units(ProofInstruction::VerifyCloseAccount)
+        .saturating_mul(number_of_fees.saturating_add(1))
+}
+
+/// Returns the number of compute units that the proof program consumes to process the entries of a
+/// `VerifyBatch` instruction.
+pub fn batch_compute_units(entries: &[BatchEntry]) -> u64 {
+    entries
+        .iter()
+        .filter_map(|entry| ProofInstruction::instruction_type(&[entry.instruction]))
+        .map(compute_units)
+        .sum()
+}
+
+/// Checks that the compute unit limit requested by the compute budget instructions in
+/// `instructions` covers the proof program instructions among them.
+///
+/// Returns the number of missing compute units if the limit is insufficient. Instructions of other
+/// programs are assumed to consume no compute units.
+#[cfg(not(target_os = "solana"))]
+pub fn check_budget_sufficiency(instructions: &[Instruction]) -> Result<(), u64> {
+    let mut requested_compute_unit_limit = None;
+    let mut number_of_non_compute_budget_instructions: u64 = 0;
+    let mut required_compute_units: u64 = 0;
+
+    for instruction in instructions {
+        if instruction.program_id == compute_budget::id() {
+            match solana_program::borsh::try_from_slice_unchecked(&instruction.data) {
+                Ok(ComputeBudgetInstruction::SetComputeUnitLimit(units))
+                | Ok(ComputeBudgetInstruction::RequestUnitsDeprecated { units, .. }) => {
+                    requested_compute_unit_limit = Some(u64::from(units));
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        number_of_non_compute_budget_instructions += 1;
+        if instruction.program_id == crate::zk_token_proof_program::id() {
+            match ProofInstruction::instruction_type(&instruction.data) {
+                Some(ProofInstruction::VerifyBatch) => {
+                    if let Ok(batch) = BorshVerifyBatch::try_from_slice(&instruction.data[1..]) {
+                        required_compute_units += batch_compute_units(&batch.entries);
+                    }
+                }
+                Some(ProofInstruction::VerifyWithheldFeeSum) => {
+                    let number_of_fees = instruction
+                        .data
+                        .len()
+                        .saturating_sub(1 + size_of::<WithheldFeeSumData>())
+                        / size_of::<WithdrawWithheldTokensData>();
+                    required_compute_units += withheld_fee_sum_compute_units(number_of_fees as u64);
+                }
+                Some(proof_instruction) => {
+                    required_compute_units += compute_units(proof_instruction);
+                }
+                None => {}
+            }
+        }
+    }
+
+    let compute_unit_limit = requested_compute_unit_limit
+        .unwrap_or(
+            number_of_non_compute_budget_instructions * DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT,
+        )
+        .min(MAX_COMPUTE_UNIT_LIMIT);
+
+    match required_compute_units.checked_sub(compute_unit_limit) {
+        Some(deficit) if deficit > 0 => Err(deficit),
+        _ => Ok(()),
+    }
+}
+
+/// Create a `CloseContextState` instruction.
+pub fn close_context_state(
+    context_state_info: ContextStateInfo,
+    destination_account: &Pubkey,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(*context_state_info.context_state_account, false),
+        AccountMeta::new(*destination_account, false),
+        AccountMeta::new_readonly(*context_state_info.context_state_authority, true),
+    ];
+
+    let data = vec![ToPrimitive::to_u8(&ProofInstruction::CloseContextState).unwrap()];
+
+    Instruction {
+        program_id: crate::zk_token_proof_program::id(),
+        accounts,
+        data,
+    }
+}
+
+/// Create a `CloseContextState` instruction for a program to invoke, closing a proof context state
+/// whose owner or delegate is the id of the invoking program.
+pub fn close_context_state_by_program(
+    context_state_info: ContextStateInfo,
+    destination_account: &Pubkey,
+) -> Instruction {
+    let mut instruction = close_context_state(context_state_info, destination_account);
+    instruction.accounts[2].is_signer = false;
+    instruction
+}
+
+/// Create a `CloseContextState` instruction for a proof context state whose owner or delegate is
+/// an SPL multisig account, signed by `multisig_signers`.
+pub fn close_context_state_with_multisig(
+    context_state_info: ContextStateInfo,
+    destination_account: &Pubkey,
+    multisig_signers: &[&Pubkey],
+) -> Instruction {
+    let mut instruction = close_context_state(context_state_info, destination_account);
+    instruction.accounts[2].is_signer = false;
+    instruction.accounts.extend(
+        multisig_signers
+            .iter()
+            .map(|signer| AccountMeta::new_readonly(**signer, true)),
+    );
+    instruction
+}
+
+/// Create a `CompleteContextState` instruction.
+pub fn complete_context_state(
+    context_state_account: &Pubkey,
+    context_state_delegate: &Pubkey,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(*context_state_account, false),
+        AccountMeta::new_readonly(*context_state_delegate, true),
+    ];
+
+    let data = vec![ToPrimitive::to_u8(&ProofInstruction::CompleteContextState).unwrap()];
+
+    Instruction {
+        program_id: crate::zk_token_proof_program::id(),
+        accounts,
+        data,
+    }
+}
+
+/// Create a `SweepContextStates` instruction closing the proof context states among
+/// `context_state_accounts` that `context_state_authority` can close.
+pub fn sweep_context_states(
+    destination_account: &Pubkey,
+    context_state_authority: &Pubkey,
+    context_state_accounts: &[Pubkey],
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*destination_account, false),
+        AccountMeta::new_readonly(*context_state_authority, true),
+    ];
+    accounts.extend(
+        context_state_accounts
+            .iter()
+            .map(|context_state_account| AccountMeta::new(*context_state_account, false)),
+    );
+
+    let data = vec![ToPrimitive::to_u8(&ProofInstruction::SweepContextStates).unwrap()];
+
+    Instruction {
+        program_id: crate::zk_token_proof_program::id(),
+        accounts,
+        data,
+    }
+}
+
+/// Create a `MigrateContextState` instruction.
+pub fn migrate_context_state(
+    context_state_info: ContextStateInfo,
+    version: u8,
+    compressed: bool,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(*context_state_info.context_state_account, false),
+        AccountMeta::new_readonly(*context_state_info.context_state_authority, true),
+    ];
+
+    let data = vec![
+        ToPrimitive::to_u8(&ProofInstruction::MigrateContextState).unwrap(),
+        version,
+        if compressed {
+            PROOF_CONTEXT_STATE_FLAG_COMPRESSED
+        } else {
+            0
+        },
+    ];
+
+    Instruction {
+        program_id: crate::zk_token_proof_program::id(),
+        accounts,
+        data,
+    }
+}
+
+/// Create a `CommitProof` instruction committing to `proof_data`.
+pub fn commit_proof<T: Pod>(
+    commitment_account: &Pubkey,
+    committer: &Pubkey,
+    proof_data: &T,
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(*commitment_account, false),
+        AccountMeta::new_readonly(*committer, true),
+    ];
+
+    let mut data = vec![ToPrimitive::to_u8(&ProofInstruction::CommitProof).unwrap()];
+    data.extend_from_slice(&ProofCommitment::hash(proof_data));
+
+    Instruction {
+        program_id: crate::zk_token_proof_program::id(),
+        accounts,
+        data,
+    }
+}
+
+/// Create an `InitializePolicyAccount` instruction creating the policy account of the kind
+/// `discriminator` of `admin` with the given id and body.
+pub fn initialize_policy_account(
+    payer: &Pubkey,
+    admin: &Pubkey,
+    discriminator: &[u8; 8],
+    id: u64,
+    body: &[u8],
+) -> Instruction {
+    let (policy_account, _) = find_policy_account_address(discriminator, admin, id);
+    let accounts = vec![
+        AccountMeta::new(policy_account, false),
+        AccountMeta::new_readonly(*admin, true),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    let mut data = vec![ToPrimitive::to_u8(&ProofInstruction::InitializePolicyAccount).unwrap()];
+    data.extend_from_slice(discriminator);
+    data.extend_from_slice(&id.to_le_bytes());
+    data.extend_from_slice(body);
+
+    Instruction {
+        program_id: crate::zk_token_proof_program::id(),
+        accounts,
+        data,
+    }
+}
+
+/// Create an `UpdatePolicyAccount` instruction replacing the body of `policy_account`.
+pub fn update_policy_account(
+    policy_account: &Pubkey,
+    payer: &Pubkey,
+    admin: &Pubkey,
+    body: &[u8],
+) -> Instruction {
+    let accounts = vec![
+        AccountMeta::new(*policy_account, false),
+        AccountMeta::new_readonly(*admin, true),
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+
+    let mut data = vec![ToPrimitive::to_u8(&ProofInstruction::UpdatePolicyAccount).unwrap()];
+    data.extend_from_slice(body);
+
+    Instruction {
+        program_id: crate::zk_token_proof_program::id(),
+        accounts,
+        data,
+    }
+}
+
+/// Create a `VerifyCloseAccount` instruction.
+pub fn verify_close_account(
+    context_state_info: Option<ContextStateInfo>,
+    proof_data: &CloseAccountData,
+) -> Instruction {
+    ProofInstruction::VerifyCloseAccount.encode_verify_proof(context_state_info, proof_data)
+}
+
+/// Create a `VerifyWithdraw` instruction.
+pub fn verify_withdraw(
+    context_state_info: Option<ContextStateInfo>,
+    proof_data: &WithdrawData,
+) -> Instruction {
+    ProofInstruction::VerifyWithdraw.encode_verify_proof(context_state_info, proof_data)
+}
+
+/// Create a `VerifyWithdrawWithheldTokens` instruction.
+pub fn verify_withdraw_withheld_tokens(
+    context_state_info: Option<ContextStateInfo>,
+    proof_data: &WithdrawWithheldTokensData,
+) -> Instruction {
+    ProofInstruction::VerifyWithdrawWithheldTokens
+        .encode_verify_proof(context_state_info, proof_data)
+}
+
+/// Create a `VerifyTransfer` instruction.
+pub fn verify_transfer(
+    context_state_info: Option<ContextStateInfo>,
+    proof_data: &TransferData,
+) -> Instruction {
+    ProofInstruction::VerifyTransfer.encode_verify_proof(context_state_info, proof_data)
+}
+
+/// Create a `VerifyTransferWithFee` instruction.
+pub fn verify_transfer_with_fee(
+    context_state_info: Option<ContextStateInfo>,
+    proof_data: &TransferWithFeeData,
+) -> Instruction {
+    ProofInstruction::VerifyTransferWithFee.encode_verify_proof(context_state_info, proof_data)
+}
+
+/// Create a `VerifyPubkeyValidity` instruction.
+pub fn verify_pubkey_validity(
+    context_state_info: Option<ContextStateInfo>,
+    proof_data: &PubkeyValidityData,
+) -> Instruction {
+    ProofInstruction::VerifyPubkeyValidity.encode_verify_proof(context_state_info, proof_data)
+}
+
+/// Create a `VerifyTransferSum` instruction.
+pub fn verify_transfer_sum(
+    context_state_info: Option<ContextStateInfo>,
+    proof_data: &TransferSumData,
+) -> Instruction {
+    ProofInstruction::VerifyTransferSum.encode_verify_proof(context_state_info, proof_data)
+}
+
+/// Create a `VerifyMaxValue` instruction.
+pub fn verify_max_value(
+    context_state_info: Option<ContextStateInfo>,
+    proof_data: &MaxValueData,
+) -> Instruction {
+    ProofInstruction::VerifyMaxValue.encode_verify_proof(context_state_info, proof_data)
+}
+
+/// Create a `VerifyParity` instruction.
+pub fn verify_parity(
+    context_state_info: Option<ContextStateInfo>,
+    proof_data: &ParityData,
+) -> Instruction {
+    ProofInstruction::VerifyParity.encode_verify_proof(context_state_info, proof_data)
+}
+
+/// Create a `VerifyGreaterThan` instruction.
+pub fn verify_greater_than(
+    context_state_info: Option<ContextStateInfo>,
+    proof_data: &GreaterThanData,
+) -> Instruction {
+    ProofInstruction::VerifyGreaterThan.encode_verify_proof(context_state_info, proof_data)
+}
+
+/// Create a `VerifyKeyPossession` instruction.
+pub fn verify_key_possession(
+    context_state_info: Option<ContextStateInfo>,
+    proof_data: &KeyPossessionData,
+) -> Instruction {
+    ProofInstruction::VerifyKeyPossession.encode_verify_proof(context_state_info, proof_data)
+}
+
+/// Create a `VerifyCommitmentEquality` instruction.
+pub fn verify_commitment_equality(
+    context_state_info: Option<ContextStateInfo>,
+    proof_data: &CommitmentEqualityData,
+) -> Instruction {
+    ProofInstruction::VerifyCommitmentEquality.encode_verify_proof(context_state_info, proof_data)
+}
+
+/// Create a `VerifyBatch` instruction.
+///
+/// Each entry is given by its verify instruction, the `Pod` bytes of its proof data, and the proof
+/// context account to create, if any.
+pub fn verify_batch(
+    entries: &[(ProofInstruction, &[u8], Option<ContextStateInfo>)],
+) -> Instruction {
+    let mut accounts = vec![];
+    let entries = entries
+        .iter()
+        .map(|(instruction, proof_data, context_state_info)| {
+            let context_accounts = context_state_info.map(|context_state_info| {
+                let context_account_index = accounts.len() as u8;
+                accounts.push(AccountMeta::new(
+                    *context_state_info.context_state_account,
+                    false,
+                ));
+                accounts.push(AccountMeta::new_readonly(
+                    *context_state_info.context_state_authority,
+                    false,
+                ));
+                (context_account_index, context_account_index + 1)
+            });
+            BatchEntry {
+                instruction: ToPrimitive::to_u8(instruction).unwrap(),
+                proof_data: proof_data.to_vec(),
+                context_accounts,
+            }
+        })
+        .collect();
+
+    let mut data = vec![ToPrimitive::to_u8(&ProofInstruction::VerifyBatch).unwrap()];
+    // serializing into a `Vec` cannot fail
+    data.extend_from_slice(&BorshVerifyBatch { entries }.try_to_vec().unwrap());
+
+    Instruction {
+        program_id: crate::zk_token_proof_program::id(),
+        accounts,
+        data,
+    }
+}
+
+/// Create a `VerifyWithheldFeeSum` instruction.
+pub fn verify_withheld_fee_sum(
+    fee_sum_data: &WithheldFeeSumData,
+    proof_data: &[WithdrawWithheldTokensData],
+) -> Instruction {
+    let mut data = vec![ToPrimitive::to_u8(&ProofInstruction::VerifyWithheldFeeSum).unwrap()];
+    data.extend_from_slice(bytes_of(fee_sum_data));
+    data.extend_from_slice(bytemuck::cast_slice(proof_data));
+
+    Instruction {
+        program_id: crate::zk_token_proof_program::id(),
+        accounts: vec![],
+        data,
+    }
+}
+
+impl ProofInstruction {
+    pub fn encode_verify_proof<T, U>(
+        &self,
+        context_state_info: Option<ContextStateInfo>,
+        proof_data: &T,
+    ) -> Instruction
+    where
+        T: Pod + ZkProofData<U>,
+        U: Pod,
+    {
+        let accounts = if let Some(context_state_info) = context_state_info {
+            vec![
+                AccountMeta::new(*context_state_info.context_state_account, false),
+                AccountMeta::new_readonly(*context_state_info.context_state_authority, false),
+            ]
+        } else {
+            vec![]
+        };
+
+        let mut data = vec![ToPrimitive::to_u8(self).unwrap()];
+        data.extend_from_slice(bytes_of(proof_data));
+
+        Instruction {
+            program_id: crate::zk_token_proof_program::id(),
+            accounts,
+            data,
+        }
+    }
+
+    /// Encode a verify instruction with a sequence of verification options.
+    ///
+    /// The `option_accounts` must list the accounts consumed by `options` in order.
+    pub fn encode_verify_proof_with_options<T, U>(
+        &self,
+        context_state_info: Option<ContextStateInfo>,
+        proof_data: &T,
+        options: &[VerifyOption],
+        option_accounts: &[AccountMeta],
+    ) -> Instruction
+    where
+        T: Pod + ZkProofData<U>,
+        U: Pod,
+    {
+        let mut instruction = self.encode_verify_proof(context_state_info, proof_data);
+        // the system program requires a newly created account to sign
+        if options.contains(&VerifyOption::CreateContextAccount) {
+            if let Some(context_state_account) = instruction.accounts.first_mut() {
+                context_state_account.is_signer = true;
+            }
+        }
+        instruction.accounts.extend_from_slice(option_accounts);
+        for option in options {
+            option.encode(&mut instruction.data);
+        }
+        instruction
+    }
+
+    /// Encode a verify instruction whose proof context state is routed to the candidate account
+    /// of the proof's type, as by `VerifyOption::RouteCandidate`.
+    ///
+    /// The `options` and `option_accounts` are appended after the candidates.
+    pub fn encode_verify_proof_with_route<T, U>(
+        &self,
+        context_state_authority: &Pubkey,
+        candidates: &[(ProofType, &Pubkey)],
+        proof_data: &T,
+        options: &[VerifyOption],
+        option_accounts: &[AccountMeta],
+    ) -> Instruction
+    where
+        T: Pod + ZkProofData<U>,
+        U: Pod,
+    {
+        let mut instruction = self.encode_verify_proof(None, proof_data);
+        instruction
+            .accounts
+            .push(AccountMeta::new_readonly(*context_state_authority, false));
+        for (proof_type, candidate) in candidates {
+            instruction
+                .accounts
+                .push(AccountMeta::new(**candidate, false));
+            VerifyOption::RouteCandidate(*proof_type as u8).encode(&mut instruction.data);
+        }
+        instruction.accounts.extend_from_slice(option_accounts);
+        for option in options {
+            option.encode(&mut instruction.data);
+        }
+        instruction
+    }
+
+    /// Encode a verify instruction as a `VerifyBorsh` instruction with borsh-encoded data.
+    pub fn encode_verify_proof_borsh<T, U>(
+        &self,
+        context_state_info: Option<ContextStateInfo>,
+        proof_data: &T,
+        options: &[VerifyOption],
+        option_accounts: &[AccountMeta],
+    ) -> Instruction
+    where
+        T: Pod + ZkProofData<U>,
+        U: Pod,
+    {
+        let mut instruction = self.encode_verify_proof_with_options(
+            context_state_info,
+            proof_data,
+            options,
+            option_accounts,
+        );
+        let envelope = BorshVerifyProof {
+            instruction: ToPrimitive::to_u8(self).unwrap(),
+            proof_data: bytes_of(proof_data).to_vec(),
+            options: options.to_vec(),
+        };
+        instruction.data = vec![ToPrimitive::to_u8(&ProofInstruction::VerifyBorsh).unwrap()];
+        // serializing into a `Vec` cannot fail
+        instruction
+            .data
+            .extend_from_slice(&envelope.try_to_vec().unwrap());
+        instruction
+    }
+
+    pub fn instruction_type(input: &[u8]) -> Option<Self> {
+        input
+            .first()
+            .and_then(|instruction| FromPrimitive::from_u8(*instruction))
+    }
+
+    pub fn proof_data<T, U>(input: &[u8]) -> Option<&T>
+    where
+        T: Pod + ZkProofData<U>,
+        U: Pod,
+    {
+        input
+            .get(1..)
+            .and_then(|data| bytemuck::try_from_bytes(data).ok())
+    }
+
+    pub fn proof_data_with_options<T, U>(input: &[u8]) -> Option<(&T, Vec<VerifyOption>)>
+    where
+        T: Pod + ZkProofData<U>,
+        U: Pod,
+    {
+        let proof_data_end = size_of::<T>().checked_add(1)?;
+        let proof_data = input
+            .get(1..proof_data_end)
+            .and_then(|data| bytemuck::try_from_bytes(data).ok())?;
+        let options = VerifyOption::decode_all(input.get(proof_data_end..)?)?;
+        Some((proof_data, options))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, bytemuck::Zeroable};
+
+    #[test]
+    fn test_expected_proof_type() {
+        assert_eq!(
+            expected_proof_type(ProofInstruction::CloseContextState),
+            ProofType::Uninitialized
+        );
+        assert_eq!(
+            expected_proof_type(ProofInstruction::MigrateContextState),
+            ProofType::Uninitialized
+        );
+        assert_eq!(
+            expected_proof_type(ProofInstruction::VerifyBorsh),
+            ProofType::Uninitialized
+        );
+        assert_eq!(
+            expected_proof_type(ProofInstruction::CommitProof),
+            ProofType::Uninitialized
+        );
+        assert_eq!(
+            expected_proof_type(ProofInstruction::SweepContextStates),
+            ProofType::Uninitialized
+        );
+        assert_eq!(
+            expected_proof_type(ProofInstruction::VerifyBatch),
+            ProofType::Uninitialized
+        );
+        assert_eq!(
+            expected_proof_type(ProofInstruction::VerifyWithheldFeeSum),
+            ProofType::Uninitialized
+        );
+        assert_eq!(
+            expected_proof_type(ProofInstruction::CompleteContextState),
+            ProofType::Uninitialized
+        );
+        assert_eq!(
+            expected_proof_type(ProofInstruction::InitializePolicyAccount),
+            ProofType::Uninitialized
+        );
+        assert_eq!(
+            expected_proof_type(ProofInstruction::UpdatePolicyAccount),
+            ProofType::Uninitialized
+        );
+        assert_eq!(
+            expected_proof_type(ProofInstruction::VerifyCloseAccount),
+            ProofType::CloseAccount
+        );
+        assert_eq!(
+            expected_proof_type(ProofInstruction::VerifyWithdraw),
+            ProofType::Withdraw
+        );
+        assert_eq!(
+            expected_proof_type(ProofInstruction::VerifyWithdrawWithheldTokens),
+            ProofType::WithdrawWithheldTokens
+        );
+        assert_eq!(
+            expected_proof_type(ProofInstruction::VerifyTransfer),
+            ProofType::Transfer
+        );
+        assert_eq!(
+            expected_proof_type(ProofInstruction::VerifyTransferWithFee),
+            ProofType::TransferWithFee
+        );
+        assert_eq!(
+            expected_proof_type(ProofInstruction::VerifyPubkeyValidity),
+            ProofType::PubkeyValidity
+        );
+        assert_eq!(
+            expected_proof_type(ProofInstruction::VerifyTransferSum),
+            ProofType::TransferSum
+        );
+        assert_eq!(
+            expected_proof_type(ProofInstruction::VerifyMaxValue),
+            ProofType::MaxValue
+        );
+        assert_eq!(
+            expected_proof_type(ProofInstruction::VerifyParity),
+            ProofType::Parity
+        );
+        assert_eq!(
+            expected_proof_type(ProofInstruction::VerifyGreaterThan),
+            ProofType::GreaterThan
+        );
+        assert_eq!(
+            expected_proof_type(ProofInstruction::VerifyKeyPossession),
+            ProofType::KeyPossession
+        );
+        assert_eq!(
+            expected_proof_type(ProofInstruction::VerifyCommitmentEquality),
+            ProofType::CommitmentEquality
+        );
+    }
+
+    #[test]
+    fn test_check_budget_sufficiency() {
+        let proof_data = CloseAccountData::zeroed();
+        let proof_instruction = verify_close_account(None, &proof_data);
+
+        // the default limit of 200,000 units per instruction covers the proofs
+        let instructions = vec![proof_instruction.clone(), proof_instruction.clone()];
+        assert_eq!(check_budget_sufficiency(&instructions), Ok(()));
+
+        // a requested limit that covers the proofs
+        let instructions = vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+            proof_instruction.clone(),
+            proof_instruction.clone(),
+        ];
+        assert_eq!(check_budget_sufficiency(&instructions), Ok(()));
+
+        // a requested limit that does not cover the proofs
+        let instructions = vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(150_000),
+            proof_instruction.clone(),
+            proof_instruction,
+        ];
+        assert_eq!(check_budget_sufficiency(&instructions), Err(50_000));
+
+        // the entries of a batch are charged individually
+        let batch_instruction = verify_batch(&[
+            (
+                ProofInstruction::VerifyCloseAccount,
+                bytes_of(&proof_data),
+                None,
+            ),
+            (
+                ProofInstruction::VerifyCloseAccount,
+                bytes_of(&proof_data),
+                None,
+            ),
+            (
+                ProofInstruction::VerifyCloseAccount,
+                bytes_of(&proof_data),
+                None,
+            ),
+        ]);
+        let instructions = vec![
+            ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+            batch_instruction,
+        ];
+        assert_eq!(check_budget_sufficiency(&instructions), Err(100_000));
+    }
+
+    #[test]
+    fn test_borsh_verify_proof_encoding() {
+        let options = [
+            VerifyOption::PriorContext,
+            VerifyOption::MinEpoch(7),
+            VerifyOption::Compressed,
+        ];
+
+        let mut encoded = vec![];
+        for option in &options {
+            option.encode(&mut encoded);
+        }
+        assert_eq!(options.to_vec().try_to_vec().unwrap()[4..], encoded[..]);
+
+        let proof_data = CloseAccountData::zeroed();
+        let raw = ProofInstruction::VerifyCloseAccount.encode_verify_proof_with_options(
+            None,
+            &proof_data,
+            &options,
+            &[],
+        );
+        let borsh = ProofInstruction::VerifyCloseAccount.encode_verify_proof_borsh(
+            None,
+            &proof_data,
+            &options,
+            &[],
+        );
+        let envelope = BorshVerifyProof::try_from_slice(&borsh.data[1..]).unwrap();
+        assert_eq!(envelope.to_instruction_data(), raw.data);
     }
 }
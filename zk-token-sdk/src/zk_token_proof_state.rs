@@ -1,15 +1,100 @@
 use {
-    crate::{zk_token_elgamal::pod::PodProofType, zk_token_proof_instruction::ProofType},
+    crate::{
+        zk_token_elgamal::pod::{ElGamalPubkey, PodProofType, PodU64},
+        zk_token_proof_instruction::{
+            CloseAccountProofContext, CommitmentEqualityProofContext, GreaterThanProofContext,
+            KeyPossessionProofContext, MaxValueProofContext, ParityProofContext, ProofType,
+            PubkeyValidityProofContext, TransferProofContext, TransferSumProofContext,
+            TransferWithFeeProofContext, WithdrawProofContext, WithdrawWithheldTokensProofContext,
+        },
+    },
     bytemuck::{bytes_of, Pod, Zeroable},
     num_traits::ToPrimitive,
     solana_program::{
+        hash::{hash, hashv},
         instruction::{InstructionError, InstructionError::InvalidAccountData},
         pubkey::Pubkey,
     },
-    std::mem::size_of,
+    std::{borrow::Cow, mem::size_of},
 };
 
+/// The layout version of a context state in the default layout, a `ProofContextState`, which has
+/// no version field
+pub const PROOF_CONTEXT_STATE_VERSION_DEFAULT: u8 = 0;
+
+/// The layout version of a context state in the extended layout, which starts with an
+/// `ExtendedProofContextStateMeta`
+pub const PROOF_CONTEXT_STATE_VERSION_EXTENDED: u8 = 1;
+
+/// The byte that an extended context state stores at the offset of the proof type in the default
+/// layout
+///
+/// The marker is not the value of any `ProofType`, so the layout of a context state is determined
+/// by this byte alone, and readers of the default layout reject extended context states instead of
+/// misreading them.
+pub const EXTENDED_CONTEXT_STATE_MARKER: u8 = 0xff;
+
+/// The flag set in an extended context state whose proof context is stored compressed
+///
+/// The compressed proof context consists of the length of the raw proof context as a
+/// little-endian `u16`, a bitmap that marks the 32-byte chunks of the raw proof context that are
+/// entirely zero, and the remaining chunks in order. Identity points and unset pubkeys are encoded
+/// as zero chunks, so proof contexts that contain them shrink accordingly.
+pub const PROOF_CONTEXT_STATE_FLAG_COMPRESSED: u8 = 0x01;
+
+/// The flag set in an extended context state that only stores a reference to the proof
+///
+/// The proof context is replaced by the SHA-256 hash of the proof data, as returned by
+/// `ProofCommitment::hash`, so that the context state is a `ProofReference` of the same length
+/// for all proof types. The proof itself is recovered from the transaction that created the
+/// context state.
+pub const PROOF_CONTEXT_STATE_FLAG_REFERENCE: u8 = 0x02;
+
+/// The flag set in a partial context state created with the `Partial` verify option and cleared by
+/// `CompleteContextState`
+///
+/// The proof context of a partial context state cannot be decoded until it is completed.
+pub const PROOF_CONTEXT_STATE_FLAG_INCOMPLETE: u8 = 0x80;
+
+const PROOF_CONTEXT_STATE_KNOWN_FLAGS: u8 = PROOF_CONTEXT_STATE_FLAG_COMPRESSED
+    | PROOF_CONTEXT_STATE_FLAG_REFERENCE
+    | PROOF_CONTEXT_STATE_FLAG_INCOMPLETE;
+
+/// The policy recorded in an extended context state created with the `AuthorityAllowlist` verify
+/// option
+pub const PROOF_CONTEXT_STATE_POLICY_AUTHORITY_ALLOWLIST: u8 = 0x01;
+
+/// The policy recorded in an extended context state created with the `RateLimit` verify option
+pub const PROOF_CONTEXT_STATE_POLICY_RATE_LIMIT: u8 = 0x02;
+
+/// The policy recorded in an extended context state created with the `SlotWindows` verify option
+pub const PROOF_CONTEXT_STATE_POLICY_SLOT_WINDOWS: u8 = 0x04;
+
+/// The policy recorded in an extended context state created with the `MinSecurityLevel` verify
+/// option
+pub const PROOF_CONTEXT_STATE_POLICY_MIN_SECURITY_LEVEL: u8 = 0x08;
+
+/// The policy recorded in an extended context state created with the `KeyBlacklist` verify option
+pub const PROOF_CONTEXT_STATE_POLICY_KEY_BLACKLIST: u8 = 0x10;
+
+/// The policy recorded in an extended context state created with the `VerifierSignature` verify
+/// option
+pub const PROOF_CONTEXT_STATE_POLICY_VERIFIER_SIGNATURE: u8 = 0x20;
+
+const PROOF_CONTEXT_STATE_KNOWN_POLICIES: u8 = PROOF_CONTEXT_STATE_POLICY_AUTHORITY_ALLOWLIST
+    | PROOF_CONTEXT_STATE_POLICY_RATE_LIMIT
+    | PROOF_CONTEXT_STATE_POLICY_SLOT_WINDOWS
+    | PROOF_CONTEXT_STATE_POLICY_MIN_SECURITY_LEVEL
+    | PROOF_CONTEXT_STATE_POLICY_KEY_BLACKLIST
+    | PROOF_CONTEXT_STATE_POLICY_VERIFIER_SIGNATURE;
+
+const COMPRESSION_CHUNK_SIZE: usize = 32;
+
 /// The proof context account state
+///
+/// This is the default layout of a context state, which SPL Token 2022 reads. Context states with
+/// a delegate, a compressed or referenced proof context, an incomplete flag, or enforced policies
+/// use the extended layout instead, see `ExtendedProofContextStateMeta`.
 #[derive(Clone, Copy, Debug, PartialEq)]
 #[repr(C)]
 pub struct ProofContextState<T: Pod> {
@@ -41,6 +126,61 @@ impl<T: Pod> ProofContextState<T> {
         buf
     }
 
+    /// Encode a context state in the extended layout that can also be closed by
+    /// `context_state_delegate`.
+    pub fn encode_with_delegate(
+        context_state_authority: &Pubkey,
+        context_state_delegate: &Pubkey,
+        proof_type: ProofType,
+        proof_context: &T,
+    ) -> Vec<u8> {
+        let mut buf = ExtendedProofContextStateMeta::new(
+            context_state_authority,
+            context_state_delegate,
+            proof_type,
+            0,
+        )
+        .to_vec();
+        buf.extend_from_slice(bytes_of(proof_context));
+        buf
+    }
+
+    /// Encode a context state in the extended layout with the proof context stored compressed.
+    pub fn encode_compressed(
+        context_state_authority: &Pubkey,
+        context_state_delegate: &Pubkey,
+        proof_type: ProofType,
+        proof_context: &T,
+    ) -> Vec<u8> {
+        let mut buf = ExtendedProofContextStateMeta::new(
+            context_state_authority,
+            context_state_delegate,
+            proof_type,
+            PROOF_CONTEXT_STATE_FLAG_COMPRESSED,
+        )
+        .to_vec();
+        buf.extend_from_slice(&compress(bytes_of(proof_context)));
+        buf
+    }
+
+    /// Decode a context state of either layout, decompressing the proof context if needed.
+    ///
+    /// The delegate and flags of an extended context state are not part of `ProofContextState`,
+    /// use `DecodedContextState::decode` to read them as well.
+    pub fn decode(input: &[u8]) -> Result<Self, InstructionError> {
+        let proof_context = decode_proof_context_data(input)?;
+        if proof_context.len() != size_of::<T>() {
+            return Err(InvalidAccountData);
+        }
+
+        let header = ContextStateHeader::decode(input)?;
+        Ok(Self {
+            context_state_authority: header.context_state_authority,
+            proof_type: header.proof_type,
+            proof_context: bytemuck::pod_read_unaligned(&proof_context),
+        })
+    }
+
     /// Interpret a slice as a `ProofContextState`.
     ///
     /// This function requires a generic parameter. To access only the generic-independent fields
@@ -70,3 +210,1894 @@ impl ProofContextStateMeta {
             .ok_or(InvalidAccountData)
     }
 }
+
+/// The proof context account state in the layout that SPL Token 2022 reads.
+///
+/// SPL Token 2022 requires a proof context account to be owned by the proof program and
+/// interprets its data as this layout with `bytemuck::try_from_bytes`, checking the proof type
+/// before it uses the proof context. This is the default layout, so a context state in this layout
+/// has no delegate or flags.
+pub type Token2022ProofContextState<T> = ProofContextState<T>;
+
+/// The `Token2022ProofContextState` without the proof context itself.
+pub type Token2022ProofContextStateMeta = ProofContextStateMeta;
+
+/// The generic-independent fields at the start of a context state in the extended layout, which
+/// are followed by the proof context, encoded as given by the flags
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+#[repr(C)]
+pub struct ExtendedProofContextStateMeta {
+    /// The proof context authority that can close the account
+    pub context_state_authority: Pubkey,
+    /// Always `EXTENDED_CONTEXT_STATE_MARKER`
+    pub marker: u8,
+    /// The proof type for the context data
+    pub proof_type: PodProofType,
+    /// The layout version, `PROOF_CONTEXT_STATE_VERSION_EXTENDED`
+    pub version: u8,
+    /// The `PROOF_CONTEXT_STATE_FLAG_*` flags of the context state
+    pub flags: u8,
+    /// The delegate that can also close the account, or `Pubkey::default()` if there is none
+    pub context_state_delegate: Pubkey,
+    /// The `PROOF_CONTEXT_STATE_POLICY_*` policies enforced when the context state was created
+    pub policies: u8,
+    /// The `context_state_policy_hash` of the policy accounts of the enforced policies, or zero if
+    /// no policy was enforced
+    pub policy_hash: [u8; 32],
+}
+
+impl ExtendedProofContextStateMeta {
+    pub fn new(
+        context_state_authority: &Pubkey,
+        context_state_delegate: &Pubkey,
+        proof_type: ProofType,
+        flags: u8,
+    ) -> Self {
+        Self {
+            context_state_authority: *context_state_authority,
+            marker: EXTENDED_CONTEXT_STATE_MARKER,
+            proof_type: proof_type.into(),
+            version: PROOF_CONTEXT_STATE_VERSION_EXTENDED,
+            flags,
+            context_state_delegate: *context_state_delegate,
+            policies: 0,
+            policy_hash: [0; 32],
+        }
+    }
+
+    fn to_vec(self) -> Vec<u8> {
+        bytes_of(&self).to_vec()
+    }
+
+    pub fn try_from_bytes(input: &[u8]) -> Result<&Self, InstructionError> {
+        input
+            .get(..size_of::<ExtendedProofContextStateMeta>())
+            .and_then(|data| bytemuck::try_from_bytes::<Self>(data).ok())
+            .filter(|meta| meta.is_valid())
+            .ok_or(InvalidAccountData)
+    }
+
+    pub fn try_from_bytes_mut(input: &mut [u8]) -> Result<&mut Self, InstructionError> {
+        input
+            .get_mut(..size_of::<ExtendedProofContextStateMeta>())
+            .and_then(|data| bytemuck::try_from_bytes_mut::<Self>(data).ok())
+            .filter(|meta| meta.is_valid())
+            .ok_or(InvalidAccountData)
+    }
+
+    fn is_valid(&self) -> bool {
+        self.marker == EXTENDED_CONTEXT_STATE_MARKER
+            && self.version == PROOF_CONTEXT_STATE_VERSION_EXTENDED
+            && self.flags & !PROOF_CONTEXT_STATE_KNOWN_FLAGS == 0
+            && self.policies & !PROOF_CONTEXT_STATE_KNOWN_POLICIES == 0
+    }
+}
+
+/// The generic-independent fields of a context state in either layout
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ContextStateHeader {
+    /// The proof context authority that can close the account
+    pub context_state_authority: Pubkey,
+    /// The delegate that can also close the account, or `Pubkey::default()` if there is none
+    pub context_state_delegate: Pubkey,
+    /// The proof type for the context data
+    pub proof_type: PodProofType,
+    /// The layout version of the context state
+    pub version: u8,
+    /// The `PROOF_CONTEXT_STATE_FLAG_*` flags of the context state, always zero in the default
+    /// layout
+    pub flags: u8,
+    /// The `PROOF_CONTEXT_STATE_POLICY_*` policies enforced when the context state was created,
+    /// always zero in the default layout
+    pub policies: u8,
+    /// The `context_state_policy_hash` of the policy accounts of the enforced policies, always zero
+    /// in the default layout
+    pub policy_hash: [u8; 32],
+}
+
+impl ContextStateHeader {
+    /// Decode the header of a context state, dispatching on the byte at the offset of the proof
+    /// type: an `EXTENDED_CONTEXT_STATE_MARKER` selects the extended layout, and any other value
+    /// the default layout.
+    pub fn decode(input: &[u8]) -> Result<Self, InstructionError> {
+        let marker_offset = size_of::<Pubkey>();
+        if input.get(marker_offset) == Some(&EXTENDED_CONTEXT_STATE_MARKER) {
+            let meta = ExtendedProofContextStateMeta::try_from_bytes(input)?;
+            Ok(Self {
+                context_state_authority: meta.context_state_authority,
+                context_state_delegate: meta.context_state_delegate,
+                proof_type: meta.proof_type,
+                version: meta.version,
+                flags: meta.flags,
+                policies: meta.policies,
+                policy_hash: meta.policy_hash,
+            })
+        } else {
+            let meta = ProofContextStateMeta::try_from_bytes(input)?;
+            Ok(Self {
+                context_state_authority: meta.context_state_authority,
+                context_state_delegate: Pubkey::default(),
+                proof_type: meta.proof_type,
+                version: PROOF_CONTEXT_STATE_VERSION_DEFAULT,
+                flags: 0,
+                policies: 0,
+                policy_hash: [0; 32],
+            })
+        }
+    }
+
+    /// The length of the meta of the context state in its layout
+    pub fn meta_length(&self) -> usize {
+        match self.version {
+            PROOF_CONTEXT_STATE_VERSION_DEFAULT => size_of::<ProofContextStateMeta>(),
+            _ => size_of::<ExtendedProofContextStateMeta>(),
+        }
+    }
+
+    /// Returns true if `pubkey` is the context state authority or its delegate.
+    pub fn is_authorized(&self, pubkey: &Pubkey) -> bool {
+        *pubkey == self.context_state_authority
+            || (self.context_state_delegate != Pubkey::default()
+                && *pubkey == self.context_state_delegate)
+    }
+
+    /// Returns true if the context state is not a partial context state awaiting completion.
+    pub fn is_complete(&self) -> bool {
+        self.flags & PROOF_CONTEXT_STATE_FLAG_INCOMPLETE == 0
+    }
+}
+
+/// The context state written by the `ReferenceOnly` verify option, an extended context state with
+/// `PROOF_CONTEXT_STATE_FLAG_REFERENCE` set
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+#[repr(C)]
+pub struct ProofReference {
+    /// The header of the context state
+    pub meta: ExtendedProofContextStateMeta,
+    /// The SHA-256 hash of the referenced proof data
+    pub proof_hash: [u8; 32],
+}
+
+impl ProofReference {
+    pub fn encode(
+        context_state_authority: &Pubkey,
+        context_state_delegate: &Pubkey,
+        proof_type: ProofType,
+        proof_hash: [u8; 32],
+    ) -> Vec<u8> {
+        bytes_of(&Self {
+            meta: ExtendedProofContextStateMeta::new(
+                context_state_authority,
+                context_state_delegate,
+                proof_type,
+                PROOF_CONTEXT_STATE_FLAG_REFERENCE,
+            ),
+            proof_hash,
+        })
+        .to_vec()
+    }
+
+    pub fn try_from_bytes(input: &[u8]) -> Result<&Self, InstructionError> {
+        bytemuck::try_from_bytes::<Self>(input)
+            .ok()
+            .filter(|reference| {
+                reference.meta.is_valid()
+                    && reference.meta.flags & PROOF_CONTEXT_STATE_FLAG_REFERENCE != 0
+            })
+            .ok_or(InvalidAccountData)
+    }
+}
+
+/// Returns the hash that a context state records for the policy accounts `policy_accounts`, given
+/// in the order of the verify options that read them, the SHA-256 hash of their addresses.
+///
+/// The `RateLimit` verify option contributes its rate limit config account.
+pub fn context_state_policy_hash(policy_accounts: &[Pubkey]) -> [u8; 32] {
+    let input: Vec<&[u8]> = policy_accounts.iter().map(|key| key.as_ref()).collect();
+    hashv(&input).to_bytes()
+}
+
+/// Returns whether `authority` can close the context state account with data `account_data`.
+///
+/// Only the generic-independent fields of the context state are read. Malformed and
+/// uninitialized context states are not closeable.
+pub fn is_closeable_by(account_data: &[u8], authority: &Pubkey) -> bool {
+    ContextStateHeader::decode(account_data).map_or(false, |header| {
+        header.proof_type != ProofType::Uninitialized.into() && header.is_authorized(authority)
+    })
+}
+
+/// Returns the ElGamal pubkey of the account that the proof context of type `proof_type` is
+/// about.
+///
+/// This is the pubkey of the account itself for the close account, withdraw, pubkey validity, and
+/// key possession proofs, the source pubkey for the transfer proofs, the source pubkey of the
+/// first transfer for the transfer sum proof, and the withdraw withheld authority pubkey for the
+/// withdraw withheld tokens proof. Returns `InvalidAccountData` if `context_data` is not a proof
+/// context of the given type and `InvalidArgument` for proof types whose proof context does not
+/// have an ElGamal pubkey, such as the max value, parity, greater-than, and commitment equality
+/// proofs.
+pub fn extract_elgamal_pubkey(
+    proof_type: ProofType,
+    context_data: &[u8],
+) -> Result<ElGamalPubkey, InstructionError> {
+    fn context<T: Pod>(context_data: &[u8]) -> Result<&T, InstructionError> {
+        bytemuck::try_from_bytes(context_data).map_err(|_| InvalidAccountData)
+    }
+
+    match proof_type {
+        ProofType::Uninitialized
+        | ProofType::MaxValue
+        | ProofType::Parity
+        | ProofType::GreaterThan
+        | ProofType::CommitmentEquality => Err(InstructionError::InvalidArgument),
+        ProofType::CloseAccount => Ok(context::<CloseAccountProofContext>(context_data)?.pubkey),
+        ProofType::Withdraw => Ok(context::<WithdrawProofContext>(context_data)?.pubkey),
+        ProofType::WithdrawWithheldTokens => {
+            Ok(context::<WithdrawWithheldTokensProofContext>(context_data)?
+                .withdraw_withheld_authority_pubkey)
+        }
+        ProofType::Transfer => Ok(context::<TransferProofContext>(context_data)?
+            .transfer_pubkeys
+            .source_pubkey),
+        ProofType::TransferWithFee => Ok(context::<TransferWithFeeProofContext>(context_data)?
+            .transfer_with_fee_pubkeys
+            .source_pubkey),
+        ProofType::PubkeyValidity => {
+            Ok(context::<PubkeyValidityProofContext>(context_data)?.pubkey)
+        }
+        ProofType::TransferSum => Ok(context::<TransferSumProofContext>(context_data)?
+            .first_transfer
+            .transfer_pubkeys
+            .source_pubkey),
+        ProofType::KeyPossession => Ok(context::<KeyPossessionProofContext>(context_data)?.pubkey),
+    }
+}
+
+/// Returns all ElGamal pubkeys in the proof context of type `proof_type`.
+///
+/// Unlike `extract_elgamal_pubkey`, this includes the pubkeys of all parties to the proof, such as
+/// the destination and auditor pubkeys of the transfer proofs. The max value, parity,
+/// greater-than, and commitment equality proof contexts do not have any ElGamal pubkeys. Returns
+/// `InvalidAccountData` if `context_data` is not a proof context of the given type.
+pub fn extract_elgamal_pubkeys(
+    proof_type: ProofType,
+    context_data: &[u8],
+) -> Result<Vec<ElGamalPubkey>, InstructionError> {
+    fn context<T: Pod>(context_data: &[u8]) -> Result<&T, InstructionError> {
+        bytemuck::try_from_bytes(context_data).map_err(|_| InvalidAccountData)
+    }
+
+    match proof_type {
+        ProofType::Uninitialized => Err(InstructionError::InvalidArgument),
+        ProofType::MaxValue
+        | ProofType::Parity
+        | ProofType::GreaterThan
+        | ProofType::CommitmentEquality => Ok(vec![]),
+        ProofType::WithdrawWithheldTokens => {
+            let context = context::<WithdrawWithheldTokensProofContext>(context_data)?;
+            Ok(vec![
+                context.withdraw_withheld_authority_pubkey,
+                context.destination_pubkey,
+            ])
+        }
+        ProofType::Transfer => {
+            let pubkeys = &context::<TransferProofContext>(context_data)?.transfer_pubkeys;
+            Ok(vec![
+                pubkeys.source_pubkey,
+                pubkeys.destination_pubkey,
+                pubkeys.auditor_pubkey,
+            ])
+        }
+        ProofType::TransferWithFee => {
+            let pubkeys =
+                &context::<TransferWithFeeProofContext>(context_data)?.transfer_with_fee_pubkeys;
+            Ok(vec![
+                pubkeys.source_pubkey,
+                pubkeys.destination_pubkey,
+                pubkeys.auditor_pubkey,
+                pubkeys.withdraw_withheld_authority_pubkey,
+            ])
+        }
+        ProofType::TransferSum => {
+            let context = context::<TransferSumProofContext>(context_data)?;
+            Ok([context.first_transfer, context.second_transfer]
+                .iter()
+                .flat_map(|transfer| {
+                    let pubkeys = &transfer.transfer_pubkeys;
+                    [
+                        pubkeys.source_pubkey,
+                        pubkeys.destination_pubkey,
+                        pubkeys.auditor_pubkey,
+                    ]
+                })
+                .collect())
+        }
+        ProofType::CloseAccount
+        | ProofType::Withdraw
+        | ProofType::PubkeyValidity
+        | ProofType::KeyPossession => Ok(vec![extract_elgamal_pubkey(proof_type, context_data)?]),
+    }
+}
+
+/// The header at the start of a policy account, an account holding the configuration or state
+/// that a verify option reads, such as an `AuthorityAllowlist` or a `RateLimitState`.
+///
+/// Policy accounts are created by `InitializePolicyAccount` at the address returned by
+/// `find_policy_account_address` for the discriminator, admin, and id of the header, and only the
+/// admin can replace their body with `UpdatePolicyAccount`. The header is followed by the body of
+/// the account, whose encoding is given by the discriminator. The verify options reject a policy
+/// account at any address other than the one derived from its header.
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+#[repr(C)]
+pub struct PolicyAccountHeader {
+    /// The discriminator of the kind of the policy account, such as
+    /// `AUTHORITY_ALLOWLIST_DISCRIMINATOR`
+    pub discriminator: [u8; 8],
+    /// The admin that initialized the policy account and can update it
+    pub admin: Pubkey,
+    /// The id that tells apart the policy accounts of the same kind and admin
+    pub id: PodU64,
+    /// The bump seed of the address of the policy account
+    pub bump: u8,
+}
+
+impl PolicyAccountHeader {
+    pub fn try_from_bytes(input: &[u8]) -> Result<&Self, InstructionError> {
+        input
+            .get(..size_of::<PolicyAccountHeader>())
+            .and_then(|data| bytemuck::try_from_bytes(data).ok())
+            .ok_or(InvalidAccountData)
+    }
+
+    /// Split the data of a policy account into its header and body, checking that the account is
+    /// of the kind `discriminator`.
+    pub fn split<'a>(
+        input: &'a [u8],
+        discriminator: &[u8; 8],
+    ) -> Result<(&'a Self, &'a [u8]), InstructionError> {
+        let header = Self::try_from_bytes(input)?;
+        if header.discriminator != *discriminator {
+            return Err(InvalidAccountData);
+        }
+        Ok((header, &input[size_of::<Self>()..]))
+    }
+
+    /// Returns the address that `find_policy_account_address` derives for the header, which is the
+    /// only address that `InitializePolicyAccount` writes the header to.
+    pub fn address(&self) -> Result<Pubkey, InstructionError> {
+        Pubkey::create_program_address(
+            &[
+                &self.discriminator,
+                self.admin.as_ref(),
+                &u64::from(self.id).to_le_bytes(),
+                &[self.bump],
+            ],
+            &crate::zk_token_proof_program::id(),
+        )
+        .map_err(|_| InvalidAccountData)
+    }
+
+    /// Returns the body of the policy account `input` of the kind `discriminator`, for writing.
+    pub fn body_mut<'a>(
+        input: &'a mut [u8],
+        discriminator: &[u8; 8],
+    ) -> Result<&'a mut [u8], InstructionError> {
+        Self::split(input, discriminator)?;
+        Ok(&mut input[size_of::<Self>()..])
+    }
+}
+
+/// Returns the address and bump seed of the policy account of the kind `discriminator` that
+/// `admin` initializes with `id`.
+pub fn find_policy_account_address(
+    discriminator: &[u8; 8],
+    admin: &Pubkey,
+    id: u64,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[discriminator, admin.as_ref(), &id.to_le_bytes()],
+        &crate::zk_token_proof_program::id(),
+    )
+}
+
+/// Check that `body` is a well-formed body of a policy account of the kind `discriminator`.
+pub fn validate_policy_account_body(
+    discriminator: &[u8; 8],
+    body: &[u8],
+) -> Result<(), InstructionError> {
+    match *discriminator {
+        AUTHORITY_ALLOWLIST_DISCRIMINATOR => {
+            let authorities = AuthorityAllowlist::decode(body)?;
+            // members are found by binary search
+            if !authorities.windows(2).all(|pair| pair[0] < pair[1]) {
+                return Err(InvalidAccountData);
+            }
+        }
+        KEY_BLACKLIST_DISCRIMINATOR => {
+            let pubkeys = KeyBlacklist::decode(body)?;
+            if !pubkeys.windows(2).all(|pair| pair[0].0 < pair[1].0) {
+                return Err(InvalidAccountData);
+            }
+        }
+        SLOT_WINDOWS_DISCRIMINATOR => {
+            SlotWindows::decode(body)?;
+        }
+        RATE_LIMIT_CONFIG_DISCRIMINATOR => {
+            RateLimitConfig::try_from_bytes(body)?;
+        }
+        RATE_LIMIT_STATE_DISCRIMINATOR => {
+            bytemuck::try_from_bytes::<RateLimitState>(body).map_err(|_| InvalidAccountData)?;
+        }
+        SECURITY_LEVEL_CONFIG_DISCRIMINATOR => {
+            SecurityLevelConfig::try_from_bytes(body)?;
+        }
+        USAGE_COUNTER_DISCRIMINATOR => {
+            bytemuck::try_from_bytes::<UsageCounter>(body).map_err(|_| InvalidAccountData)?;
+        }
+        NULLIFIER_SET_DISCRIMINATOR => {
+            NullifierSet::decode(body)?;
+        }
+        RETRY_NONCE_TRACKER_DISCRIMINATOR => {
+            RetryNonceTracker::try_from_bytes(body)?;
+        }
+        _ => return Err(InvalidAccountData),
+    }
+    Ok(())
+}
+
+/// The discriminator of an authority allowlist account
+pub const AUTHORITY_ALLOWLIST_DISCRIMINATOR: [u8; 8] = *b"ctxallow";
+
+/// The set of context state authorities that an authority allowlist account admits.
+///
+/// The body of an authority allowlist account consists of the authorities in ascending order.
+pub struct AuthorityAllowlist;
+
+impl AuthorityAllowlist {
+    pub fn encode(authorities: &[Pubkey]) -> Vec<u8> {
+        let mut authorities = authorities.to_vec();
+        authorities.sort_unstable();
+        authorities.dedup();
+        bytemuck::cast_slice(&authorities).to_vec()
+    }
+
+    fn decode(body: &[u8]) -> Result<&[Pubkey], InstructionError> {
+        bytemuck::try_cast_slice(body).map_err(|_| InvalidAccountData)
+    }
+
+    /// Returns whether the allowlist with the body `body` contains `authority`.
+    pub fn contains(body: &[u8], authority: &Pubkey) -> Result<bool, InstructionError> {
+        Ok(Self::decode(body)?.binary_search(authority).is_ok())
+    }
+}
+
+/// The discriminator of a key blacklist account
+pub const KEY_BLACKLIST_DISCRIMINATOR: [u8; 8] = *b"keyblkls";
+
+/// The set of ElGamal pubkeys that a key blacklist account rejects.
+///
+/// The body of a key blacklist account consists of the pubkeys in ascending order of their bytes.
+pub struct KeyBlacklist;
+
+impl KeyBlacklist {
+    pub fn encode(pubkeys: &[ElGamalPubkey]) -> Vec<u8> {
+        let mut pubkeys = pubkeys.to_vec();
+        pubkeys.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        pubkeys.dedup();
+        bytemuck::cast_slice(&pubkeys).to_vec()
+    }
+
+    fn decode(body: &[u8]) -> Result<&[ElGamalPubkey], InstructionError> {
+        bytemuck::try_cast_slice(body).map_err(|_| InvalidAccountData)
+    }
+
+    /// Returns whether the blacklist with the body `body` contains `pubkey`.
+    pub fn contains(body: &[u8], pubkey: &ElGamalPubkey) -> Result<bool, InstructionError> {
+        Ok(Self::decode(body)?
+            .binary_search_by(|member| member.0.cmp(&pubkey.0))
+            .is_ok())
+    }
+}
+
+/// The discriminator of a slot windows account
+pub const SLOT_WINDOWS_DISCRIMINATOR: [u8; 8] = *b"slotwndw";
+
+/// The slot windows within which a slot windows account admits proofs.
+///
+/// The body of a slot windows account consists of the windows, each encoded as its inclusive
+/// start slot and exclusive end slot.
+pub struct SlotWindows;
+
+impl SlotWindows {
+    pub fn encode(windows: &[(u64, u64)]) -> Vec<u8> {
+        let windows: Vec<[PodU64; 2]> = windows
+            .iter()
+            .map(|(start_slot, end_slot)| [(*start_slot).into(), (*end_slot).into()])
+            .collect();
+        bytemuck::cast_slice(&windows).to_vec()
+    }
+
+    fn decode(body: &[u8]) -> Result<&[[PodU64; 2]], InstructionError> {
+        bytemuck::try_cast_slice(body).map_err(|_| InvalidAccountData)
+    }
+
+    /// Returns whether `slot` falls within any of the windows with the body `body`.
+    pub fn contains(body: &[u8], slot: u64) -> Result<bool, InstructionError> {
+        Ok(Self::decode(body)?.iter().any(|[start_slot, end_slot]| {
+            u64::from(*start_slot) <= slot && slot < u64::from(*end_slot)
+        }))
+    }
+}
+
+/// The discriminator of a rate limit config account
+pub const RATE_LIMIT_CONFIG_DISCRIMINATOR: [u8; 8] = *b"rlconfig";
+
+/// The discriminator of a rate limit state account
+pub const RATE_LIMIT_STATE_DISCRIMINATOR: [u8; 8] = *b"rlstate\0";
+
+/// The body of a rate limit config account
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+#[repr(C)]
+pub struct RateLimitConfig {
+    /// The number of slots in a rate limit window
+    pub window_slots: PodU64,
+    /// The number of proof context states that an authority can create in a window
+    pub limit: PodU64,
+}
+
+impl RateLimitConfig {
+    pub fn new(window_slots: u64, limit: u64) -> Self {
+        Self {
+            window_slots: window_slots.into(),
+            limit: limit.into(),
+        }
+    }
+
+    pub fn try_from_bytes(input: &[u8]) -> Result<&Self, InstructionError> {
+        bytemuck::try_from_bytes::<Self>(input).map_err(|_| InvalidAccountData)
+    }
+}
+
+/// The body of the rate limit state account of a context state authority under a rate limit
+/// config, which must be initialized by the admin of the config
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+#[repr(C)]
+pub struct RateLimitState {
+    /// The rate limit config account that the state counts against
+    pub config: Pubkey,
+    /// The proof context authority whose context state creations are counted
+    pub context_state_authority: Pubkey,
+    /// The first slot of the current window
+    pub window_start_slot: PodU64,
+    /// The number of proof context states created in the current window
+    pub count: PodU64,
+}
+
+impl RateLimitState {
+    pub fn new(config: &Pubkey, context_state_authority: &Pubkey) -> Self {
+        Self {
+            config: *config,
+            context_state_authority: *context_state_authority,
+            window_start_slot: 0_u64.into(),
+            count: 0_u64.into(),
+        }
+    }
+
+    pub fn try_from_bytes_mut(input: &mut [u8]) -> Result<&mut Self, InstructionError> {
+        bytemuck::try_from_bytes_mut::<Self>(input).map_err(|_| InvalidAccountData)
+    }
+
+    /// Count a context state creation at `slot`, starting a new window if the current one has
+    /// elapsed. Returns false without counting if the limit of the window is reached.
+    pub fn try_count(&mut self, config: &RateLimitConfig, slot: u64) -> bool {
+        let window_end_slot =
+            u64::from(self.window_start_slot).saturating_add(config.window_slots.into());
+        if slot >= window_end_slot {
+            self.window_start_slot = slot.into();
+            self.count = 0_u64.into();
+        }
+
+        let count = u64::from(self.count);
+        if count >= config.limit.into() {
+            return false;
+        }
+        self.count = (count + 1).into();
+        true
+    }
+}
+
+/// The security level of proofs whose amounts are range proven in chunks of fewer than 32 bits,
+/// which are `Transfer` and `TransferWithFee` proofs with their 16-bit low amount chunks
+pub const SECURITY_LEVEL_CHUNKED_RANGE: u8 = 1;
+
+/// The security level of all other proofs
+pub const SECURITY_LEVEL_FULL_RANGE: u8 = 2;
+
+/// Returns the security level of the parameters of a proof of type `proof_type`, which is
+/// `SECURITY_LEVEL_CHUNKED_RANGE` or `SECURITY_LEVEL_FULL_RANGE`, or `0` for
+/// `ProofType::Uninitialized`.
+///
+/// A `SecurityLevelConfig` with a minimum security level of `SECURITY_LEVEL_FULL_RANGE` phases out
+/// the chunked range proofs.
+pub fn security_level(proof_type: ProofType) -> u8 {
+    match proof_type {
+        ProofType::Uninitialized => 0,
+        ProofType::Transfer | ProofType::TransferWithFee => SECURITY_LEVEL_CHUNKED_RANGE,
+        ProofType::CloseAccount
+        | ProofType::Withdraw
+        | ProofType::WithdrawWithheldTokens
+        | ProofType::PubkeyValidity
+        | ProofType::TransferSum
+        | ProofType::MaxValue
+        | ProofType::Parity
+        | ProofType::GreaterThan
+        | ProofType::KeyPossession
+        | ProofType::CommitmentEquality => SECURITY_LEVEL_FULL_RANGE,
+    }
+}
+
+/// The discriminator of a security level config account
+pub const SECURITY_LEVEL_CONFIG_DISCRIMINATOR: [u8; 8] = *b"seclevel";
+
+/// The body of a security level config account, read by the `MinSecurityLevel` verify option
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+#[repr(C)]
+pub struct SecurityLevelConfig {
+    /// The minimum security level, as returned by `security_level`, of the proofs to accept
+    pub minimum_security_level: u8,
+}
+
+impl SecurityLevelConfig {
+    pub fn new(minimum_security_level: u8) -> Self {
+        Self {
+            minimum_security_level,
+        }
+    }
+
+    pub fn try_from_bytes(input: &[u8]) -> Result<&Self, InstructionError> {
+        bytemuck::try_from_bytes::<Self>(input).map_err(|_| InvalidAccountData)
+    }
+}
+
+/// The discriminator at the start of a proof commitment account
+pub const PROOF_COMMITMENT_DISCRIMINATOR: [u8; 8] = *b"proofcmt";
+
+/// The proof commitment account state written by `CommitProof`
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+#[repr(C)]
+pub struct ProofCommitment {
+    /// Always `PROOF_COMMITMENT_DISCRIMINATOR`
+    pub discriminator: [u8; 8],
+    /// The committer that must sign the instruction revealing the proof
+    pub committer: Pubkey,
+    /// The SHA-256 hash of the committed proof data
+    pub proof_hash: [u8; 32],
+}
+
+impl ProofCommitment {
+    pub fn new(committer: &Pubkey, proof_hash: [u8; 32]) -> Self {
+        Self {
+            discriminator: PROOF_COMMITMENT_DISCRIMINATOR,
+            committer: *committer,
+            proof_hash,
+        }
+    }
+
+    /// The hash of `proof_data` that a commitment to it records.
+    pub fn hash<T: Pod>(proof_data: &T) -> [u8; 32] {
+        hash(bytes_of(proof_data)).to_bytes()
+    }
+
+    pub fn try_from_bytes(input: &[u8]) -> Result<&Self, InstructionError> {
+        bytemuck::try_from_bytes::<Self>(input)
+            .ok()
+            .filter(|commitment| commitment.discriminator == PROOF_COMMITMENT_DISCRIMINATOR)
+            .ok_or(InvalidAccountData)
+    }
+}
+
+/// The discriminator of a usage counter account
+pub const USAGE_COUNTER_DISCRIMINATOR: [u8; 8] = *b"usagecnt";
+
+/// The body of a usage counter account, incremented by the `UsageCounter` verify option
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+#[repr(C)]
+pub struct UsageCounter {
+    /// The number of proofs verified against the counter
+    pub count: PodU64,
+}
+
+impl UsageCounter {
+    pub fn new(count: u64) -> Self {
+        Self {
+            count: count.into(),
+        }
+    }
+
+    pub fn try_from_bytes_mut(input: &mut [u8]) -> Result<&mut Self, InstructionError> {
+        bytemuck::try_from_bytes_mut::<Self>(input).map_err(|_| InvalidAccountData)
+    }
+
+    /// Increment the count, returning false without changing it if the count would overflow.
+    pub fn try_increment(&mut self) -> bool {
+        match u64::from(self.count).checked_add(1) {
+            Some(count) => {
+                self.count = count.into();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// The discriminator of a nullifier set account
+pub const NULLIFIER_SET_DISCRIMINATOR: [u8; 8] = *b"nullfset";
+
+/// The nullifiers of the proofs verified against a nullifier set account.
+///
+/// The body of a nullifier set account consists of the number of recorded nullifiers as a
+/// little-endian `u64` and a fixed number of 32-byte entries, of which the first are the recorded
+/// nullifiers. The capacity of the set is the number of entries that the body holds.
+pub struct NullifierSet;
+
+impl NullifierSet {
+    /// Returns the body of an empty nullifier set holding up to `capacity` nullifiers.
+    pub fn encode(capacity: usize) -> Vec<u8> {
+        vec![0; size_of::<u64>() + capacity * 32]
+    }
+
+    /// The nullifier of a proof of `proof_type` with the `Pod` bytes `proof_context`, the SHA-256
+    /// hash of the proof type and the proof context.
+    pub fn nullifier(proof_type: ProofType, proof_context: &[u8]) -> [u8; 32] {
+        hashv(&[&[proof_type as u8], proof_context]).to_bytes()
+    }
+
+    fn decode(body: &[u8]) -> Result<(usize, &[[u8; 32]]), InstructionError> {
+        let (count, entries) = body.split_at(size_of::<u64>().min(body.len()));
+        let count = u64::from_le_bytes(count.try_into().map_err(|_| InvalidAccountData)?);
+        let entries: &[[u8; 32]] =
+            bytemuck::try_cast_slice(entries).map_err(|_| InvalidAccountData)?;
+        let count = usize::try_from(count)
+            .ok()
+            .filter(|count| *count <= entries.len())
+            .ok_or(InvalidAccountData)?;
+        Ok((count, entries))
+    }
+
+    /// Returns whether the nullifier set with the body `body` contains `nullifier`.
+    pub fn contains(body: &[u8], nullifier: &[u8; 32]) -> Result<bool, InstructionError> {
+        let (count, entries) = Self::decode(body)?;
+        Ok(entries[..count].contains(nullifier))
+    }
+
+    /// Record `nullifier` in the nullifier set with the body `body`, returning false without
+    /// recording it if the set already contains it. Errors with `AccountDataTooSmall` if the set
+    /// is full.
+    pub fn try_insert(body: &mut [u8], nullifier: &[u8; 32]) -> Result<bool, InstructionError> {
+        let (count, entries) = Self::decode(body)?;
+        if entries[..count].contains(nullifier) {
+            return Ok(false);
+        }
+        if count == entries.len() {
+            return Err(InstructionError::AccountDataTooSmall);
+        }
+
+        let entry_offset = size_of::<u64>() + count * 32;
+        body[entry_offset..entry_offset + 32].copy_from_slice(nullifier);
+        body[..size_of::<u64>()].copy_from_slice(&(count as u64 + 1).to_le_bytes());
+        Ok(true)
+    }
+}
+
+/// The discriminator of a retry nonce tracker account
+pub const RETRY_NONCE_TRACKER_DISCRIMINATOR: [u8; 8] = *b"retrynce";
+
+/// The number of nonces that a retry nonce tracker remembers
+pub const RETRY_NONCE_TRACKER_CAPACITY: usize = 32;
+
+/// The body of a retry nonce tracker account, recording the nonces processed by the `RetryNonce`
+/// verify option. The admin of the tracker must sign the instructions recording nonces in it.
+///
+/// The tracker remembers the last `RETRY_NONCE_TRACKER_CAPACITY` recorded nonces in a ring buffer,
+/// so a nonce is only recognized as processed until that many newer nonces have been recorded.
+/// Each nonce is recorded with the hash of the instruction it was processed with, as returned by
+/// `retry_nonce_instruction_hash`, so that a replay can be told apart from a reuse of the nonce.
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+#[repr(C)]
+pub struct RetryNonceTracker {
+    /// The number of nonces ever recorded in the tracker
+    pub count: PodU64,
+    /// The recorded nonces, of which the first `count` are set until the tracker wraps around
+    pub nonces: [PodU64; RETRY_NONCE_TRACKER_CAPACITY],
+    /// The hashes of the instructions that the recorded nonces were processed with, in the order
+    /// of `nonces`
+    pub instruction_hashes: [[u8; 32]; RETRY_NONCE_TRACKER_CAPACITY],
+}
+
+impl RetryNonceTracker {
+    pub fn new() -> Self {
+        Self {
+            count: 0_u64.into(),
+            nonces: [0_u64.into(); RETRY_NONCE_TRACKER_CAPACITY],
+            instruction_hashes: [[0; 32]; RETRY_NONCE_TRACKER_CAPACITY],
+        }
+    }
+
+    pub fn try_from_bytes(input: &[u8]) -> Result<&Self, InstructionError> {
+        bytemuck::try_from_bytes::<Self>(input).map_err(|_| InvalidAccountData)
+    }
+
+    pub fn try_from_bytes_mut(input: &mut [u8]) -> Result<&mut Self, InstructionError> {
+        bytemuck::try_from_bytes_mut::<Self>(input).map_err(|_| InvalidAccountData)
+    }
+
+    /// Returns the instruction hash recorded with `nonce` if it is among the remembered nonces of
+    /// the tracker.
+    pub fn instruction_hash(&self, nonce: u64) -> Option<&[u8; 32]> {
+        let remembered = u64::from(self.count).min(RETRY_NONCE_TRACKER_CAPACITY as u64) as usize;
+        self.nonces[..remembered]
+            .iter()
+            .position(|recorded| u64::from(*recorded) == nonce)
+            .map(|index| &self.instruction_hashes[index])
+    }
+
+    /// Returns whether `nonce` is among the remembered nonces of the tracker.
+    pub fn contains(&self, nonce: u64) -> bool {
+        self.instruction_hash(nonce).is_some()
+    }
+
+    /// Record `nonce` with `instruction_hash`, returning false without recording it if the tracker
+    /// already contains it. Once the tracker is full, the oldest nonce is forgotten.
+    pub fn try_record(&mut self, nonce: u64, instruction_hash: [u8; 32]) -> bool {
+        if self.contains(nonce) {
+            return false;
+        }
+
+        let count = u64::from(self.count);
+        let slot = (count % RETRY_NONCE_TRACKER_CAPACITY as u64) as usize;
+        self.nonces[slot] = nonce.into();
+        self.instruction_hashes[slot] = instruction_hash;
+        self.count = count.wrapping_add(1).into();
+        true
+    }
+}
+
+impl Default for RetryNonceTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns the hash that the `RetryNonce` verify option records a nonce with for an instruction
+/// with the full `instruction_data` and the keys of its accounts `account_keys`, in order.
+pub fn retry_nonce_instruction_hash(instruction_data: &[u8], account_keys: &[Pubkey]) -> [u8; 32] {
+    let data_length = (instruction_data.len() as u64).to_le_bytes();
+    let mut input: Vec<&[u8]> = vec![&data_length, instruction_data];
+    input.extend(account_keys.iter().map(|key| key.as_ref()));
+    hashv(&input).to_bytes()
+}
+
+/// The receipt that the `Receipt` verify option returns for a proof of `proof_type` with the
+/// `Pod` bytes `proof_context` verified in `slot`.
+///
+/// The receipt is the SHA-256 hash of the proof type, the SHA-256 hash of the proof context, and
+/// the little-endian slot.
+pub fn verification_receipt(proof_type: ProofType, proof_context: &[u8], slot: u64) -> [u8; 32] {
+    hashv(&[
+        &[proof_type as u8],
+        hash(proof_context).as_ref(),
+        &slot.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// The seed prefix of the address of a program-managed proof context state
+pub const PROGRAM_MANAGED_CONTEXT_SEED: &[u8] = b"program_managed_context";
+
+/// Returns the address and bump seed of the proof context state that the `ProgramManaged` verify
+/// option creates for `controlling_program_id` with the client-supplied `id`.
+///
+/// The address is derived from the proof program id, so the proof program can sign for it, and
+/// the seeds include the controlling program id, so programs cannot create each other's context
+/// states.
+pub fn find_program_managed_context_address(
+    controlling_program_id: &Pubkey,
+    id: u64,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            PROGRAM_MANAGED_CONTEXT_SEED,
+            controlling_program_id.as_ref(),
+            &id.to_le_bytes(),
+        ],
+        &crate::zk_token_proof_program::id(),
+    )
+}
+
+/// Return the raw proof context stored in a context state account of either layout.
+pub fn decode_proof_context_data(input: &[u8]) -> Result<Cow<[u8]>, InstructionError> {
+    let header = ContextStateHeader::decode(input)?;
+    if !header.is_complete() {
+        return Err(InvalidAccountData);
+    }
+    decode_encoded_proof_context(input, &header)
+}
+
+/// Return the raw proof context of a context state with header `header`, which may be incomplete.
+fn decode_encoded_proof_context<'a>(
+    input: &'a [u8],
+    header: &ContextStateHeader,
+) -> Result<Cow<'a, [u8]>, InstructionError> {
+    let proof_context = &input[header.meta_length()..];
+    if header.flags & PROOF_CONTEXT_STATE_FLAG_REFERENCE != 0 {
+        return Err(InvalidAccountData);
+    }
+    if header.flags & PROOF_CONTEXT_STATE_FLAG_COMPRESSED != 0 {
+        decompress(proof_context)
+            .map(Cow::Owned)
+            .ok_or(InvalidAccountData)
+    } else {
+        Ok(Cow::Borrowed(proof_context))
+    }
+}
+
+/// A context state of either layout and any proof type, with the proof context as its raw `Pod`
+/// bytes
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodedContextState {
+    /// The proof context authority that can close the account
+    pub context_state_authority: Pubkey,
+    /// The delegate that can also close the account, or `Pubkey::default()` if there is none
+    pub context_state_delegate: Pubkey,
+    /// The proof type for the context data
+    pub proof_type: ProofType,
+    /// The layout version of the context state
+    pub version: u8,
+    /// The `PROOF_CONTEXT_STATE_FLAG_*` flags of the context state
+    pub flags: u8,
+    /// The raw proof context data
+    pub proof_context: Vec<u8>,
+}
+
+impl DecodedContextState {
+    /// Decode a context state of either layout, decompressing the proof context if needed.
+    pub fn decode(input: &[u8]) -> Result<Self, InstructionError> {
+        let proof_context = decode_proof_context_data(input)?.into_owned();
+        let header = ContextStateHeader::decode(input)?;
+        Ok(Self {
+            context_state_authority: header.context_state_authority,
+            context_state_delegate: header.context_state_delegate,
+            proof_type: header.proof_type.try_into()?,
+            version: header.version,
+            flags: header.flags,
+            proof_context,
+        })
+    }
+}
+
+/// A field in which a context state differs from the expected context state
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FieldDiff {
+    /// The context state could not be decoded
+    Malformed(InstructionError),
+    ContextStateAuthority {
+        expected: Pubkey,
+        actual: Pubkey,
+    },
+    ContextStateDelegate {
+        expected: Pubkey,
+        actual: Pubkey,
+    },
+    ProofType {
+        expected: ProofType,
+        actual: ProofType,
+    },
+    Version {
+        expected: u8,
+        actual: u8,
+    },
+    Flags {
+        expected: u8,
+        actual: u8,
+    },
+    ProofContext {
+        expected: Vec<u8>,
+        actual: Vec<u8>,
+    },
+}
+
+/// Returns the fields in which the context state account data `actual_data` differs from
+/// `expected`, in declaration order, or a single `FieldDiff::Malformed` if the data cannot be
+/// decoded.
+pub fn diff_context_state(expected: &DecodedContextState, actual_data: &[u8]) -> Vec<FieldDiff> {
+    let actual = match DecodedContextState::decode(actual_data) {
+        Ok(actual) => actual,
+        Err(err) => return vec![FieldDiff::Malformed(err)],
+    };
+
+    let mut diffs = vec![];
+    if expected.context_state_authority != actual.context_state_authority {
+        diffs.push(FieldDiff::ContextStateAuthority {
+            expected: expected.context_state_authority,
+            actual: actual.context_state_authority,
+        });
+    }
+    if expected.context_state_delegate != actual.context_state_delegate {
+        diffs.push(FieldDiff::ContextStateDelegate {
+            expected: expected.context_state_delegate,
+            actual: actual.context_state_delegate,
+        });
+    }
+    if expected.proof_type != actual.proof_type {
+        diffs.push(FieldDiff::ProofType {
+            expected: expected.proof_type,
+            actual: actual.proof_type,
+        });
+    }
+    if expected.version != actual.version {
+        diffs.push(FieldDiff::Version {
+            expected: expected.version,
+            actual: actual.version,
+        });
+    }
+    if expected.flags != actual.flags {
+        diffs.push(FieldDiff::Flags {
+            expected: expected.flags,
+            actual: actual.flags,
+        });
+    }
+    if expected.proof_context != actual.proof_context {
+        diffs.push(FieldDiff::ProofContext {
+            expected: expected.proof_context.clone(),
+            actual: actual.proof_context,
+        });
+    }
+    diffs
+}
+
+/// Returns the layout version that a context state of layout version `from` is migrated to when
+/// `to` is requested, or `None` if the migration is not supported.
+///
+/// A context state can be migrated from the default layout to the extended layout, or stay in its
+/// layout to change the encoding of its proof context, but can never be migrated to an older or
+/// unknown layout version.
+pub fn migrated_layout_version(from: u8, to: u8) -> Option<u8> {
+    match (from, to) {
+        (PROOF_CONTEXT_STATE_VERSION_DEFAULT, PROOF_CONTEXT_STATE_VERSION_DEFAULT)
+        | (PROOF_CONTEXT_STATE_VERSION_DEFAULT, PROOF_CONTEXT_STATE_VERSION_EXTENDED)
+        | (PROOF_CONTEXT_STATE_VERSION_EXTENDED, PROOF_CONTEXT_STATE_VERSION_EXTENDED) => Some(to),
+        _ => None,
+    }
+}
+
+/// Re-encode a context state account of either layout in the layout of `version`, with the proof
+/// context stored compressed if `compressed` is set.
+///
+/// The layout versions must form a migration supported by `migrated_layout_version`, and the
+/// default layout has no room for the compression flag. The delegate, the incomplete flag, and the
+/// enforced policies of the context state carry over, and the context state must actually change. A reference has no
+/// proof context to re-encode and cannot be migrated.
+pub fn migrate_context_state_data(
+    input: &[u8],
+    version: u8,
+    compressed: bool,
+) -> Result<Vec<u8>, InstructionError> {
+    let header = ContextStateHeader::decode(input)?;
+    let version = migrated_layout_version(header.version, version)
+        .ok_or(InstructionError::InvalidArgument)?;
+    if version == PROOF_CONTEXT_STATE_VERSION_DEFAULT && compressed {
+        return Err(InstructionError::InvalidArgument);
+    }
+
+    let mut flags = header.flags & PROOF_CONTEXT_STATE_FLAG_INCOMPLETE;
+    if compressed {
+        flags |= PROOF_CONTEXT_STATE_FLAG_COMPRESSED;
+    }
+    if version == header.version && flags == header.flags {
+        return Err(InstructionError::InvalidArgument);
+    }
+
+    // a migration to the default layout is always a no-op, so the target is the extended layout
+    let proof_context = decode_encoded_proof_context(input, &header)?;
+    let proof_type = ProofType::try_from(header.proof_type)?;
+    let mut meta = ExtendedProofContextStateMeta::new(
+        &header.context_state_authority,
+        &header.context_state_delegate,
+        proof_type,
+        flags,
+    );
+    meta.policies = header.policies;
+    meta.policy_hash = header.policy_hash;
+    let mut buf = meta.to_vec();
+    if compressed {
+        buf.extend_from_slice(&compress(&proof_context));
+    } else {
+        buf.extend_from_slice(&proof_context);
+    }
+    Ok(buf)
+}
+
+fn compress(data: &[u8]) -> Vec<u8> {
+    let number_of_chunks = (data.len() + COMPRESSION_CHUNK_SIZE - 1) / COMPRESSION_CHUNK_SIZE;
+    let mut bitmap = vec![0_u8; (number_of_chunks + 7) / 8];
+    let mut chunks = Vec::with_capacity(data.len());
+    for (i, chunk) in data.chunks(COMPRESSION_CHUNK_SIZE).enumerate() {
+        if chunk.iter().all(|byte| *byte == 0) {
+            bitmap[i / 8] |= 1 << (i % 8);
+        } else {
+            chunks.extend_from_slice(chunk);
+        }
+    }
+
+    let mut buf = Vec::with_capacity(2 + bitmap.len() + chunks.len());
+    buf.extend_from_slice(&(data.len() as u16).to_le_bytes());
+    buf.extend_from_slice(&bitmap);
+    buf.extend_from_slice(&chunks);
+    buf
+}
+
+fn decompress(input: &[u8]) -> Option<Vec<u8>> {
+    let len = u16::from_le_bytes(input.get(..2)?.try_into().ok()?) as usize;
+    let number_of_chunks = (len + COMPRESSION_CHUNK_SIZE - 1) / COMPRESSION_CHUNK_SIZE;
+    let bitmap = input.get(2..2 + (number_of_chunks + 7) / 8)?;
+    let mut chunks = &input[2 + bitmap.len()..];
+
+    let mut data = Vec::with_capacity(len);
+    for i in 0..number_of_chunks {
+        let chunk_len = COMPRESSION_CHUNK_SIZE.min(len - i * COMPRESSION_CHUNK_SIZE);
+        if bitmap[i / 8] & (1 << (i % 8)) != 0 {
+            data.resize(data.len() + chunk_len, 0);
+        } else {
+            let (chunk, rest) = (chunks.get(..chunk_len)?, &chunks[chunk_len..]);
+            data.extend_from_slice(chunk);
+            chunks = rest;
+        }
+    }
+
+    // reject trailing bytes so that every context has a single compressed encoding
+    chunks.is_empty().then_some(data)
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, crate::zk_token_elgamal::pod};
+
+    #[test]
+    fn test_compressed_context_state() {
+        let context_state_authority = Pubkey::new_unique();
+        let proof_context = CloseAccountProofContext {
+            pubkey: pod::ElGamalPubkey([1; 32]),
+            ciphertext: pod::ElGamalCiphertext([0; 64]),
+        };
+
+        let raw = ProofContextState::encode(
+            &context_state_authority,
+            ProofType::CloseAccount,
+            &proof_context,
+        );
+        let compressed = ProofContextState::encode_compressed(
+            &context_state_authority,
+            &Pubkey::default(),
+            ProofType::CloseAccount,
+            &proof_context,
+        );
+        assert!(compressed.len() < raw.len());
+
+        let decoded = ProofContextState::<CloseAccountProofContext>::decode(&compressed).unwrap();
+        assert_eq!(decoded.context_state_authority, context_state_authority);
+        assert_eq!(bytes_of(&decoded.proof_context), bytes_of(&proof_context));
+        let header = ContextStateHeader::decode(&compressed).unwrap();
+        assert_eq!(header.version, PROOF_CONTEXT_STATE_VERSION_EXTENDED);
+        assert_eq!(header.flags, PROOF_CONTEXT_STATE_FLAG_COMPRESSED);
+        assert_eq!(
+            *decode_proof_context_data(&compressed).unwrap(),
+            *decode_proof_context_data(&raw).unwrap()
+        );
+
+        // truncated or padded compressed data is rejected
+        assert!(ProofContextState::<CloseAccountProofContext>::decode(
+            &compressed[..compressed.len() - 1]
+        )
+        .is_err());
+        let mut padded = compressed;
+        padded.push(0);
+        assert!(ProofContextState::<CloseAccountProofContext>::decode(&padded).is_err());
+    }
+
+    #[test]
+    fn test_diff_context_state() {
+        let context_state_authority = Pubkey::new_unique();
+        let context_state_delegate = Pubkey::new_unique();
+        let proof_context = CloseAccountProofContext {
+            pubkey: pod::ElGamalPubkey([1; 32]),
+            ciphertext: pod::ElGamalCiphertext([0; 64]),
+        };
+        let expected = DecodedContextState {
+            context_state_authority,
+            context_state_delegate,
+            proof_type: ProofType::CloseAccount,
+            version: PROOF_CONTEXT_STATE_VERSION_EXTENDED,
+            flags: 0,
+            proof_context: bytes_of(&proof_context).to_vec(),
+        };
+
+        // an exact match
+        let data = ProofContextState::encode_with_delegate(
+            &context_state_authority,
+            &context_state_delegate,
+            ProofType::CloseAccount,
+            &proof_context,
+        );
+        assert_eq!(DecodedContextState::decode(&data).unwrap(), expected);
+        assert!(diff_context_state(&expected, &data).is_empty());
+
+        // a mismatch in each field
+        let other_authority = Pubkey::new_unique();
+        let data = ProofContextState::encode_with_delegate(
+            &other_authority,
+            &context_state_delegate,
+            ProofType::CloseAccount,
+            &proof_context,
+        );
+        assert_eq!(
+            diff_context_state(&expected, &data),
+            vec![FieldDiff::ContextStateAuthority {
+                expected: context_state_authority,
+                actual: other_authority,
+            }]
+        );
+
+        let data = ProofContextState::encode_with_delegate(
+            &context_state_authority,
+            &Pubkey::default(),
+            ProofType::CloseAccount,
+            &proof_context,
+        );
+        assert_eq!(
+            diff_context_state(&expected, &data),
+            vec![FieldDiff::ContextStateDelegate {
+                expected: context_state_delegate,
+                actual: Pubkey::default(),
+            }]
+        );
+
+        let data = ProofContextState::encode_with_delegate(
+            &context_state_authority,
+            &context_state_delegate,
+            ProofType::PubkeyValidity,
+            &proof_context,
+        );
+        assert_eq!(
+            diff_context_state(&expected, &data),
+            vec![FieldDiff::ProofType {
+                expected: ProofType::CloseAccount,
+                actual: ProofType::PubkeyValidity,
+            }]
+        );
+
+        let data = ProofContextState::encode_compressed(
+            &context_state_authority,
+            &context_state_delegate,
+            ProofType::CloseAccount,
+            &proof_context,
+        );
+        assert_eq!(
+            diff_context_state(&expected, &data),
+            vec![FieldDiff::Flags {
+                expected: 0,
+                actual: PROOF_CONTEXT_STATE_FLAG_COMPRESSED,
+            }]
+        );
+
+        let data = ProofContextState::encode(
+            &context_state_authority,
+            ProofType::CloseAccount,
+            &proof_context,
+        );
+        assert_eq!(
+            diff_context_state(&expected, &data),
+            vec![
+                FieldDiff::ContextStateDelegate {
+                    expected: context_state_delegate,
+                    actual: Pubkey::default(),
+                },
+                FieldDiff::Version {
+                    expected: PROOF_CONTEXT_STATE_VERSION_EXTENDED,
+                    actual: PROOF_CONTEXT_STATE_VERSION_DEFAULT,
+                },
+            ]
+        );
+
+        let other_proof_context = CloseAccountProofContext {
+            pubkey: pod::ElGamalPubkey([2; 32]),
+            ciphertext: pod::ElGamalCiphertext([0; 64]),
+        };
+        let data = ProofContextState::encode_with_delegate(
+            &context_state_authority,
+            &context_state_delegate,
+            ProofType::CloseAccount,
+            &other_proof_context,
+        );
+        assert_eq!(
+            diff_context_state(&expected, &data),
+            vec![FieldDiff::ProofContext {
+                expected: bytes_of(&proof_context).to_vec(),
+                actual: bytes_of(&other_proof_context).to_vec(),
+            }]
+        );
+
+        // data that is not a context state
+        assert_eq!(
+            diff_context_state(&expected, &[0; 10]),
+            vec![FieldDiff::Malformed(InvalidAccountData)]
+        );
+    }
+
+    #[test]
+    fn test_migrate_context_state_data() {
+        let context_state_authority = Pubkey::new_unique();
+        let proof_context = CloseAccountProofContext {
+            pubkey: pod::ElGamalPubkey([1; 32]),
+            ciphertext: pod::ElGamalCiphertext([0; 64]),
+        };
+        let data = ProofContextState::encode(
+            &context_state_authority,
+            ProofType::CloseAccount,
+            &proof_context,
+        );
+
+        // the default layout is migrated to the extended layout, with or without compression
+        for compressed in [false, true] {
+            let migrated =
+                migrate_context_state_data(&data, PROOF_CONTEXT_STATE_VERSION_EXTENDED, compressed)
+                    .unwrap();
+            let decoded = DecodedContextState::decode(&migrated).unwrap();
+            assert_eq!(decoded.context_state_authority, context_state_authority);
+            assert_eq!(decoded.context_state_delegate, Pubkey::default());
+            assert_eq!(decoded.version, PROOF_CONTEXT_STATE_VERSION_EXTENDED);
+            assert_eq!(decoded.flags != 0, compressed);
+            assert_eq!(decoded.proof_context, bytes_of(&proof_context));
+        }
+
+        // the default layout has no compression, and unknown versions and no-ops are rejected
+        for (version, compressed) in [
+            (PROOF_CONTEXT_STATE_VERSION_DEFAULT, true),
+            (PROOF_CONTEXT_STATE_VERSION_DEFAULT, false),
+            (PROOF_CONTEXT_STATE_VERSION_EXTENDED + 1, false),
+        ] {
+            assert_eq!(
+                migrate_context_state_data(&data, version, compressed),
+                Err(InstructionError::InvalidArgument)
+            );
+        }
+
+        // an extended context state keeps its delegate, incomplete flag, and enforced policies,
+        // and is never migrated back to the default layout
+        let context_state_delegate = Pubkey::new_unique();
+        let policy_hash = context_state_policy_hash(&[Pubkey::new_unique()]);
+        let mut partial = ProofContextState::encode_with_delegate(
+            &context_state_authority,
+            &context_state_delegate,
+            ProofType::CloseAccount,
+            &proof_context,
+        );
+        let meta = ExtendedProofContextStateMeta::try_from_bytes_mut(&mut partial).unwrap();
+        meta.flags |= PROOF_CONTEXT_STATE_FLAG_INCOMPLETE;
+        meta.policies = PROOF_CONTEXT_STATE_POLICY_KEY_BLACKLIST;
+        meta.policy_hash = policy_hash;
+        let migrated =
+            migrate_context_state_data(&partial, PROOF_CONTEXT_STATE_VERSION_EXTENDED, true)
+                .unwrap();
+        let header = ContextStateHeader::decode(&migrated).unwrap();
+        assert_eq!(header.context_state_delegate, context_state_delegate);
+        assert_eq!(
+            header.flags,
+            PROOF_CONTEXT_STATE_FLAG_COMPRESSED | PROOF_CONTEXT_STATE_FLAG_INCOMPLETE
+        );
+        assert_eq!(header.policies, PROOF_CONTEXT_STATE_POLICY_KEY_BLACKLIST);
+        assert_eq!(header.policy_hash, policy_hash);
+        assert_eq!(
+            migrate_context_state_data(&migrated, PROOF_CONTEXT_STATE_VERSION_DEFAULT, false),
+            Err(InstructionError::InvalidArgument)
+        );
+
+        // a reference cannot be migrated
+        let reference = ProofReference::encode(
+            &context_state_authority,
+            &Pubkey::default(),
+            ProofType::CloseAccount,
+            [3; 32],
+        );
+        assert!(
+            migrate_context_state_data(&reference, PROOF_CONTEXT_STATE_VERSION_EXTENDED, true)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_is_closeable_by() {
+        let context_state_authority = Pubkey::new_unique();
+        let proof_context = CloseAccountProofContext {
+            pubkey: pod::ElGamalPubkey([1; 32]),
+            ciphertext: pod::ElGamalCiphertext([2; 64]),
+        };
+        let data = ProofContextState::encode(
+            &context_state_authority,
+            ProofType::CloseAccount,
+            &proof_context,
+        );
+
+        assert!(is_closeable_by(&data, &context_state_authority));
+        assert!(!is_closeable_by(&data, &Pubkey::new_unique()));
+
+        let context_state_delegate = Pubkey::new_unique();
+        let extended = ProofContextState::encode_with_delegate(
+            &context_state_authority,
+            &context_state_delegate,
+            ProofType::CloseAccount,
+            &proof_context,
+        );
+        assert!(is_closeable_by(&extended, &context_state_authority));
+        assert!(is_closeable_by(&extended, &context_state_delegate));
+        assert!(!is_closeable_by(&extended, &Pubkey::new_unique()));
+
+        // truncated, uninitialized, and unknown version data
+        assert!(!is_closeable_by(&data[..20], &context_state_authority));
+        assert!(!is_closeable_by(&extended[..40], &context_state_authority));
+        assert!(!is_closeable_by(&vec![0; data.len()], &Pubkey::default()));
+        let mut unknown_version = extended;
+        unknown_version[34] = PROOF_CONTEXT_STATE_VERSION_EXTENDED + 1;
+        assert!(!is_closeable_by(&unknown_version, &context_state_authority));
+    }
+
+    #[test]
+    fn test_default_layout_is_not_read_as_extended_layout() {
+        let context_state_authority = Pubkey::new_unique();
+        let attacker = Pubkey::new_unique();
+
+        // a context state in the default layout whose proof context bytes are where the version,
+        // flags, and delegate of the extended layout would be
+        let mut data = ProofContextState::encode(
+            &context_state_authority,
+            ProofType::CloseAccount,
+            &CloseAccountProofContext {
+                pubkey: pod::ElGamalPubkey([1; 32]),
+                ciphertext: pod::ElGamalCiphertext([2; 64]),
+            },
+        );
+        data[34] = PROOF_CONTEXT_STATE_VERSION_EXTENDED;
+        data[35] = 0;
+        data[36..68].copy_from_slice(attacker.as_ref());
+
+        let header = ContextStateHeader::decode(&data).unwrap();
+        assert_eq!(header.version, PROOF_CONTEXT_STATE_VERSION_DEFAULT);
+        assert_eq!(header.context_state_delegate, Pubkey::default());
+        assert_eq!(header.proof_type, ProofType::CloseAccount.into());
+        assert!(ExtendedProofContextStateMeta::try_from_bytes(&data).is_err());
+
+        assert!(is_closeable_by(&data, &context_state_authority));
+        assert!(!is_closeable_by(&data, &attacker));
+
+        // the proof context is read back unchanged from the default layout
+        assert_eq!(
+            *decode_proof_context_data(&data).unwrap(),
+            data[size_of::<ProofContextStateMeta>()..]
+        );
+    }
+
+    #[test]
+    fn test_proof_reference() {
+        let context_state_authority = Pubkey::new_unique();
+        let data = ProofReference::encode(
+            &context_state_authority,
+            &Pubkey::default(),
+            ProofType::CloseAccount,
+            [3; 32],
+        );
+        assert_eq!(data.len(), size_of::<ProofReference>());
+
+        let reference = ProofReference::try_from_bytes(&data).unwrap();
+        assert_eq!(reference.meta.proof_type, ProofType::CloseAccount.into());
+        assert_eq!(reference.proof_hash, [3; 32]);
+        assert!(is_closeable_by(&data, &context_state_authority));
+
+        // a reference cannot be read as a proof context
+        assert_eq!(decode_proof_context_data(&data), Err(InvalidAccountData));
+
+        // an extended context state without the reference flag is not a reference
+        let mut other_flags = data;
+        other_flags[35] = 0;
+        assert!(ProofReference::try_from_bytes(&other_flags).is_err());
+    }
+
+    #[test]
+    fn test_policy_account_header() {
+        let admin = Pubkey::new_unique();
+        let (address, bump) = find_policy_account_address(&SLOT_WINDOWS_DISCRIMINATOR, &admin, 1);
+        assert_ne!(
+            address,
+            find_policy_account_address(&SLOT_WINDOWS_DISCRIMINATOR, &admin, 2).0
+        );
+        assert_ne!(
+            address,
+            find_policy_account_address(&KEY_BLACKLIST_DISCRIMINATOR, &admin, 1).0
+        );
+
+        let header = PolicyAccountHeader {
+            discriminator: SLOT_WINDOWS_DISCRIMINATOR,
+            admin,
+            id: 1_u64.into(),
+            bump,
+        };
+        let mut data = bytes_of(&header).to_vec();
+        data.extend_from_slice(&SlotWindows::encode(&[(10, 20)]));
+
+        let (decoded_header, body) =
+            PolicyAccountHeader::split(&data, &SLOT_WINDOWS_DISCRIMINATOR).unwrap();
+        assert_eq!(decoded_header, &header);
+        assert_eq!(decoded_header.address().unwrap(), address);
+        assert!(SlotWindows::contains(body, 10).unwrap());
+
+        // the header must be of the expected kind
+        assert_eq!(
+            PolicyAccountHeader::split(&data, &KEY_BLACKLIST_DISCRIMINATOR),
+            Err(InvalidAccountData)
+        );
+        assert_eq!(
+            PolicyAccountHeader::body_mut(&mut data[..8], &SLOT_WINDOWS_DISCRIMINATOR),
+            Err(InvalidAccountData)
+        );
+
+        // a body is validated by the kind of the account
+        assert!(validate_policy_account_body(&SLOT_WINDOWS_DISCRIMINATOR, body).is_ok());
+        assert_eq!(
+            validate_policy_account_body(&SLOT_WINDOWS_DISCRIMINATOR, &body[1..]),
+            Err(InvalidAccountData)
+        );
+        assert_eq!(
+            validate_policy_account_body(b"unknown\0", body),
+            Err(InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn test_authority_allowlist() {
+        let members: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).rev().collect();
+        let allowlist = AuthorityAllowlist::encode(&members);
+
+        for member in &members {
+            assert!(AuthorityAllowlist::contains(&allowlist, member).unwrap());
+        }
+        assert!(!AuthorityAllowlist::contains(&allowlist, &Pubkey::new_unique()).unwrap());
+
+        // a body of a partial pubkey is rejected
+        assert!(
+            AuthorityAllowlist::contains(&allowlist[..allowlist.len() - 1], &members[0]).is_err()
+        );
+
+        // only a sorted allowlist is a valid body
+        assert!(
+            validate_policy_account_body(&AUTHORITY_ALLOWLIST_DISCRIMINATOR, &allowlist).is_ok()
+        );
+        let unsorted: Vec<u8> = bytemuck::cast_slice(&members).to_vec();
+        assert_eq!(
+            validate_policy_account_body(&AUTHORITY_ALLOWLIST_DISCRIMINATOR, &unsorted),
+            Err(InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn test_key_blacklist() {
+        let members: Vec<ElGamalPubkey> = (0..5_u8).rev().map(|i| ElGamalPubkey([i; 32])).collect();
+        let blacklist = KeyBlacklist::encode(&members);
+
+        for member in &members {
+            assert!(KeyBlacklist::contains(&blacklist, member).unwrap());
+        }
+        assert!(!KeyBlacklist::contains(&blacklist, &ElGamalPubkey([5; 32])).unwrap());
+
+        // a body of a partial pubkey is rejected
+        assert!(KeyBlacklist::contains(&blacklist[..blacklist.len() - 1], &members[0]).is_err());
+
+        // only a sorted blacklist is a valid body
+        assert!(validate_policy_account_body(&KEY_BLACKLIST_DISCRIMINATOR, &blacklist).is_ok());
+        let unsorted: Vec<u8> = bytemuck::cast_slice(&members).to_vec();
+        assert_eq!(
+            validate_policy_account_body(&KEY_BLACKLIST_DISCRIMINATOR, &unsorted),
+            Err(InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn test_slot_windows() {
+        let windows = SlotWindows::encode(&[(10, 20), (30, 31)]);
+
+        assert!(SlotWindows::contains(&windows, 10).unwrap());
+        assert!(SlotWindows::contains(&windows, 19).unwrap());
+        assert!(SlotWindows::contains(&windows, 30).unwrap());
+        assert!(!SlotWindows::contains(&windows, 9).unwrap());
+        assert!(!SlotWindows::contains(&windows, 20).unwrap());
+        assert!(!SlotWindows::contains(&windows, 31).unwrap());
+
+        // no windows admit no slots
+        assert!(!SlotWindows::contains(&SlotWindows::encode(&[]), 10).unwrap());
+
+        assert_eq!(
+            SlotWindows::contains(&windows[..windows.len() - 1], 10),
+            Err(InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn test_nullifier_set() {
+        let first_nullifier = NullifierSet::nullifier(ProofType::Transfer, &[1, 2, 3]);
+        let second_nullifier = NullifierSet::nullifier(ProofType::TransferWithFee, &[1, 2, 3]);
+        assert_ne!(first_nullifier, second_nullifier);
+
+        let mut nullifier_set = NullifierSet::encode(2);
+        assert!(!NullifierSet::contains(&nullifier_set, &first_nullifier).unwrap());
+
+        assert!(NullifierSet::try_insert(&mut nullifier_set, &first_nullifier).unwrap());
+        assert!(NullifierSet::contains(&nullifier_set, &first_nullifier).unwrap());
+        assert!(!NullifierSet::contains(&nullifier_set, &second_nullifier).unwrap());
+
+        // a recorded nullifier is not recorded again
+        assert!(!NullifierSet::try_insert(&mut nullifier_set, &first_nullifier).unwrap());
+
+        assert!(NullifierSet::try_insert(&mut nullifier_set, &second_nullifier).unwrap());
+        assert!(NullifierSet::contains(&nullifier_set, &second_nullifier).unwrap());
+
+        // a full set rejects new nullifiers
+        let third_nullifier = NullifierSet::nullifier(ProofType::Transfer, &[1, 2, 4]);
+        assert_eq!(
+            NullifierSet::try_insert(&mut nullifier_set, &third_nullifier),
+            Err(InstructionError::AccountDataTooSmall)
+        );
+
+        // a set with a partial entry or a count over its capacity is malformed
+        assert_eq!(
+            NullifierSet::contains(&nullifier_set[..nullifier_set.len() - 1], &first_nullifier),
+            Err(InvalidAccountData)
+        );
+        let mut nullifier_set = NullifierSet::encode(1);
+        nullifier_set[0] = 2;
+        assert_eq!(
+            NullifierSet::contains(&nullifier_set, &first_nullifier),
+            Err(InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn test_retry_nonce_tracker() {
+        let mut tracker = RetryNonceTracker::new();
+        // an unset entry is not mistaken for a recorded zero nonce
+        assert!(!tracker.contains(0));
+
+        assert!(tracker.try_record(0, [1; 32]));
+        assert!(tracker.contains(0));
+        assert_eq!(tracker.instruction_hash(0), Some(&[1; 32]));
+        assert!(!tracker.try_record(0, [2; 32]));
+        assert_eq!(tracker.instruction_hash(0), Some(&[1; 32]));
+        assert_eq!(u64::from(tracker.count), 1);
+
+        // a full tracker forgets its oldest nonce
+        for nonce in 1..RETRY_NONCE_TRACKER_CAPACITY as u64 {
+            assert!(tracker.try_record(nonce, [nonce as u8; 32]));
+        }
+        assert!(tracker.contains(0));
+        assert!(tracker.try_record(RETRY_NONCE_TRACKER_CAPACITY as u64, [0; 32]));
+        assert!(!tracker.contains(0));
+        assert_eq!(tracker.instruction_hash(1), Some(&[1; 32]));
+        assert_eq!(
+            tracker.instruction_hash(RETRY_NONCE_TRACKER_CAPACITY as u64),
+            Some(&[0; 32])
+        );
+
+        let mut data = bytes_of(&tracker).to_vec();
+        assert_eq!(
+            RetryNonceTracker::try_from_bytes_mut(&mut data).unwrap(),
+            &tracker
+        );
+        assert_eq!(
+            RetryNonceTracker::try_from_bytes(&data[1..]),
+            Err(InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn test_retry_nonce_instruction_hash() {
+        let keys = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let hash = retry_nonce_instruction_hash(&[1, 2, 3], &keys);
+        assert_eq!(hash, retry_nonce_instruction_hash(&[1, 2, 3], &keys));
+
+        assert_ne!(hash, retry_nonce_instruction_hash(&[1, 2, 4], &keys));
+        assert_ne!(hash, retry_nonce_instruction_hash(&[1, 2, 3], &keys[..1]));
+        assert_ne!(
+            hash,
+            retry_nonce_instruction_hash(&[1, 2, 3], &[keys[1], keys[0]])
+        );
+        // the instruction data cannot absorb the key of an account
+        let mut extended_data = vec![1, 2, 3];
+        extended_data.extend_from_slice(keys[0].as_ref());
+        assert_ne!(
+            hash,
+            retry_nonce_instruction_hash(&extended_data, &keys[1..])
+        );
+    }
+
+    #[test]
+    fn test_verification_receipt() {
+        let receipt = verification_receipt(ProofType::Transfer, &[1, 2, 3], 42);
+        assert_eq!(
+            receipt,
+            verification_receipt(ProofType::Transfer, &[1, 2, 3], 42)
+        );
+
+        assert_ne!(
+            receipt,
+            verification_receipt(ProofType::TransferWithFee, &[1, 2, 3], 42)
+        );
+        assert_ne!(
+            receipt,
+            verification_receipt(ProofType::Transfer, &[1, 2, 4], 42)
+        );
+        assert_ne!(
+            receipt,
+            verification_receipt(ProofType::Transfer, &[1, 2, 3], 43)
+        );
+    }
+
+    #[test]
+    fn test_find_program_managed_context_address() {
+        let program_id = Pubkey::new_unique();
+        let (address, _) = find_program_managed_context_address(&program_id, 1);
+        assert_eq!(
+            address,
+            find_program_managed_context_address(&program_id, 1).0
+        );
+
+        assert_ne!(
+            address,
+            find_program_managed_context_address(&program_id, 2).0
+        );
+        assert_ne!(
+            address,
+            find_program_managed_context_address(&Pubkey::new_unique(), 1).0
+        );
+    }
+
+    #[test]
+    fn test_extract_elgamal_pubkey() {
+        let pubkey = pod::ElGamalPubkey([7; 32]);
+
+        let mut close_account = CloseAccountProofContext::zeroed();
+        close_account.pubkey = pubkey;
+        let mut withdraw = WithdrawProofContext::zeroed();
+        withdraw.pubkey = pubkey;
+        let mut withdraw_withheld = WithdrawWithheldTokensProofContext::zeroed();
+        withdraw_withheld.withdraw_withheld_authority_pubkey = pubkey;
+        let mut transfer = TransferProofContext::zeroed();
+        transfer.transfer_pubkeys.source_pubkey = pubkey;
+        let mut transfer_with_fee = TransferWithFeeProofContext::zeroed();
+        transfer_with_fee.transfer_with_fee_pubkeys.source_pubkey = pubkey;
+        let mut pubkey_validity = PubkeyValidityProofContext::zeroed();
+        pubkey_validity.pubkey = pubkey;
+        let mut transfer_sum = TransferSumProofContext::zeroed();
+        transfer_sum.first_transfer.transfer_pubkeys.source_pubkey = pubkey;
+        let mut key_possession = KeyPossessionProofContext::zeroed();
+        key_possession.pubkey = pubkey;
+
+        let contexts: [(ProofType, &[u8]); 8] = [
+            (ProofType::CloseAccount, bytes_of(&close_account)),
+            (ProofType::Withdraw, bytes_of(&withdraw)),
+            (
+                ProofType::WithdrawWithheldTokens,
+                bytes_of(&withdraw_withheld),
+            ),
+            (ProofType::Transfer, bytes_of(&transfer)),
+            (ProofType::TransferWithFee, bytes_of(&transfer_with_fee)),
+            (ProofType::PubkeyValidity, bytes_of(&pubkey_validity)),
+            (ProofType::TransferSum, bytes_of(&transfer_sum)),
+            (ProofType::KeyPossession, bytes_of(&key_possession)),
+        ];
+        for (proof_type, context_data) in contexts {
+            assert_eq!(
+                extract_elgamal_pubkey(proof_type, context_data).unwrap(),
+                pubkey
+            );
+            // the context data must be of the given proof type
+            assert_eq!(
+                extract_elgamal_pubkey(proof_type, &context_data[1..]),
+                Err(InvalidAccountData)
+            );
+        }
+
+        assert_eq!(
+            extract_elgamal_pubkey(ProofType::Uninitialized, &[]),
+            Err(InstructionError::InvalidArgument)
+        );
+        assert_eq!(
+            extract_elgamal_pubkey(
+                ProofType::MaxValue,
+                bytes_of(&MaxValueProofContext::zeroed())
+            ),
+            Err(InstructionError::InvalidArgument)
+        );
+        assert_eq!(
+            extract_elgamal_pubkey(ProofType::Parity, bytes_of(&ParityProofContext::zeroed())),
+            Err(InstructionError::InvalidArgument)
+        );
+        assert_eq!(
+            extract_elgamal_pubkey(
+                ProofType::GreaterThan,
+                bytes_of(&GreaterThanProofContext::zeroed())
+            ),
+            Err(InstructionError::InvalidArgument)
+        );
+        assert_eq!(
+            extract_elgamal_pubkey(
+                ProofType::CommitmentEquality,
+                bytes_of(&CommitmentEqualityProofContext::zeroed())
+            ),
+            Err(InstructionError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn test_extract_elgamal_pubkeys() {
+        let mut transfer = TransferProofContext::zeroed();
+        transfer.transfer_pubkeys.source_pubkey = ElGamalPubkey([1; 32]);
+        transfer.transfer_pubkeys.destination_pubkey = ElGamalPubkey([2; 32]);
+        transfer.transfer_pubkeys.auditor_pubkey = ElGamalPubkey([3; 32]);
+        assert_eq!(
+            extract_elgamal_pubkeys(ProofType::Transfer, bytes_of(&transfer)).unwrap(),
+            vec![
+                ElGamalPubkey([1; 32]),
+                ElGamalPubkey([2; 32]),
+                ElGamalPubkey([3; 32])
+            ]
+        );
+
+        let mut transfer_sum = TransferSumProofContext::zeroed();
+        transfer_sum.second_transfer = transfer;
+        let pubkeys =
+            extract_elgamal_pubkeys(ProofType::TransferSum, bytes_of(&transfer_sum)).unwrap();
+        assert_eq!(pubkeys.len(), 6);
+        assert_eq!(
+            pubkeys[3..],
+            extract_elgamal_pubkeys(ProofType::Transfer, bytes_of(&transfer)).unwrap()
+        );
+
+        assert!(extract_elgamal_pubkeys(
+            ProofType::MaxValue,
+            bytes_of(&MaxValueProofContext::zeroed())
+        )
+        .unwrap()
+        .is_empty());
+        assert_eq!(
+            extract_elgamal_pubkeys(ProofType::Transfer, &[0; 3]),
+            Err(InvalidAccountData)
+        );
+    }
+}